@@ -273,6 +273,111 @@
               mv reaper_test $out/
             '';
           };
+          test-cargo-reaper-new-template-git = pkgs.stdenv.mkDerivation {
+            name = "test-cargo-reaper-new-template-git";
+            buildInputs = [
+              self.packages.${system}.default
+              pkgs.git
+            ];
+            doCheck = true;
+            phases = [
+              "buildPhase"
+              "checkPhase"
+              "installPhase"
+            ];
+            buildPhase = ''
+              mkdir -p template/src
+              cp ${./templates/extension/cargo.toml} template/Cargo.toml
+              cp ${./templates/extension/reaper.toml} template/reaper.toml
+              echo 'reaper_template = "./."' >> template/reaper.toml
+              touch template/src/lib.rs template/LICENSE
+              git -C template init --quiet --initial-branch main
+              git -C template add --all
+              git -C template -c user.name=test -c user.email=test@example.com commit --quiet --message template
+              cargo-reaper new --template-git "file://$PWD/template" --branch main reaper_test
+            '';
+            checkPhase = ''
+              test -f reaper_test/LICENSE
+              test ! -e reaper_test/.git/refs/heads/main
+              grep -q '^name = "reaper_test"$' reaper_test/Cargo.toml
+              grep -q '^reaper_test = "./."$' reaper_test/reaper.toml
+              ! grep -q reaper_template reaper_test/reaper.toml
+            '';
+            installPhase = ''
+              mkdir -p $out
+              mv reaper_test $out/
+            '';
+          };
+          test-cargo-reaper-new-template-path = pkgs.stdenv.mkDerivation {
+            name = "test-cargo-reaper-new-template-path";
+            buildInputs = [
+              self.packages.${system}.default
+            ];
+            doCheck = true;
+            phases = [
+              "buildPhase"
+              "checkPhase"
+              "installPhase"
+            ];
+            buildPhase = ''
+              mkdir -p template/src template/target/debug template/.git shared
+              cp ${./templates/extension/cargo.toml} template/Cargo.toml
+              cp ${./templates/extension/reaper.toml} template/reaper.toml
+              touch template/src/lib.rs template/target/debug/artifact template/.git/HEAD
+              echo license > shared/LICENSE
+              ln -s ../shared/LICENSE template/LICENSE
+              cargo-reaper new --template-path template --vcs none reaper_test
+            '';
+            checkPhase = ''
+              test -f reaper_test/LICENSE
+              test ! -L reaper_test/LICENSE
+              test ! -e reaper_test/target
+              test ! -e reaper_test/.git
+              grep -q '^name = "reaper_test"$' reaper_test/Cargo.toml
+              grep -q '^reaper_test = "./."$' reaper_test/reaper.toml
+            '';
+            installPhase = ''
+              mkdir -p $out
+              mv reaper_test $out/
+            '';
+          };
+          test-cargo-reaper-new-workspace = pkgs.stdenv.mkDerivation {
+            name = "test-cargo-reaper-new-workspace";
+            buildInputs = [
+              self.packages.${system}.default
+            ];
+            doCheck = true;
+            phases = [
+              "buildPhase"
+              "checkPhase"
+              "installPhase"
+            ];
+            buildPhase = ''
+              mkdir workspace
+              cd workspace
+              cat > Cargo.toml <<EOF
+              [workspace]
+              resolver = "3"
+              members = []
+
+              [workspace.dependencies]
+              reaper-low = { git = "https://github.com/helgoboss/reaper-rs.git", branch = "master" }
+              EOF
+              cargo-reaper new crates/reaper_test
+              cd ..
+            '';
+            checkPhase = ''
+              grep -q '"crates/reaper_test"' workspace/Cargo.toml
+              grep -q '^reaper_test = "crates/reaper_test"$' workspace/reaper.toml
+              grep -q '^reaper-low = { workspace = true }$' workspace/crates/reaper_test/Cargo.toml
+              test ! -e workspace/crates/reaper_test/reaper.toml
+              test ! -e workspace/crates/reaper_test/.git
+            '';
+            installPhase = ''
+              mkdir -p $out
+              mv workspace $out/
+            '';
+          };
           test-cargo-reaper-list-package-manifest = pkgs.stdenv.mkDerivation {
             name = "test-cargo-reaper-list-package-manifest";
             src = testFileset ./tests/plugin_manifests/package_manifest;
@@ -291,6 +396,44 @@
               mkdir -p $out
             '';
           };
+          test-cargo-reaper-list-recursive = pkgs.stdenv.mkDerivation {
+            name = "test-cargo-reaper-list-recursive";
+            src = ./tests/project_discovery;
+            buildInputs = [
+              self.packages.${system}.default
+              pkgs.git
+            ];
+            doCheck = true;
+            phases = [
+              "unpackPhase"
+              "buildPhase"
+              "checkPhase"
+              "installPhase"
+            ];
+            buildPhase = ''
+              # Projects ignored by git are skipped, as are directories that can't be read.
+              mv gitignore .gitignore
+              git init --quiet
+              mkdir unreadable
+              chmod 000 unreadable
+              cargo-reaper list --recursive > list.txt 2> list.err
+              cargo-reaper list --quiet --recursive > keys.txt
+              chmod 755 unreadable
+            '';
+            checkPhase = ''
+              cat list.txt list.err
+              printf 'reaper_alpha\nreaper_beta\nreaper_root\n' | diff - keys.txt
+              grep -q '^Available Plugins (reaper.toml):$' list.txt
+              grep -q '^Available Plugins (plugins/alpha/reaper.toml):$' list.txt
+              grep -q '^Available Plugins (plugins/nested/beta/reaper.toml):$' list.txt
+              ! grep -Eq 'reaper_(stray|hidden|cached|node|generated)' list.txt
+              grep -q "skipping '.*unreadable'" list.err
+            '';
+            installPhase = ''
+              mkdir -p $out
+              cp list.txt keys.txt $out/
+            '';
+          };
           test-cargo-reaper-list-workspace-manifest = pkgs.stdenv.mkDerivation {
             name = "test-cargo-reaper-list-workspace-manifest";
             src = testFileset ./tests/plugin_manifests/workspace_manifest;
@@ -327,6 +470,145 @@
               mkdir -p $out
             '';
           };
+          # Invalid manifests must be reported as diagnostics (exit code 1), rather than panicking.
+          test-cargo-reaper-list-dotted-lib-manifest = pkgs.stdenv.mkDerivation {
+            name = "test-cargo-reaper-list-dotted-lib-manifest";
+            src = testFileset ./tests/plugin_manifests/dotted_lib_manifest;
+            buildInputs = [
+              self.packages.${system}.default
+            ];
+            phases = [
+              "unpackPhase"
+              "buildPhase"
+              "installPhase"
+            ];
+            buildPhase = ''
+              cargo-reaper list && exit 1 || test $? -eq 1
+            '';
+            installPhase = ''
+              mkdir -p $out
+            '';
+          };
+          test-cargo-reaper-list-missing-lib-manifest = pkgs.stdenv.mkDerivation {
+            name = "test-cargo-reaper-list-missing-lib-manifest";
+            src = testFileset ./tests/plugin_manifests/missing_lib_manifest;
+            buildInputs = [
+              self.packages.${system}.default
+            ];
+            phases = [
+              "unpackPhase"
+              "buildPhase"
+              "installPhase"
+            ];
+            buildPhase = ''
+              cargo-reaper list && exit 1 || test $? -eq 1
+            '';
+            installPhase = ''
+              mkdir -p $out
+            '';
+          };
+          # A plugin pointing at a virtual workspace is cleaned through the member owning its library.
+          test-cargo-reaper-clean-virtual-workspace-manifest = pkgs.stdenv.mkDerivation {
+            name = "test-cargo-reaper-clean-virtual-workspace-manifest";
+            src = testFileset ./tests/plugin_manifests/virtual_workspace_manifest;
+            buildInputs = [
+              self.packages.${system}.default
+              pkgs.cargo
+            ];
+            phases = [
+              "unpackPhase"
+              "buildPhase"
+              "installPhase"
+            ];
+            buildPhase = ''
+              export HOME=$TMPDIR
+              cargo-reaper clean --remove-artifacts --dry-run --format json | grep -q '"package":"virtual_extension"'
+            '';
+            installPhase = ''
+              mkdir -p $out
+            '';
+          };
+          test-cargo-reaper-run-exit-code = pkgs.stdenv.mkDerivation {
+            name = "test-cargo-reaper-run-exit-code";
+            src = testFileset ./tests/plugin_manifests/package_manifest;
+            buildInputs = [
+              self.packages.${system}.default
+            ];
+            phases = [
+              "unpackPhase"
+              "buildPhase"
+              "installPhase"
+            ];
+            # A fake REAPER executable that exits with `$REAPER_EXIT_CODE` after `$REAPER_SLEEP`.
+            buildPhase = ''
+              cat > reaper <<'EOF'
+              #!${pkgs.runtimeShell}
+              sleep ''${REAPER_SLEEP:-0}
+              exit $REAPER_EXIT_CODE
+              EOF
+              chmod +x reaper
+
+              for code in 0 1 3 42; do
+                REAPER_EXIT_CODE=$code cargo-reaper run --no-build --exec ./reaper && status=0 || status=$?
+                test $status -eq $code
+              done
+
+              export REAPER_EXIT_CODE=0 REAPER_SLEEP=30
+              cargo-reaper run --no-build --exec ./reaper --timeout 1s && status=0 || status=$?
+              test $status -eq 124
+              cargo-reaper run --no-build --exec ./reaper --timeout 1s --timeout-ok
+            '';
+            installPhase = ''
+              mkdir -p $out
+            '';
+          };
+          test-cargo-reaper-test = pkgs.stdenv.mkDerivation {
+            name = "test-cargo-reaper-test";
+            src = testFileset ./tests/plugin_manifests/package_manifest;
+            buildInputs = [
+              self.packages.${system}.default
+            ] ++ lib.optionals pkgs.stdenv.isLinux [
+              # Test cases run headless on Linux
+              pkgs.xorg.xorgserver
+            ];
+            phases = [
+              "unpackPhase"
+              "buildPhase"
+              "installPhase"
+            ];
+            # A fake REAPER executable that exits with `$REAPER_EXIT_CODE` after `$REAPER_SLEEP`.
+            buildPhase = ''
+              cat > reaper <<'EOF'
+              #!${pkgs.runtimeShell}
+              sleep ''${REAPER_SLEEP:-0}
+              exit $REAPER_EXIT_CODE
+              EOF
+              chmod +x reaper
+
+              cat >> reaper.toml <<'EOF'
+
+              [[test]]
+              name = "first"
+              timeout = "1s"
+
+              [[test]]
+              name = "second"
+              timeout = "1s"
+              EOF
+
+              export REAPER_EXIT_CODE=0 REAPER_SLEEP=30
+              cargo-reaper test --no-build --exec ./reaper
+              cargo-reaper test second --no-build --exec ./reaper
+              cargo-reaper test missing --no-build --exec ./reaper && exit 1 || test $? -eq 1
+
+              export REAPER_EXIT_CODE=3 REAPER_SLEEP=0
+              cargo-reaper test --no-build --exec ./reaper && status=0 || status=$?
+              test $status -eq 1
+            '';
+            installPhase = ''
+              mkdir -p $out
+            '';
+          };
         } // lib.optionalAttrs pkgs.stdenv.isLinux {
           test-cargo-reaper-link =
             let
@@ -386,6 +668,118 @@
                 plugin_name = "reaper_package_ext";
               };
             };
+          test-cargo-reaper-link-plugin =
+            let
+              tests = import ./tests {
+                inherit pkgs;
+                inherit (self.packages.${system}) cargo-reaper;
+                inherit (scripts) mkCargoReaperDryRun;
+              };
+            in
+            pkgs.testers.nixosTest {
+              name = "test-cargo-reaper-link-plugin";
+              inherit (tests) nodes;
+              testScript = tests.test-cargo-reaper-link-plugin {
+                plugin = test-cargo-reaper-build-package-manifest;
+                plugin_source = testFileset ./tests/plugin_manifests/package_manifest;
+                plugin_name = "reaper_package_ext";
+              };
+            };
+          test-cargo-reaper-link-relative =
+            let
+              tests = import ./tests {
+                inherit pkgs;
+                inherit (self.packages.${system}) cargo-reaper;
+                inherit (scripts) mkCargoReaperDryRun;
+              };
+            in
+            pkgs.testers.nixosTest {
+              name = "test-cargo-reaper-link-relative";
+              inherit (tests) nodes;
+              testScript = tests.test-cargo-reaper-link-relative {
+                plugin = test-cargo-reaper-build-package-manifest;
+                plugin_name = "reaper_package_ext";
+              };
+            };
+          test-cargo-reaper-link-force =
+            let
+              tests = import ./tests {
+                inherit pkgs;
+                inherit (self.packages.${system}) cargo-reaper;
+                inherit (scripts) mkCargoReaperDryRun;
+              };
+            in
+            pkgs.testers.nixosTest {
+              name = "test-cargo-reaper-link-force";
+              inherit (tests) nodes;
+              testScript = tests.test-cargo-reaper-link-force {
+                plugin = test-cargo-reaper-build-package-manifest;
+                plugin_name = "reaper_package_ext";
+              };
+            };
+          test-cargo-reaper-link-directory =
+            let
+              tests = import ./tests {
+                inherit pkgs;
+                inherit (self.packages.${system}) cargo-reaper;
+                inherit (scripts) mkCargoReaperDryRun;
+              };
+            in
+            pkgs.testers.nixosTest {
+              name = "test-cargo-reaper-link-directory";
+              inherit (tests) nodes;
+              testScript = tests.test-cargo-reaper-link-directory {
+                plugin = test-cargo-reaper-build-package-manifest;
+                plugin_name = "reaper_package_ext";
+              };
+            };
+          test-cargo-reaper-link-validation =
+            let
+              tests = import ./tests {
+                inherit pkgs;
+                inherit (self.packages.${system}) cargo-reaper;
+                inherit (scripts) mkCargoReaperDryRun;
+              };
+            in
+            pkgs.testers.nixosTest {
+              name = "test-cargo-reaper-link-validation";
+              inherit (tests) nodes;
+              testScript = tests.test-cargo-reaper-link-validation;
+            };
+          test-cargo-reaper-link-all =
+            let
+              tests = import ./tests {
+                inherit pkgs;
+                inherit (self.packages.${system}) cargo-reaper;
+                inherit (scripts) mkCargoReaperDryRun;
+              };
+            in
+            pkgs.testers.nixosTest {
+              name = "test-cargo-reaper-link-all";
+              inherit (tests) nodes;
+              testScript = tests.test-cargo-reaper-link-all {
+                plugin = test-cargo-reaper-build-package-manifest;
+                plugin_source = testFileset ./tests/plugin_manifests/package_manifest;
+                plugin_name = "reaper_package_ext";
+              };
+            };
+          test-cargo-reaper-unlink =
+            let
+              tests = import ./tests {
+                inherit pkgs;
+                inherit (self.packages.${system}) cargo-reaper;
+                inherit (scripts) mkCargoReaperDryRun;
+              };
+            in
+            pkgs.testers.nixosTest {
+              name = "test-cargo-reaper-unlink";
+              inherit (tests) nodes;
+              testScript = tests.test-cargo-reaper-unlink {
+                plugin = test-cargo-reaper-build-package-manifest;
+                plugin_source = testFileset ./tests/plugin_manifests/package_manifest;
+                plugin_name = "reaper_package_ext";
+              };
+            };
           test-cargo-reaper-build-cross-windows =
             let
               rustcTarget = "x86_64-pc-windows-msvc";
@@ -1,12 +1,28 @@
 use std::{fmt, path, process, time};
 
 pub use clap::{CommandFactory, FromArgMatches};
-use clap::{Parser, ValueEnum, ValueHint, builder::styling};
+use clap::{
+    Parser, ValueEnum, ValueHint,
+    builder::{ArgPredicate, styling},
+};
 use colored::Colorize;
 
 #[cfg(target_os = "linux")]
-/// The default display used by `Xvfb` for running REAPER in a headless environment.
-const DEFAULT_XSERVER_DISPLAY: &str = ":99";
+/// The default screen geometry of the virtual display used for running REAPER in a headless
+/// environment.
+const DEFAULT_XSERVER_SCREEN: &str = "1024x768x24";
+
+#[cfg(target_os = "linux")]
+/// The display used by `Xvfb` for running REAPER in a headless environment when `DISPLAY` isn't
+/// set, which picks an unused display number.
+pub(crate) const AUTO_XSERVER_DISPLAY: &str = "auto";
+
+#[cfg(target_os = "linux")]
+/// The display used for running REAPER in a headless environment when neither `--display` nor
+/// `CARGO_REAPER_DISPLAY` is set, i.e. `DISPLAY`, or [`AUTO_XSERVER_DISPLAY`].
+fn default_display() -> String {
+    std::env::var("DISPLAY").unwrap_or_else(|_| AUTO_XSERVER_DISPLAY.to_string())
+}
 
 /// The terminal output style configuration.
 pub const TERM_STYLE: styling::Styles = styling::Styles::styled()
@@ -46,15 +62,172 @@ impl CargoReaperArgs {
 pub enum CargoReaperCommand {
     /// Create a new REAPER plugin from a template at `PATH`.
     New {
-        /// The type of template to use.
-        #[arg(long, short = 't', default_value_t = PluginTemplate::Ext)]
-        template: PluginTemplate,
+        /// The template to use: the name of a template in the `[templates]` table of the user
+        /// config file, or else `ext` for the extension plugin template, or `vst` for the VST
+        /// plugin template.
+        #[arg(long, short = 't', default_value_t = PluginTemplate::Ext.to_string())]
+        template: String,
 
-        path: path::PathBuf,
+        /// Create the project from the git repository at `URL` instead, which must contain a
+        /// `Cargo.toml` manifest and a `reaper.toml` configuration file.
+        #[arg(long, value_name = "URL", conflicts_with = "template")]
+        template_git: Option<String>,
+
+        /// Create the project from a copy of the directory at `DIR` instead, which must contain a
+        /// `Cargo.toml` manifest and a `reaper.toml` configuration file.
+        #[arg(
+            long,
+            value_name = "DIR",
+            value_hint = ValueHint::DirPath,
+            conflicts_with_all = ["template", "template_git"]
+        )]
+        template_path: Option<path::PathBuf>,
+
+        /// The branch of the `--template-git` repository to use, instead of its default branch.
+        #[arg(
+            long,
+            requires = "template_git",
+            conflicts_with_all = ["rev", "template_path"]
+        )]
+        branch: Option<String>,
+
+        /// The revision of the `--template-git` repository to use, e.g. a commit hash or a tag.
+        #[arg(long, requires = "template_git", conflicts_with = "template_path")]
+        rev: Option<String>,
+
+        /// The package name, which defaults to the name of the directory. It's also the library
+        /// name, and the plugin key in `reaper.toml` (prefixed by `reaper_` if it isn't already).
+        #[arg(long)]
+        name: Option<String>,
+
+        /// The version control system the project is initialized with. Initialization is skipped
+        /// when the project is created within an existing git repository.
+        #[arg(long, default_value = "git")]
+        vcs: Vcs,
+
+        /// List the available templates, i.e. those of the user config file and the bundled
+        /// ones, along with where they come from, and exit.
+        #[arg(long, exclusive = true)]
+        list_templates: bool,
+
+        #[arg(required_unless_present = "list_templates")]
+        path: Option<path::PathBuf>,
     },
 
     /// List available extension plugin(s).
-    List,
+    List {
+        /// Only list the plugin(s) whose key matches a glob pattern, e.g. `reaper_midi*`.
+        #[arg(value_name = "PATTERN")]
+        pattern: Option<PluginPattern>,
+
+        /// Do not warn when a symlinked plugin is older than its sources.
+        #[arg(long)]
+        no_staleness_check: bool,
+
+        /// The format plugin(s) are printed in: `human`, or `json` to print an array of objects
+        /// to stdout, with diagnostics and warnings on stderr.
+        #[arg(long, value_name = "FMT", default_value = "human")]
+        format: ListFormat,
+
+        /// Only look for artifacts built with this profile, e.g. `release`. Can be passed multiple
+        /// times. Defaults to `debug` and `release`.
+        #[arg(long, value_name = "PROFILE-NAME")]
+        profile: Vec<String>,
+
+        /// Report every manifest that can't be read or parsed instead of stopping at the first,
+        /// list every plugin including the broken ones, and exit with a non-zero status code if
+        /// any problem was found.
+        #[arg(long)]
+        check: bool,
+
+        /// Also show each plugin's license, repository, minimum supported Rust version, crate
+        /// types and library name.
+        #[arg(long, short)]
+        verbose: bool,
+
+        /// Only print the sorted plugin keys, one per line, without validating the plugin
+        /// manifests. Nothing is printed to stderr unless the configuration can't be loaded.
+        #[arg(long, short, conflicts_with_all = ["no_staleness_check", "format", "profile", "check", "verbose"])]
+        quiet: bool,
+
+        /// List the plugin(s) of every `cargo-reaper` project below the current directory (e.g.
+        /// the sub-projects of a monorepo), grouped by configuration file. Hidden and `target`
+        /// directories are skipped.
+        #[arg(long, short)]
+        recursive: bool,
+    },
+
+    /// Show everything known about an extension plugin.
+    Info {
+        /// The key of the plugin in the `cargo-reaper` configuration file.
+        #[arg(value_name = "PLUGIN_KEY")]
+        key: String,
+
+        /// The format the plugin is printed in: `human`, or `json` to print an object to stdout,
+        /// with diagnostics and warnings on stderr.
+        #[arg(long, value_name = "FMT", default_value = "human")]
+        format: ListFormat,
+
+        /// Only look for artifacts built with this profile, e.g. `release`. Can be passed multiple
+        /// times. Defaults to `debug` and `release`.
+        #[arg(long, value_name = "PROFILE-NAME")]
+        profile: Vec<String>,
+    },
+
+    /// Show which plugin(s) are out of sync between the configuration, Cargo's target directory
+    /// and the `UserPlugins` directory.
+    Status {
+        /// Check the `UserPlugins` directory of a portable REAPER installation, i.e. a directory
+        /// containing `reaper.ini`.
+        #[arg(long, env = "CARGO_REAPER_PORTABLE", value_name = "DIR", value_hint = ValueHint::DirPath, value_parser = parse_portable_dir)]
+        portable: Option<path::PathBuf>,
+
+        /// Exit with a non-zero status code if any plugin is out of sync.
+        #[arg(long)]
+        check: bool,
+    },
+
+    /// Print the REAPER executable, `UserPlugins` directory and configuration file `cargo-reaper`
+    /// resolves, along with the version of REAPER.
+    Which {
+        /// Override the REAPER executable file path, see `cargo reaper run --exec`.
+        #[arg(
+            long = "exec",
+            short = 'e',
+            env = "CARGO_REAPER_EXEC",
+            value_name = "REAPER",
+            value_hint = ValueHint::ExecutablePath
+        )]
+        reaper: Option<path::PathBuf>,
+
+        /// Resolve the REAPER executable and `UserPlugins` directory of a portable REAPER
+        /// installation, see `cargo reaper run --portable`.
+        #[arg(long, env = "CARGO_REAPER_PORTABLE", value_name = "DIR", value_hint = ValueHint::DirPath, value_parser = parse_portable_dir)]
+        portable: Option<path::PathBuf>,
+
+        /// The format the paths are printed in: `human`, or `json` to print an object to stdout.
+        #[arg(long, value_name = "FMT", default_value = "human")]
+        format: ListFormat,
+    },
+
+    /// Diagnose common problems with the environment plugin(s) are developed in, e.g. a REAPER
+    /// executable that can't be found, or a missing `UserPlugins` directory.
+    Doctor {
+        /// Override the REAPER executable file path, see `cargo reaper run --exec`.
+        #[arg(
+            long = "exec",
+            short = 'e',
+            env = "CARGO_REAPER_EXEC",
+            value_name = "REAPER",
+            value_hint = ValueHint::ExecutablePath
+        )]
+        reaper: Option<path::PathBuf>,
+
+        /// Check the REAPER executable and `UserPlugins` directory of a portable REAPER
+        /// installation, see `cargo reaper run --portable`.
+        #[arg(long, env = "CARGO_REAPER_PORTABLE", value_name = "DIR", value_hint = ValueHint::DirPath, value_parser = parse_portable_dir)]
+        portable: Option<path::PathBuf>,
+    },
 
     /// Compile REAPER extension plugin(s).
     Build {
@@ -64,112 +237,145 @@ pub enum CargoReaperCommand {
         #[arg(long)]
         no_symlink: bool,
 
+        /// Symlink plugin(s) by a path relative to the `UserPlugins` directory rather than an
+        /// absolute one, e.g. when the home directory is mounted at different paths. Defaults to
+        /// the `symlink_style` of the configuration file.
+        #[arg(long, conflicts_with = "no_symlink")]
+        relative: bool,
+
+        /// Symlink plugin(s) to the `UserPlugins` directory of a portable REAPER installation,
+        /// i.e. a directory containing `reaper.ini`.
+        #[arg(long, env = "CARGO_REAPER_PORTABLE", value_name = "DIR", value_hint = ValueHint::DirPath, value_parser = parse_portable_dir)]
+        portable: Option<path::PathBuf>,
+
         /// Arguments to forward to the `cargo build` invocation.
         #[arg(allow_hyphen_values = true, trailing_var_arg = true, num_args = 0.., value_name = "CARGO_BUILD_ARGS")]
         args: Vec<String>,
     },
 
     /// Symlink plugin(s) to the `UserPlugins` directory.
+    #[command(group(clap::ArgGroup::new("link").args(["paths", "plugins", "all"]).required(true).multiple(true)))]
+    #[command(group(clap::ArgGroup::new("keys").args(["plugins", "all"])))]
     Link {
-        /// Create symlink(s) by path.
-        #[arg(value_name = "PLUGIN_PATH", value_hint = ValueHint::FilePath, required = true, num_args = 1..)]
+        /// Create symlink(s) by path. The plugin files in a directory, i.e. those named
+        /// `reaper_*` with the plugin extension of the host, are symlinked each.
+        #[arg(value_name = "PLUGIN_PATH", value_hint = ValueHint::AnyPath, num_args = 1..)]
         paths: Vec<path::PathBuf>,
-    },
 
-    /// Compile and run REAPER extension plugin(s).
-    Run {
-        /// Override the REAPER executable file path. By default, the REAPER executable found on
-        /// `$PATH` will be used. If the REAPER exectuable can't be found in the current working
-        /// directory, the default global installation path will be used instead.
-        #[arg(
-            long = "exec",
-            short = 'e',
-            value_name = "REAPER",
-            value_hint = ValueHint::ExecutablePath
-        )]
-        reaper: Option<path::PathBuf>,
+        /// Also look for plugin files in the directories below directory paths.
+        #[arg(long, short = 'r')]
+        recursive: bool,
 
-        /// Open a specific REAPER project file.
-        #[arg(
-            long = "open",
-            short = 'o',
-            value_name = "PROJECT",
-            value_hint = ValueHint::FilePath
-        )]
-        project: Option<path::PathBuf>,
+        /// Create the symlink of a plugin by key, to its renamed artifact in Cargo's target
+        /// directory. Can be passed multiple times.
+        #[arg(long = "plugin", short = 'p', value_name = "PLUGIN_KEY")]
+        plugins: Vec<String>,
 
-        /// Do not build plugin(s) before running REAPER.
-        #[arg(long, conflicts_with = "args")]
-        no_build: bool,
+        /// Create the symlink of every plugin of the configuration file whose renamed artifact
+        /// exists in Cargo's target directory, without building anything. Plugins without an
+        /// artifact are reported, without failing unless passed `--strict`.
+        #[arg(long, short = 'a', conflicts_with = "paths")]
+        all: bool,
+
+        /// Exit with a non-zero status code if any plugin has no artifact to link.
+        #[arg(long, requires = "all")]
+        strict: bool,
 
-        /// Run REAPER in a headless environment.
-        #[cfg(target_os = "linux")]
+        /// Only look for the artifacts of `--plugin` or `--all` built with this profile, e.g.
+        /// `release`. Defaults to `release`, then `debug`.
+        #[arg(long, value_name = "PROFILE-NAME", requires = "keys")]
+        profile: Option<String>,
+
+        /// Symlink plugin(s) by a path relative to the `UserPlugins` directory rather than an
+        /// absolute one. Defaults to the `symlink_style` of the configuration file, if any.
         #[arg(long)]
-        headless: bool,
+        relative: bool,
 
-        /// The virtual display that should be used for the headless environment.
-        #[cfg(target_os = "linux")]
-        #[arg(
-            long,
-            short = 'D',
-            env = "DISPLAY",
-            default_value = DEFAULT_XSERVER_DISPLAY,
-            required_if_eq("headless", "true")
-        )]
-        display: String,
+        /// Replace a regular file, or a symlink to another file, already in the way of a symlink.
+        /// Directories are never replaced.
+        #[arg(long, short = 'f')]
+        force: bool,
 
-        /// Locate a window based on its title and exit with status code 0 if found.
-        #[cfg(target_os = "linux")]
-        #[arg(
-            long = "locate-window",
-            short = 'w',
-            value_name = "TITLE",
-            requires = "headless"
-        )]
-        window_title: Option<String>,
+        /// Symlink plugin(s) without checking that they are dynamic libraries for the host, built
+        /// for the architecture of REAPER.
+        #[arg(long)]
+        skip_validation: bool,
 
-        /// Continue until the specified timeout, even after a window is located.
-        #[cfg(target_os = "linux")]
-        #[arg(long, requires_all = ["headless", "window_title", "timeout"])]
-        keep_going: bool,
+        /// Symlink plugin(s) to this directory instead of the default `UserPlugins` directory,
+        /// e.g. that of a portable REAPER installation. It must exist, unless passed `--create`.
+        #[arg(long, value_name = "DIR", value_hint = ValueHint::DirPath)]
+        to: Option<path::PathBuf>,
 
-        /// The amount of time to wait before closing REAPER, in human-readable format (e.g. 10s, 2m, 1h).
-        #[arg(
-            long,
-            short = 't',
-            value_name = "DURATION",
-            value_parser = humantime::parse_duration
-        )]
-        timeout: Option<time::Duration>,
+        /// Create the directory passed to `--to` if it doesn't exist.
+        #[arg(long, requires = "to")]
+        create: bool,
+    },
+
+    /// Remove symlink(s) from the `UserPlugins` directory, without touching Cargo's target
+    /// directory.
+    Unlink {
+        /// Remove plugin(s) by key or by file name, or by a glob pattern matched against them
+        /// (e.g. `reaper_midi*`). Keys are those of the configuration file, and the file names in
+        /// the `UserPlugins` directory without their plugin extension.
+        #[arg(value_name = "PLUGIN", required = true, num_args = 1..)]
+        plugins: Vec<PluginPattern>,
 
-        /// Configuration for the child process’s standard input (stdin) handle.
-        #[arg(long, short = 'I', value_name = "STDIO", default_value = "null")]
-        stdin: Stdio,
+        /// Display what would be removed without removing anything.
+        #[arg(long, short = 'n')]
+        dry_run: bool,
 
-        /// Configuration for the child process’s standard output (stdout) handle.
-        #[arg(long, short = 'O', value_name = "STDIO", default_value = "inherit")]
-        stdout: Stdio,
+        /// Also remove matched entries that are regular files rather than symlinks, e.g. plugins
+        /// installed manually, which are otherwise only reported.
+        #[arg(long, short = 'f')]
+        force: bool,
 
-        /// Configuration for the child process’s standard error (stderr) handle.
-        #[arg(long, short = 'E', value_name = "STDIO", default_value = "inherit")]
-        stderr: Stdio,
+        /// Also unlink plugin(s) from the `UserPlugins` directory of a portable REAPER
+        /// installation, which `cargo reaper build --portable` symlinks plugin(s) to.
+        #[arg(long, env = "CARGO_REAPER_PORTABLE", value_name = "DIR", value_hint = ValueHint::DirPath, value_parser = parse_portable_dir)]
+        portable: Option<path::PathBuf>,
 
-        /// Arguments to forward to the `cargo build` invocation.
+        /// Unlink plugin(s) from this `UserPlugins` directory instead of the default one (and
+        /// that of `--portable`), e.g. one plugin(s) were symlinked to with `cargo reaper link
+        /// --to`. Can be passed multiple times.
+        #[arg(long = "user-plugins-dir", visible_alias = "to", value_name = "DIR", value_hint = ValueHint::DirPath)]
+        user_plugins_dirs: Vec<path::PathBuf>,
+    },
+
+    /// Compile and run REAPER extension plugin(s).
+    #[command(after_long_help = format!("{}\n\n{}", RunArgs::environment_help(), RunArgs::exit_status_help()))]
+    Run(Box<RunArgs>),
+
+    /// Compile REAPER extension plugin(s) and run the test cases in the `cargo-reaper`
+    /// configuration file's `[[test]]` tables, printing a summary of their results.
+    Test {
+        /// Only run the test case with this name.
+        name: Option<String>,
+
+        /// Override the REAPER executable file path, see `cargo reaper run --exec`.
         #[arg(
-            allow_hyphen_values = true,
-            trailing_var_arg = true,
-            num_args = 0..,
-            value_name = "CARGO_BUILD_ARGS",
-            conflicts_with = "no_build"
+            long = "exec",
+            short = 'e',
+            env = "CARGO_REAPER_EXEC",
+            value_name = "REAPER",
+            value_hint = ValueHint::ExecutablePath
         )]
+        reaper: Option<path::PathBuf>,
+
+        /// Do not build plugin(s) before running the test cases.
+        #[arg(long, conflicts_with = "args")]
+        no_build: bool,
+
+        /// Arguments to forward to the `cargo build` invocation.
+        #[arg(last = true, value_name = "CARGO_BUILD_ARGS")]
         args: Vec<String>,
     },
 
     /// Remove plugin(s) from the `UserPlugins` directory that cargo-reaper has generated in the past.
     Clean {
-        /// Clean plugin(s) by key.
+        /// Clean plugin(s) by key, or by a glob pattern matched against the keys (e.g.
+        /// `reaper_midi*`). `--dry-run` shows the keys each pattern matched.
         #[arg(long = "plugin", short = 'p', value_name = "PLUGIN_KEY")]
-        plugins: Vec<String>,
+        plugins: Vec<PluginPattern>,
 
         /// Display what would be deleted without deleting anything.
         #[arg(long, short = 'n')]
@@ -178,6 +384,72 @@ pub enum CargoReaperCommand {
         /// Remove artifacts that cargo-reaper has generated in the past.
         #[arg(long, short = 'a', default_value = "false")]
         remove_artifacts: bool,
+
+        /// Also remove the files of the plugin(s) with the extension of another operating system
+        /// (e.g. `reaper_my_plugin.dll` on Linux), such as cross compiled plugins copied to the
+        /// `UserPlugins` directory, which are otherwise only reported.
+        #[arg(long)]
+        all_extensions: bool,
+
+        /// Instead of the symlinks of the configured plugin(s), remove the symlinks in the
+        /// `UserPlugins` directory whose targets no longer exist, e.g. after renaming a plugin
+        /// key or removing the target directory. Only symlinks pointing into the project's target
+        /// directory are removed.
+        #[arg(long, conflicts_with_all = ["plugins", "remove_artifacts", "all_extensions"])]
+        orphans: bool,
+
+        /// Like `--orphans`, but remove every dangling `reaper_*` symlink in the `UserPlugins`
+        /// directory regardless of where it points, once confirmed.
+        #[arg(long, conflicts_with_all = ["plugins", "remove_artifacts", "all_extensions", "orphans"])]
+        all_orphans: bool,
+
+        /// Instead of the symlinks of the configured plugin(s), remove the symlinks in the
+        /// `UserPlugins` directory that resolve into the project's target directory, but whose
+        /// file names don't correspond to any configured plugin key, e.g. after removing a plugin
+        /// from the configuration file.
+        #[arg(
+            long,
+            conflicts_with_all = ["plugins", "remove_artifacts", "all_extensions", "orphans", "all_orphans"]
+        )]
+        stale: bool,
+
+        /// Instead of the symlinks of the configured plugin(s), remove every symlink
+        /// `cargo-reaper` has created for any project (e.g. old checkouts, renamed projects, or
+        /// `cargo reaper link`), as recorded in its registry, once confirmed. Registry entries
+        /// whose symlinks no longer exist are pruned.
+        #[arg(
+            long,
+            conflicts_with_all = ["plugins", "remove_artifacts", "all_extensions", "orphans", "all_orphans", "stale", "user_plugins_dirs"]
+        )]
+        all_projects: bool,
+
+        /// Do not ask for confirmation before removing artifacts (`--remove-artifacts`), any
+        /// dangling symlink (`--all-orphans`), or the symlinks of any project
+        /// (`--all-projects`), which is required when stdin is not a terminal.
+        #[arg(long, short = 'y')]
+        yes: bool,
+
+        /// Exit with a non-zero status code if any removal fails (e.g. a symlink is missing when
+        /// it was expected, permission is denied, or `cargo clean` fails), once every removal has
+        /// been attempted.
+        #[arg(long)]
+        strict: bool,
+
+        /// The format the removals are reported in: `human`, or `json` to print an object listing
+        /// each planned action to stdout, annotated with its outcome unless passed `--dry-run`,
+        /// with errors on stderr.
+        #[arg(long, value_name = "FMT", default_value = "human")]
+        format: ListFormat,
+
+        /// Also clean the `UserPlugins` directory of a portable REAPER installation, which
+        /// `cargo reaper build --portable` symlinks plugin(s) to.
+        #[arg(long, env = "CARGO_REAPER_PORTABLE", value_name = "DIR", value_hint = ValueHint::DirPath, value_parser = parse_portable_dir)]
+        portable: Option<path::PathBuf>,
+
+        /// Clean this `UserPlugins` directory instead of the default one (and that of
+        /// `--portable`). Can be passed multiple times.
+        #[arg(long = "user-plugins-dir", value_name = "DIR", value_hint = ValueHint::DirPath)]
+        user_plugins_dirs: Vec<path::PathBuf>,
     },
 
     /// Generate shell completions.
@@ -191,6 +463,672 @@ pub enum CargoReaperCommand {
     },
 }
 
+/// Arguments for the `cargo reaper run` command.
+#[derive(Debug, Clone, clap::Args)]
+#[command(group(clap::ArgGroup::new("locate").multiple(true)))]
+pub struct RunArgs {
+    /// Override the REAPER executable file path. By default, the REAPER executable found on
+    /// `$PATH` will be used. If the REAPER exectuable can't be found in the current working
+    /// directory, the default global installation path will be used instead.
+    #[arg(
+        long = "exec",
+        short = 'e',
+        env = "CARGO_REAPER_EXEC",
+        value_name = "REAPER",
+        value_hint = ValueHint::ExecutablePath
+    )]
+    pub(crate) reaper: Option<path::PathBuf>,
+
+    /// Open a specific REAPER project file. Can be passed multiple times to open each project in
+    /// its own tab, in the order they are passed.
+    #[arg(
+        long = "open",
+        short = 'o',
+        env = "CARGO_REAPER_PROJECT",
+        value_name = "PROJECT",
+        value_hint = ValueHint::FilePath,
+        value_parser = parse_project_file
+    )]
+    pub(crate) project: Vec<path::PathBuf>,
+
+    /// Run a portable REAPER installation, i.e. a directory containing `reaper.ini` (`-cfgfile`).
+    /// The REAPER executable inside the directory is used unless `--exec` is passed, and plugin(s)
+    /// are symlinked to its `UserPlugins` directory.
+    #[arg(long, env = "CARGO_REAPER_PORTABLE", value_name = "DIR", value_hint = ValueHint::DirPath, value_parser = parse_portable_dir)]
+    pub(crate) portable: Option<path::PathBuf>,
+
+    /// Abort unless the version of the REAPER executable is at least `VERSION`, e.g. `7.03`.
+    #[arg(long, value_name = "VERSION")]
+    pub(crate) min_reaper_version: Option<ReaperVersion>,
+
+    /// Run REAPER with a throwaway resource directory containing a minimal `reaper.ini`
+    /// (`-cfgfile`), which plugin(s) are symlinked to. The directory is removed once REAPER exits.
+    #[arg(long, conflicts_with_all = ["portable", "no_build"])]
+    pub(crate) fresh_config: bool,
+
+    /// Keep the resource directory created by `--fresh-config` once REAPER exits, printing its
+    /// path.
+    #[arg(long, requires = "fresh_config")]
+    pub(crate) keep_config: bool,
+
+    /// Open copies of the projects passed to `--open` (and their `.rpp-bak` backups) in a
+    /// temporary directory, so that REAPER never modifies the originals. The directory is removed
+    /// once REAPER exits.
+    #[arg(long)]
+    pub(crate) ephemeral: bool,
+
+    /// Keep the temporary directory created by `--ephemeral` once REAPER exits, printing its path.
+    #[arg(long, requires = "ephemeral")]
+    pub(crate) keep: bool,
+
+    /// Copy the contents of a directory (e.g. `reaper.ini`, `reaper-kb.ini`) into REAPER's resource
+    /// path before plugin(s) are symlinked and REAPER is launched.
+    #[arg(long, value_name = "DIR", value_hint = ValueHint::DirPath)]
+    pub(crate) install_config: Option<path::PathBuf>,
+
+    /// Overwrite existing files in REAPER's resource path when passed `--install-config`.
+    #[arg(long, requires = "install_config")]
+    pub(crate) force: bool,
+
+    /// Run a ReaScript once REAPER has launched. The script is copied to the `Scripts`
+    /// directory of REAPER's resource path before running.
+    #[arg(long, value_name = "SCRIPT", value_hint = ValueHint::FilePath)]
+    pub(crate) script: Option<path::PathBuf>,
+
+    /// Do not build plugin(s) before running REAPER.
+    #[arg(long, conflicts_with = "args")]
+    pub(crate) no_build: bool,
+
+    /// Print the commands that would be run (`cargo build`, the virtual display server and
+    /// REAPER), along with REAPER's working directory and environment, without running anything.
+    #[arg(long)]
+    pub(crate) dry_run: bool,
+
+    /// Exit with a non-zero status code unless REAPER's splash log mentions each plugin as loaded.
+    /// Defaults to the file names of all plugins in the `cargo-reaper` configuration file.
+    #[arg(long, value_name = "PLUGIN", num_args = 0..)]
+    pub(crate) require_plugin_loaded: Option<Vec<String>>,
+
+    /// Exit with a non-zero status code unless `FILE` contains `TEXT` once REAPER exits, e.g. a
+    /// log file the extension plugin writes its console messages to. Can be passed multiple
+    /// times, and every unmet expectation is reported.
+    #[arg(long, value_name = "FILE:TEXT")]
+    pub(crate) expect_console: Vec<ConsoleExpectation>,
+
+    /// Record REAPER's splash log to a file (`-splashlog`), summarizing the extensions it loaded
+    /// once REAPER exits.
+    #[arg(long, value_name = "PATH", value_hint = ValueHint::FilePath)]
+    pub(crate) splash_log: Option<path::PathBuf>,
+
+    /// Render the project passed to `--open` and exit (`-renderproject`).
+    #[arg(long, requires = "project")]
+    pub(crate) render: bool,
+
+    /// Do not show the render progress dialog while rendering (`-norenderdialog`).
+    #[arg(long, requires = "render")]
+    pub(crate) no_render_dialog: bool,
+
+    /// Do not show REAPER's splash screen while it starts (`-nosplash`).
+    #[arg(long)]
+    pub(crate) no_splash: bool,
+
+    /// Do not show error dialogs while REAPER starts (`-ignoreerrors`), e.g. when a plugin fails
+    /// to load, which would otherwise block a headless run until it times out.
+    #[arg(long)]
+    pub(crate) ignore_errors: bool,
+
+    /// Create a new project from a REAPER project template file (`-template`).
+    #[arg(
+        long,
+        value_name = "FILE",
+        value_hint = ValueHint::FilePath,
+        value_parser = parse_template_file
+    )]
+    pub(crate) template: Option<path::PathBuf>,
+
+    /// Always launch a new REAPER instance, even if REAPER is already running (`-newinst`).
+    #[arg(long, conflicts_with = "no_new_instance")]
+    pub(crate) new_instance: bool,
+
+    /// Never launch a new REAPER instance if REAPER is already running (`-nonewinst`).
+    #[arg(long)]
+    pub(crate) no_new_instance: bool,
+
+    /// Terminate any running REAPER instance before launching, so that plugin(s) are loaded into
+    /// a new one. Each instance is asked to exit first, and killed if it is still running
+    /// afterwards.
+    #[arg(long, conflicts_with_all = ["new_instance", "no_new_instance"])]
+    pub(crate) kill_existing: bool,
+
+    /// Spawn REAPER detached from the terminal, print its PID and executable path, and exit
+    /// straight away, leaving REAPER running. REAPER's stdio must be `null`, which is the default
+    /// when detaching.
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "timeout",
+            "startup_timeout",
+            "locate",
+            "fail_on_window",
+            "fail_on_window_regex",
+            "fail_on_log_pattern",
+            "require_plugin_loaded",
+            "expect_console",
+            "log_file",
+            "tee",
+            "report_junit",
+            "progress",
+            "debugger",
+            "fresh_config",
+            "ephemeral",
+            "after",
+        ]
+    )]
+    #[cfg_attr(
+        target_os = "linux",
+        arg(conflicts_with_all = ["headless", "valgrind"])
+    )]
+    pub(crate) detach: bool,
+
+    /// Do not warn when a symlinked plugin is older than its sources, or symlinked to another
+    /// build than its most recently built artifact.
+    #[arg(long, requires = "no_build")]
+    pub(crate) no_staleness_check: bool,
+
+    /// Run REAPER under a debugger, with a pending breakpoint on the entry point of extension
+    /// plugins (`ReaperPluginEntry`). Plugin(s) are built with debug info, the debugger is attached
+    /// to the terminal, and the session lasts until the debugger exits. `auto` uses `lldb` on
+    /// macOS and `gdb` elsewhere, falling back to the other if it is not found on `$PATH`.
+    #[arg(
+        long,
+        value_name = "DEBUGGER",
+        num_args = 0..=1,
+        default_missing_value = "auto",
+        conflicts_with_all = ["timeout", "startup_timeout", "locate", "log_file", "tee"]
+    )]
+    #[cfg_attr(target_os = "linux", arg(conflicts_with = "headless"))]
+    pub(crate) debugger: Option<Debugger>,
+
+    /// Run REAPER under `valgrind`, writing a log per process to `valgrind-<PID>.log` in Cargo's
+    /// target directory, and summarizing the errors and definite leaks it reports once REAPER
+    /// exits. Exits with status code 42 if any were reported.
+    #[cfg(target_os = "linux")]
+    #[arg(long, conflicts_with = "debugger")]
+    pub(crate) valgrind: bool,
+
+    /// Additional arguments for `valgrind`, separated by whitespace (e.g. `--track-origins=yes`).
+    #[cfg(target_os = "linux")]
+    #[arg(
+        long,
+        value_name = "ARGS",
+        allow_hyphen_values = true,
+        requires = "valgrind"
+    )]
+    pub(crate) valgrind_args: Vec<String>,
+
+    /// Build plugin(s) with a sanitizer (requires a nightly toolchain), and run REAPER with its
+    /// runtime preloaded. Reports are written to `asan.<PID>` files in the `asan` directory of
+    /// Cargo's target directory, and summarized once REAPER exits.
+    #[cfg(unix)]
+    #[arg(long, value_name = "SANITIZER")]
+    #[cfg_attr(target_os = "linux", arg(conflicts_with = "valgrind"))]
+    pub(crate) sanitizer: Option<Sanitizer>,
+
+    /// Run REAPER in a headless environment.
+    #[cfg(target_os = "linux")]
+    #[arg(long)]
+    pub(crate) headless: bool,
+
+    /// The display server REAPER is attached to when running headless.
+    #[cfg(target_os = "linux")]
+    #[arg(long, value_enum, default_value_t = Backend::Xvfb, requires = "headless")]
+    pub(crate) backend: Backend,
+
+    /// The screen geometry of the virtual display, i.e. `WIDTHxHEIGHTxDEPTH`.
+    #[cfg(target_os = "linux")]
+    #[arg(long, value_name = "WxHxD", default_value = DEFAULT_XSERVER_SCREEN)]
+    pub(crate) screen: Screen,
+
+    /// Additional arguments for the virtual display server, separated by whitespace. Default
+    /// arguments are only passed when they don't conflict with these, e.g. `-screen` or
+    /// `-nolisten`.
+    #[arg(
+        long,
+        value_name = "ARGS",
+        allow_hyphen_values = true,
+        requires = "headless"
+    )]
+    #[cfg(target_os = "linux")]
+    pub(crate) xvfb_args: Vec<String>,
+
+    /// Set an environment variable on the virtual display server, i.e. `KEY=VALUE`. Can be passed
+    /// multiple times.
+    #[cfg(target_os = "linux")]
+    #[arg(long, value_name = "KEY=VALUE", requires = "headless")]
+    pub(crate) xvfb_env: Vec<EnvVar>,
+
+    /// Configuration for the virtual display server’s standard output (stdout) handle, or
+    /// `file:PATH` to write to a file (`file+:PATH` to append to it).
+    #[cfg(target_os = "linux")]
+    #[arg(long, value_name = "STDIO", default_value = "null")]
+    pub(crate) xvfb_stdout: Stdio,
+
+    /// Configuration for the virtual display server’s standard error (stderr) handle, or
+    /// `file:PATH` to write to a file (`file+:PATH` to append to it).
+    #[cfg(target_os = "linux")]
+    #[arg(long, value_name = "STDIO", default_value = "null")]
+    pub(crate) xvfb_stderr: Stdio,
+
+    /// Override the `Xvfb` executable file path. By default, `Xvfb` is found on `$PATH`.
+    #[cfg(target_os = "linux")]
+    #[arg(
+        long,
+        value_name = "XVFB",
+        value_hint = ValueHint::ExecutablePath,
+        requires = "headless"
+    )]
+    pub(crate) xvfb_path: Option<path::PathBuf>,
+
+    /// The virtual display that should be used for the headless environment, or `auto` to pick an
+    /// unused display number. Defaults to `DISPLAY` when it is set.
+    #[cfg(target_os = "linux")]
+    #[arg(
+        long,
+        short = 'D',
+        env = "CARGO_REAPER_DISPLAY",
+        default_value_t = default_display()
+    )]
+    pub(crate) display: String,
+
+    /// Capture the virtual display to a PNG image when a window is not located, or the timeout is
+    /// reached. Requires ImageMagick's `import`.
+    #[cfg(target_os = "linux")]
+    #[arg(
+        long,
+        value_name = "PATH",
+        value_hint = ValueHint::FilePath,
+        requires = "headless"
+    )]
+    pub(crate) screenshot: Option<path::PathBuf>,
+
+    /// Capture the virtual display whenever a window is located, and once the session ends,
+    /// regardless of its outcome.
+    #[cfg(target_os = "linux")]
+    #[arg(long, requires = "screenshot")]
+    pub(crate) screenshot_always: bool,
+
+    /// Record the virtual display to a video file for the duration of the session. Requires
+    /// `ffmpeg`.
+    #[cfg(target_os = "linux")]
+    #[arg(
+        long,
+        value_name = "FILE",
+        value_hint = ValueHint::FilePath,
+        requires = "headless"
+    )]
+    pub(crate) record: Option<path::PathBuf>,
+
+    /// Locate a window based on its title and exit with status code 0 if found.
+    #[arg(
+        long = "locate-window",
+        short = 'w',
+        env = "CARGO_REAPER_LOCATE_WINDOW",
+        value_name = "TITLE",
+        group = "locate",
+        conflicts_with = "window_regex"
+    )]
+    #[cfg_attr(target_os = "linux", arg(requires = "headless"))]
+    pub(crate) window_title: Option<String>,
+
+    /// Locate a window with a title matching a regular expression and exit with status code 0 if
+    /// found.
+    #[arg(
+        long = "locate-window-regex",
+        value_name = "PATTERN",
+        value_parser = regex::Regex::new,
+        group = "locate"
+    )]
+    #[cfg_attr(target_os = "linux", arg(requires = "headless"))]
+    pub(crate) window_regex: Option<regex::Regex>,
+
+    /// Locate a window based on its class (`WM_CLASS`) and exit with status code 0 if found.
+    /// When passed alongside `--locate-window` or `--locate-window-regex`, either match counts.
+    #[cfg(target_os = "linux")]
+    #[arg(
+        long = "locate-class",
+        value_name = "CLASS",
+        group = "locate",
+        requires = "headless"
+    )]
+    pub(crate) window_class: Option<String>,
+
+    /// Wait for a file to be created, e.g. by the extension plugin once its tests pass, and exit
+    /// with status code 0 once it exists. When passed alongside a window to locate, either counts.
+    #[arg(long, value_name = "PATH", value_hint = ValueHint::FilePath, group = "locate")]
+    pub(crate) wait_for_file: Option<path::PathBuf>,
+
+    /// Do not remove the file passed to `--wait-for-file` before launching REAPER.
+    #[arg(long, requires = "wait_for_file")]
+    pub(crate) no_clean_marker: bool,
+
+    /// Continue until the specified timeout, even after a window is located.
+    #[arg(long, requires_all = ["locate", "timeout"])]
+    pub(crate) keep_going: bool,
+
+    /// Exit with status code 3 as soon as a window with a title containing `TITLE` is located,
+    /// e.g. an error dialog. Can be passed multiple times.
+    #[arg(long, value_name = "TITLE")]
+    #[cfg_attr(target_os = "linux", arg(requires = "headless"))]
+    pub(crate) fail_on_window: Vec<String>,
+
+    /// Exit with status code 3 as soon as a window with a title matching a regular expression is
+    /// located. Can be passed multiple times.
+    #[arg(long, value_name = "PATTERN", value_parser = regex::Regex::new)]
+    #[cfg_attr(target_os = "linux", arg(requires = "headless"))]
+    pub(crate) fail_on_window_regex: Vec<regex::Regex>,
+
+    /// The amount of time to wait before closing REAPER, in human-readable format (e.g. 10s, 2m, 1h).
+    #[arg(
+        long,
+        short = 't',
+        env = "CARGO_REAPER_TIMEOUT",
+        value_name = "DURATION",
+        value_parser = humantime::parse_duration
+    )]
+    pub(crate) timeout: Option<time::Duration>,
+
+    /// Fail with status code 124 if a window has not been located (or the file passed to
+    /// `--wait-for-file` created) within this amount of time, in human-readable format (e.g. 30s),
+    /// while `--timeout` still bounds the whole run. Must not exceed `--timeout`.
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = humantime::parse_duration,
+        requires = "locate"
+    )]
+    pub(crate) startup_timeout: Option<time::Duration>,
+
+    /// Print the elapsed and remaining time, whether the window has been located, and REAPER's PID
+    /// while waiting for the session to end, as a single updating line (or a line every 10 seconds
+    /// when stderr is not a terminal). Nothing is printed with `--message-format json`.
+    #[arg(long)]
+    pub(crate) progress: bool,
+
+    /// Exit successfully when the timeout is reached, instead of with status code 124. A window
+    /// that is not located before the timeout still counts as a failure.
+    #[arg(long, requires = "timeout")]
+    pub(crate) timeout_ok: bool,
+
+    /// Start counting down the timeout once REAPER has started up, i.e. its main window appears,
+    /// or the splash log (when one is written) mentions the plugins passed to
+    /// `--require-plugin-loaded` (or any extension plugin). If neither happens within 2 minutes,
+    /// the timeout counts from when REAPER was spawned.
+    #[arg(long, requires = "timeout")]
+    pub(crate) timeout_after_startup: bool,
+
+    /// Only kill the REAPER process itself when the session ends, instead of its whole process
+    /// group (which includes any helper processes REAPER, or an extension plugin, launched).
+    #[cfg(unix)]
+    #[arg(long)]
+    pub(crate) no_process_group: bool,
+
+    /// How long to wait for REAPER (and the virtual display server) to exit once the session ends,
+    /// after `SIGTERM` (or closing its windows on Windows), before killing it, in human-readable
+    /// format (e.g. 500ms, 5s).
+    #[arg(
+        long,
+        value_name = "DURATION",
+        default_value = "5s",
+        value_parser = humantime::parse_duration
+    )]
+    pub(crate) kill_grace: time::Duration,
+
+    /// Kill REAPER and exit with status code 4 as soon as a line of its output matches a regular
+    /// expression, printing the line. Streams that are not `null` are captured and scanned, and
+    /// `inherit` streams are still printed. The splash log is also scanned once REAPER exits, if
+    /// any. Can be passed multiple times.
+    #[arg(long, value_name = "PATTERN", value_parser = regex::Regex::new)]
+    pub(crate) fail_on_log_pattern: Vec<regex::Regex>,
+
+    /// Write REAPER's stdout and stderr to a file instead, appending to it after a header with the
+    /// command line and the time. When running headless, the virtual display server's output is
+    /// written to a sibling file, e.g. `reaper.xvfb.log`.
+    #[arg(long, value_name = "PATH", value_hint = ValueHint::FilePath)]
+    pub(crate) log_file: Option<path::PathBuf>,
+
+    /// Print REAPER's stdout and stderr as usual, and also append them to a file with `[stdout]`
+    /// and `[stderr]` prefixes, after a header with the command line and the time.
+    #[arg(long, value_name = "PATH", value_hint = ValueHint::FilePath, conflicts_with = "log_file")]
+    pub(crate) tee: Option<path::PathBuf>,
+
+    /// Write a JUnit XML report to a file once the session ends, with a test case per assertion
+    /// (e.g. a window being located, or a plugin being loaded) and REAPER's output attached.
+    #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    pub(crate) report_junit: Option<path::PathBuf>,
+
+    /// Print additional information, e.g. which defaults were taken from the `[run]` table of the
+    /// `cargo-reaper` configuration file. There's no `-v` short, which is passed through to
+    /// `cargo build` as part of `CARGO_BUILD_ARGS`.
+    #[arg(long)]
+    pub(crate) verbose: bool,
+
+    /// The format of the messages printed while running REAPER. `json` prints newline-delimited
+    /// JSON lifecycle events (e.g. `reaper-spawned`, `window-located`, `exited`) to stdout, and
+    /// human-readable messages to stderr.
+    #[arg(long, value_name = "FMT", default_value = "human")]
+    pub(crate) message_format: MessageFormat,
+
+    /// Set an environment variable on the REAPER process, i.e. `KEY=VALUE`, e.g. a log level read
+    /// by the extension plugin. Can be passed multiple times.
+    #[arg(long, value_name = "KEY=VALUE")]
+    pub(crate) env: Vec<EnvVar>,
+
+    /// Run a shell command from the project root before REAPER is launched, e.g. to start a
+    /// fixture server. Can be passed multiple times.
+    #[arg(long, value_name = "CMD")]
+    pub(crate) before: Vec<String>,
+
+    /// Run a shell command from the project root once the session has ended, however it ended
+    /// (e.g. timed out or interrupted), given REAPER's PID in `CARGO_REAPER_PID`. Can be passed
+    /// multiple times.
+    #[arg(long, value_name = "CMD")]
+    pub(crate) after: Vec<String>,
+
+    /// Fail the run when a `--before` or `--after` hook fails, rather than only reporting it. A
+    /// failed `--before` hook aborts the run before REAPER is launched.
+    #[arg(long)]
+    pub(crate) strict_hooks: bool,
+
+    /// Configuration for the REAPER process’s standard input (stdin) handle, or `file:PATH` to
+    /// read from a file.
+    #[arg(
+        long,
+        short = 'I',
+        env = "CARGO_REAPER_STDIN",
+        value_name = "STDIO",
+        default_value = "null"
+    )]
+    pub(crate) stdin: Stdio,
+
+    /// Configuration for the REAPER process’s standard output (stdout) handle, or `file:PATH` to
+    /// write to a file (`file+:PATH` to append to it).
+    #[arg(
+        long,
+        short = 'O',
+        env = "CARGO_REAPER_STDOUT",
+        value_name = "STDIO",
+        default_value = "inherit",
+        default_value_if("detach", ArgPredicate::IsPresent, "null")
+    )]
+    pub(crate) stdout: Stdio,
+
+    /// Configuration for the REAPER process’s standard error (stderr) handle, or `file:PATH` to
+    /// write to a file (`file+:PATH` to append to it).
+    #[arg(
+        long,
+        short = 'E',
+        env = "CARGO_REAPER_STDERR",
+        value_name = "STDIO",
+        default_value = "inherit",
+        default_value_if("detach", ArgPredicate::IsPresent, "null")
+    )]
+    pub(crate) stderr: Stdio,
+
+    /// Arguments to forward to the `cargo build` invocation. Arguments following the first `--`
+    /// are forwarded to the REAPER executable instead (e.g. `-- -nosplash -ignoreerrors`).
+    #[arg(
+        allow_hyphen_values = true,
+        trailing_var_arg = true,
+        num_args = 0..,
+        value_name = "CARGO_BUILD_ARGS",
+        conflicts_with = "no_build"
+    )]
+    pub(crate) args: Vec<String>,
+
+    /// Arguments to forward to the REAPER executable, which are split from the command line
+    /// arguments before parsing, see [`RunArgs::split_reaper_args`].
+    #[arg(skip)]
+    pub reaper_args: Vec<String>,
+}
+impl RunArgs {
+    /// Creates the `clap::Command::after_long_help` message which lists the environment variables
+    /// a `run` invocation reads in place of flags, which take precedence over them.
+    pub fn environment_help() -> String {
+        format!(
+            "{}
+  CARGO_REAPER_EXEC           `--exec`
+  CARGO_REAPER_PROJECT        `--open`
+  CARGO_REAPER_PORTABLE       `--portable`
+  CARGO_REAPER_TIMEOUT        `--timeout`, in human-readable format (e.g. 10s, 2m, 1h)
+  CARGO_REAPER_LOCATE_WINDOW  `--locate-window`
+  CARGO_REAPER_DISPLAY        `--display` (Linux only, falling back to `DISPLAY`)
+  CARGO_REAPER_STDIN          `--stdin`
+  CARGO_REAPER_STDOUT         `--stdout`
+  CARGO_REAPER_STDERR         `--stderr`",
+            "Environment:".green().bold()
+        )
+    }
+
+    /// Creates the `clap::Command::after_long_help` message which lists the status codes a `run`
+    /// invocation exits with.
+    pub fn exit_status_help() -> String {
+        format!(
+            "{}
+  0    REAPER exited successfully, or a window was located (or `--wait-for-file` created)
+  1    A window was not located (or `--wait-for-file` created) before REAPER exited, a required
+       plugin was not loaded, an `--expect-console` expectation was unmet, a sanitizer report
+       was written, or `cargo-reaper` failed
+  N    REAPER exited with status code N (128 + the signal number if killed by a signal)
+  3    A window passed to `--fail-on-window` or `--fail-on-window-regex` was located
+  4    A line of REAPER's output, or its splash log, matched `--fail-on-log-pattern`
+  124  The timeout was reached before REAPER exited, or before a window was located
+       (0 or 1 respectively when passed `--timeout-ok`), or a window was not located within
+       `--startup-timeout`
+  42   `valgrind` reported errors or definite leaks when passed `--valgrind` (Linux only)
+  130  `cargo-reaper` was interrupted by `SIGINT` or `SIGTERM`",
+            "Exit Status:".green().bold()
+        )
+    }
+
+    /// The arguments passed to `cargo build` before running REAPER. Debug info is always
+    /// generated when running under `--debugger`, even for release builds.
+    pub fn build_args(&self) -> Vec<String> {
+        let mut args = self.args.clone();
+        if self.debugger.is_some() {
+            args.extend(
+                [
+                    "--config",
+                    "profile.dev.debug=true",
+                    "--config",
+                    "profile.release.debug=true",
+                ]
+                .map(String::from),
+            );
+        }
+        args
+    }
+
+    /// Validate the relationships between arguments that `clap` can't express, once any defaults
+    /// have been applied.
+    pub fn validate(&self) -> Result<(), String> {
+        if let (Some(startup_timeout), Some(timeout)) = (self.startup_timeout, self.timeout)
+            && startup_timeout > timeout
+        {
+            return Err(format!(
+                "`--startup-timeout` ({}) must not exceed `--timeout` ({})",
+                humantime::format_duration(startup_timeout),
+                humantime::format_duration(timeout)
+            ));
+        }
+        if self.detach {
+            for (flag, stdio) in [
+                ("--stdin", &self.stdin),
+                ("--stdout", &self.stdout),
+                ("--stderr", &self.stderr),
+            ] {
+                if *stdio != Stdio::Null {
+                    return Err(format!(
+                        "`--detach` requires REAPER's stdio to be `null`, but `{flag}` is `{stdio}`"
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Split the arguments following the first `--` separator of a `run` invocation from the
+    /// command line arguments, so they can be forwarded to the REAPER executable verbatim rather
+    /// than being consumed as `CARGO_BUILD_ARGS`.
+    pub fn split_reaper_args(args: &mut Vec<String>) -> Vec<String> {
+        (args.get(1).map(String::as_str) == Some("run"))
+            .then(|| args.iter().position(|arg| arg == "--"))
+            .flatten()
+            .map(|separator| args.split_off(separator).split_off(1))
+            .unwrap_or_default()
+    }
+}
+
+/// Parses the path to a portable REAPER installation, which must contain a `reaper.ini` file.
+fn parse_portable_dir(dir: &str) -> Result<path::PathBuf, String> {
+    let dir = path::Path::new(dir)
+        .canonicalize()
+        .map_err(|err| format!("failed to canonicalize path `{dir}`: {err}"))?;
+    if !dir.join("reaper.ini").is_file() {
+        return Err(format!(
+            "`{}` is not a portable REAPER installation, `reaper.ini` was not found",
+            dir.display()
+        ));
+    }
+    Ok(dir)
+}
+
+/// Parse a REAPER project file passed to `--open`, which must be an existing regular file. The
+/// path is made absolute, since REAPER resolves relative paths against its own working directory.
+fn parse_project_file(project: &str) -> Result<path::PathBuf, String> {
+    let project = path::Path::new(project)
+        .canonicalize()
+        .map_err(|err| format!("project file `{project}` does not exist: {err}"))?;
+    if !project.is_file() {
+        return Err(format!(
+            "project file `{}` is not a regular file",
+            project.display()
+        ));
+    }
+    Ok(project)
+}
+
+/// Parse a project template file passed to `--template`, which must exist.
+fn parse_template_file(template: &str) -> Result<path::PathBuf, String> {
+    let template = path::PathBuf::from(template);
+    if !template.is_file() {
+        return Err(format!(
+            "template file `{}` does not exist",
+            template.display()
+        ));
+    }
+    Ok(template)
+}
+
 /// The type of template to use
 #[derive(Debug, Clone, clap::ValueEnum)]
 pub enum PluginTemplate {
@@ -224,18 +1162,397 @@ impl fmt::Display for ReaperBinaryPath<'_> {
     }
 }
 
-#[derive(Debug, Clone, Copy, ValueEnum)]
+/// A file that must contain some text once REAPER exits, i.e. `FILE:TEXT`.
+#[derive(Debug, Clone)]
+pub struct ConsoleExpectation {
+    pub file: path::PathBuf,
+    pub text: String,
+}
+impl std::str::FromStr for ConsoleExpectation {
+    type Err = String;
+
+    fn from_str(expectation: &str) -> Result<Self, Self::Err> {
+        // Skip the colon of a Windows drive letter, e.g. `C:\reaper.log:Hello, world!`.
+        let skip = match expectation.as_bytes() {
+            [drive, b':', b'\\' | b'/', ..] if drive.is_ascii_alphabetic() => 2,
+            _ => 0,
+        };
+        match expectation[skip..].split_once(':') {
+            Some((file, text)) if !text.is_empty() && skip + file.len() > 0 => Ok(Self {
+                file: expectation[..skip + file.len()].into(),
+                text: text.to_string(),
+            }),
+            _ => Err("expected `FILE:TEXT`, e.g. `console.log:Hello, world!`".to_string()),
+        }
+    }
+}
+
+/// An environment variable set on a child process, i.e. `KEY=VALUE`.
+#[derive(Debug, Clone)]
+pub struct EnvVar {
+    pub key: String,
+    pub value: String,
+}
+impl std::str::FromStr for EnvVar {
+    type Err = String;
+
+    fn from_str(env_var: &str) -> Result<Self, Self::Err> {
+        // Only the first `=` separates the key, e.g. `FLAGS=a=1` sets `FLAGS` to `a=1`.
+        match env_var.split_once('=') {
+            Some((key, value)) if !key.is_empty() && !key.contains('\0') => Ok(Self {
+                key: key.to_string(),
+                value: value.to_string(),
+            }),
+            _ => Err("expected `KEY=VALUE`, e.g. `RUST_LOG=debug`".to_string()),
+        }
+    }
+}
+
+/// A glob pattern matched against plugin keys, e.g. `reaper_midi*`. `*` matches any sequence of
+/// characters, `?` any single character, `[...]` any character in the brackets (`[!...]` any
+/// character not in them), and `{a,b}` either of the comma-separated patterns.
+#[derive(Debug, Clone)]
+pub struct PluginPattern {
+    pattern: String,
+    matcher: globset::GlobMatcher,
+}
+impl PluginPattern {
+    /// Whether the plugin `key` matches the pattern.
+    pub fn matches(&self, key: &str) -> bool {
+        self.matcher.is_match(key)
+    }
+
+    /// Whether the pattern contains wildcards, rather than being a plugin key.
+    pub fn is_glob(&self) -> bool {
+        self.pattern.contains(['*', '?', '[', '{'])
+    }
+}
+impl std::str::FromStr for PluginPattern {
+    type Err = String;
+
+    fn from_str(pattern: &str) -> Result<Self, Self::Err> {
+        Ok(Self {
+            pattern: pattern.to_string(),
+            matcher: globset::Glob::new(pattern)
+                .map_err(|err| err.to_string())?
+                .compile_matcher(),
+        })
+    }
+}
+impl fmt::Display for PluginPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.pattern)
+    }
+}
+
+/// A REAPER version, e.g. `7.03` or `v6.83+dev1234`, compared by its dot-separated numeric
+/// components, ignoring a leading `v` and any trailing suffix. Missing components compare as
+/// zero, so `7` equals `7.0`.
+#[derive(Debug, Clone)]
+pub struct ReaperVersion {
+    components: Vec<u32>,
+    version: String,
+}
+impl std::str::FromStr for ReaperVersion {
+    type Err = String;
+
+    fn from_str(version: &str) -> Result<Self, Self::Err> {
+        let version = version.trim().trim_start_matches(['v', 'V']);
+        let numeric = &version[..version
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(version.len())];
+        let components = numeric
+            .trim_end_matches('.')
+            .split('.')
+            .map(|component| component.parse::<u32>().ok())
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| format!("expected a version like `7.03`, found `{version}`"))?;
+        Ok(Self {
+            components,
+            version: version.to_string(),
+        })
+    }
+}
+impl Ord for ReaperVersion {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        let len = self.components.len().max(other.components.len());
+        let padded = |components: &[u32]| {
+            (0..len)
+                .map(|i| components.get(i).copied().unwrap_or(0))
+                .collect::<Vec<_>>()
+        };
+        padded(&self.components).cmp(&padded(&other.components))
+    }
+}
+impl PartialOrd for ReaperVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl PartialEq for ReaperVersion {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+impl Eq for ReaperVersion {}
+impl fmt::Display for ReaperVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.version)
+    }
+}
+
+/// The screen geometry of a virtual display, i.e. `WIDTHxHEIGHTxDEPTH`.
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone, Copy)]
+pub struct Screen {
+    pub width: u32,
+    pub height: u32,
+    pub depth: u32,
+}
+#[cfg(target_os = "linux")]
+impl std::str::FromStr for Screen {
+    type Err = String;
+
+    fn from_str(screen: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("expected `WIDTHxHEIGHTxDEPTH`, e.g. `{DEFAULT_XSERVER_SCREEN}`");
+        let mut dimensions = screen.split('x').map(|dimension| {
+            dimension
+                .parse::<u32>()
+                .ok()
+                .filter(|dimension| *dimension > 0)
+        });
+        match (
+            dimensions.next(),
+            dimensions.next(),
+            dimensions.next(),
+            dimensions.next(),
+        ) {
+            (Some(Some(width)), Some(Some(height)), Some(Some(depth)), None) => Ok(Self {
+                width,
+                height,
+                depth,
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
+#[cfg(target_os = "linux")]
+impl fmt::Display for Screen {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}x{}", self.width, self.height, self.depth)
+    }
+}
+
+/// The display server used for running REAPER in a headless environment.
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Backend {
+    /// A virtual framebuffer X server
+    Xvfb,
+
+    /// A nested X server, rendering into a visible window on the host display
+    Xephyr,
+
+    /// A headless Wayland compositor, running Xwayland
+    Weston,
+}
+
+/// The debugger REAPER is run under by `cargo reaper run --debugger`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Debugger {
+    /// `lldb` on macOS and `gdb` elsewhere, whichever is found on `$PATH`
+    Auto,
+
+    /// The GNU debugger
+    Gdb,
+
+    /// The LLVM debugger
+    Lldb,
+}
+
+/// The sanitizer plugin(s) are built with by `cargo reaper run --sanitizer`.
+#[cfg(unix)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Sanitizer {
+    /// AddressSanitizer, detecting out-of-bounds accesses, use-after-free and leaks
+    Address,
+}
+
+/// The format of the messages printed by `cargo reaper run`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum MessageFormat {
+    /// Human-readable messages
+    Human,
+
+    /// Newline-delimited JSON lifecycle events on stdout, and human-readable messages on stderr
+    Json,
+}
+
+/// The format plugin(s) are printed in by `cargo reaper list`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ListFormat {
+    /// Human-readable plugin information, followed by the detected REAPER executable
+    Human,
+
+    /// A JSON array of plugin objects on stdout
+    Json,
+}
+
+/// The version control system a project is initialized with by `cargo reaper new`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Vcs {
+    /// A git repository, along with a `.gitignore` file
+    Git,
+
+    /// No version control system
+    None,
+}
+
+/// Configuration for a child process's standard I/O handle, i.e. `piped`, `inherit`, `null`, or
+/// `file:PATH` (`file+:PATH` to append to the file).
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Stdio {
     Piped,
     Inherit,
     Null,
+
+    /// A file, which is created or truncated when written to, unless `append` is set.
+    File {
+        path: path::PathBuf,
+        append: bool,
+    },
 }
-impl From<Stdio> for process::Stdio {
-    fn from(value: Stdio) -> Self {
-        match value {
-            Stdio::Piped => process::Stdio::piped(),
-            Stdio::Inherit => process::Stdio::inherit(),
-            Stdio::Null => process::Stdio::null(),
+impl Stdio {
+    /// The keywords a handle can be configured with, besides `file:PATH` and `file+:PATH`.
+    const KEYWORDS: [&str; 3] = ["piped", "inherit", "null"];
+
+    /// The handle for a child process's stdin, reading from the file of a `file:` handle.
+    pub fn input(&self) -> std::io::Result<process::Stdio> {
+        match self {
+            Self::File { path, .. } => std::fs::File::open(path)
+                .map(Into::into)
+                .map_err(|err| Self::open_error(path, err)),
+            stdio => stdio.output(),
         }
     }
+
+    /// The handle for a child process's stdout or stderr, creating (or truncating, unless
+    /// appending to) the file of a `file:` handle.
+    pub fn output(&self) -> std::io::Result<process::Stdio> {
+        match self {
+            Self::Piped => Ok(process::Stdio::piped()),
+            Self::Inherit => Ok(process::Stdio::inherit()),
+            Self::Null => Ok(process::Stdio::null()),
+            Self::File { path, append } => std::fs::OpenOptions::new()
+                .create(true)
+                .write(true)
+                .append(*append)
+                .truncate(!*append)
+                .open(path)
+                .map(Into::into)
+                .map_err(|err| Self::open_error(path, err)),
+        }
+    }
+
+    /// Add the path of a file that could not be opened to `err`.
+    fn open_error(path: &path::Path, err: std::io::Error) -> std::io::Error {
+        std::io::Error::new(
+            err.kind(),
+            format!("failed to open '{}': {err}", path.display()),
+        )
+    }
+}
+impl std::str::FromStr for Stdio {
+    type Err = String;
+
+    fn from_str(stdio: &str) -> Result<Self, Self::Err> {
+        let (path, append) = match stdio {
+            "piped" => return Ok(Self::Piped),
+            "inherit" => return Ok(Self::Inherit),
+            "null" => return Ok(Self::Null),
+            _ => match (stdio.strip_prefix("file:"), stdio.strip_prefix("file+:")) {
+                (Some(path), _) => (path, false),
+                (_, Some(path)) => (path, true),
+                _ => {
+                    return Err(
+                        "expected `piped`, `inherit`, `null`, `file:PATH` or `file+:PATH`"
+                            .to_string(),
+                    );
+                }
+            },
+        };
+        if path.is_empty() {
+            return Err("expected a path, e.g. `file:/tmp/reaper.log`".to_string());
+        }
+        let path = path::PathBuf::from(path);
+        // Fail before anything is spawned, rather than once the file is opened.
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+            && !parent.is_dir()
+        {
+            return Err(format!("directory '{}' does not exist", parent.display()));
+        }
+        Ok(Self::File { path, append })
+    }
+}
+impl fmt::Display for Stdio {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Piped => write!(f, "piped"),
+            Self::Inherit => write!(f, "inherit"),
+            Self::Null => write!(f, "null"),
+            Self::File {
+                path,
+                append: false,
+            } => write!(f, "file:{}", path.display()),
+            Self::File { path, append: true } => write!(f, "file+:{}", path.display()),
+        }
+    }
+}
+impl clap::builder::ValueParserFactory for Stdio {
+    type Parser = StdioValueParser;
+
+    fn value_parser() -> Self::Parser {
+        StdioValueParser
+    }
+}
+
+/// Parses a [`Stdio`], offering its keywords as possible values for help and shell completions.
+#[derive(Clone)]
+pub struct StdioValueParser;
+impl clap::builder::TypedValueParser for StdioValueParser {
+    type Value = Stdio;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &std::ffi::OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        value
+            .to_str()
+            .ok_or_else(|| "expected valid UTF-8".to_string())
+            .and_then(str::parse)
+            .map_err(|err| {
+                clap::Error::raw(
+                    clap::error::ErrorKind::ValueValidation,
+                    format!(
+                        "invalid value '{}' for '{}': {err}\n",
+                        value.to_string_lossy(),
+                        arg.map(ToString::to_string).unwrap_or_default()
+                    ),
+                )
+                .with_cmd(cmd)
+            })
+    }
+
+    fn possible_values(
+        &self,
+    ) -> Option<Box<dyn Iterator<Item = clap::builder::PossibleValue> + '_>> {
+        Some(Box::new(
+            Stdio::KEYWORDS
+                .into_iter()
+                .map(clap::builder::PossibleValue::new),
+        ))
+    }
 }
@@ -1,6 +1,12 @@
 pub(crate) mod build;
 pub(crate) mod clean;
+pub(crate) mod doctor;
+pub(crate) mod info;
 pub(crate) mod link;
 pub(crate) mod list;
 pub(crate) mod new;
 pub(crate) mod run;
+pub(crate) mod status;
+pub(crate) mod test;
+pub(crate) mod unlink;
+pub(crate) mod which;
@@ -1,24 +1,36 @@
-use std::{env, fs, process};
+use std::{env, fs, path, process};
 
 use crate::{
-    config::ReaperPluginConfig,
+    config::{ReaperPluginConfig, SymlinkStyle},
     error::TomlErrorEmitter,
     util::{
-        Colorize, TargetOs, find_project_root, os::symlink_plugin, rename_plugin, validate_plugin,
+        Colorize, Replace, TargetOs, find_project_root, inherit_stdout, os::symlink_plugin,
+        project_target_dir, rename_plugin, resource_dir, status_println, validate_plugin,
     },
 };
 
-/// Build a REAPER extension plugin.
-pub(crate) fn build(no_symlink: bool, args: Vec<String>) -> anyhow::Result<()> {
+/// Build REAPER extension plugin(s), returning the path of each plugin artifact that was built.
+/// Plugin(s) are symlinked by relative paths when `relative`, or when the configuration file's
+/// `symlink_style` is `relative`.
+pub(crate) fn build(
+    no_symlink: bool,
+    relative: bool,
+    portable: Option<&path::Path>,
+    args: Vec<String>,
+) -> anyhow::Result<Vec<path::PathBuf>> {
     let project_root = find_project_root()?;
     let config = ReaperPluginConfig::load(&project_root)?;
+    let relative = relative || config.symlink_style() == SymlinkStyle::Relative;
     let mut emitter = TomlErrorEmitter::<String, String>::new();
 
+    // A relative `CARGO_TARGET_DIR` is resolved against the project root, like everywhere else.
+    let target_dir = env::var_os("CARGO_TARGET_DIR").map(|_| project_target_dir(&project_root));
     match process::Command::new("cargo")
         .arg("build")
         .args(&args)
+        .envs(target_dir.map(|target_dir| ("CARGO_TARGET_DIR", target_dir)))
         .stdin(process::Stdio::inherit())
-        .stdout(process::Stdio::inherit())
+        .stdout(inherit_stdout())
         .stderr(process::Stdio::inherit())
         .status()
         .map_err(|err| err.into())
@@ -40,6 +52,7 @@ pub(crate) fn build(no_symlink: bool, args: Vec<String>) -> anyhow::Result<()> {
                 .and_then(TargetOs::from_triple)
                 .unwrap_or_else(TargetOs::host);
 
+            let mut plugins = Vec::with_capacity(config.extension_plugins().len());
             for (to_plugin_file_name, plugin_manifest_dir) in config.extension_plugins().iter() {
                 let manifest_file = plugin_manifest_dir.get_ref().join("Cargo.toml");
                 let manifest_file_content = fs::read_to_string(&manifest_file).map_err(|err| {
@@ -57,7 +70,13 @@ pub(crate) fn build(no_symlink: bool, args: Vec<String>) -> anyhow::Result<()> {
                     &manifest_file,
                     &manifest_file_content,
                 )?;
+                plugins.push((to_plugin_file_name, manifest));
+            }
+            emitter.emit()?;
 
+            let mut artifacts = Vec::with_capacity(plugins.len());
+            for (to_plugin_file_name, manifest) in plugins {
+                // Validation errors have already been emitted, so the library name is present
                 let lib_name = manifest
                     .into_inner()
                     .lib
@@ -74,9 +93,10 @@ pub(crate) fn build(no_symlink: bool, args: Vec<String>) -> anyhow::Result<()> {
                 // Cross builds land in target/{triple}/{profile}/; native in target/{profile}/
                 let profile_path = target_triple
                     .iter()
-                    .fold(project_root.join("target"), |plugin_path, target_triple| {
-                        plugin_path.join(target_triple)
-                    })
+                    .fold(
+                        project_target_dir(&project_root),
+                        |plugin_path, target_triple| plugin_path.join(target_triple),
+                    )
                     .join(profile);
                 let plugin_path = profile_path.join(&*from_lib_file_name);
 
@@ -84,23 +104,29 @@ pub(crate) fn build(no_symlink: bool, args: Vec<String>) -> anyhow::Result<()> {
                     let plugin_path =
                         rename_plugin(&plugin_path, profile_path.join(to_lib_name_with_ext))?;
                     if target_triple.is_some() {
-                        println!(
+                        status_println!(
                             "{}: skipping symlink — cross compilation target specified ({})",
                             "warning".yellow().bold(),
                             plugin_path.display()
                         );
                     } else if !no_symlink {
-                        symlink_plugin(&plugin_path)?;
+                        symlink_plugin(
+                            &plugin_path,
+                            &resource_dir(portable)?.join("UserPlugins"),
+                            relative,
+                            Replace::Symlinks,
+                        )?;
                     } else {
-                        println!(
+                        status_println!(
                             "{}: plugin was not symlinked ({})",
                             "warning".yellow().bold(),
                             plugin_path.display()
                         );
                     }
+                    artifacts.push(plugin_path);
                 }
             }
-            Ok(())
+            Ok(artifacts)
         }
         Ok(status) => {
             process::exit(status.code().unwrap_or(1));
@@ -1,30 +1,114 @@
-use std::{collections, fs, path, process};
+use std::{
+    collections, env, fs,
+    io::{self, IsTerminal, Write},
+    path, process, sync,
+};
 
 use crate::{
+    cli::{ListFormat, PluginPattern},
     config::ReaperPluginConfig,
     error::TomlErrorEmitter,
-    util::{Colorize, TargetOs, find_project_root, os::remove_plugin_symlink},
+    registry::{self, Registry},
+    util::{
+        self, Colorize, TargetOs, find_project_root, format_size, orphaned_symlinks,
+        plugin_artifacts, project_target_dir, read_link_target, remove_plugin_symlink,
+        status_println, unconfigured_symlinks,
+    },
 };
 
-/// Remove extension plugins from the `UserPlugins` directory.
+/// The actions `cargo reaper clean` planned, or took along with their outcome, printed to stdout
+/// as a JSON object by `--format json`.
+struct Report {
+    dry_run: bool,
+    actions: Vec<serde_json::Value>,
+}
+impl Report {
+    /// A report to print instead of the human-readable output when `format` is JSON, in which
+    /// case stdout is reserved for it.
+    fn for_format(format: ListFormat, dry_run: bool) -> Option<Self> {
+        if format == ListFormat::Human {
+            return None;
+        }
+        util::JSON_MESSAGE_FORMAT.store(true, sync::atomic::Ordering::Relaxed);
+        Some(Self {
+            dry_run,
+            actions: Vec::new(),
+        })
+    }
+
+    /// Record an `action` (e.g. `{ "action": "remove-symlink", "path": ... }`), annotated with
+    /// its `outcome`, which is only planned when dry running unless it already failed.
+    fn record(&mut self, mut action: serde_json::Value, outcome: Result<(), String>) {
+        action["status"] = match (&outcome, self.dry_run) {
+            (Ok(()), true) => "planned",
+            (Ok(()), false) => "succeeded",
+            (Err(_), _) => "failed",
+        }
+        .into();
+        if let Err(err) = outcome {
+            action["error"] = err.into();
+        }
+        self.actions.push(action);
+    }
+
+    /// Print the report to stdout, along with the total `freed` size in bytes, if known.
+    fn print(self, freed: Option<u64>) {
+        println!(
+            "{}",
+            serde_json::json!({
+                "dry_run": self.dry_run,
+                "actions": self.actions,
+                "freed": freed,
+            })
+        );
+    }
+}
+
+/// Remove extension plugins from the `UserPlugins` directory, and their artifacts when passed
+/// `--remove-artifacts`. Everything that will be removed is resolved first, so that it can be
+/// listed when asking for confirmation before removing artifacts, unless passed `--yes`.
+///
+/// Files of the plugin(s) with the extension of another operating system (e.g. `.dll` on Linux)
+/// are only removed when passed `--all-extensions`, and are otherwise reported.
+///
+/// When `format` is JSON, the human-readable output is suppressed and the planned actions are
+/// printed as a JSON object instead, annotated with their outcome unless passed `--dry-run`.
+///
+/// Removals that fail are reported without stopping the remaining ones, and their number is
+/// returned, see `--strict`.
 pub(crate) fn clean(
-    plugins: &[String],
+    plugins: &[PluginPattern],
     dry_run: bool,
     remove_artifacts: bool,
-) -> anyhow::Result<()> {
+    all_extensions: bool,
+    yes: bool,
+    format: ListFormat,
+    user_plugins_dirs: &[path::PathBuf],
+) -> anyhow::Result<usize> {
+    let mut report = Report::for_format(format, dry_run);
     let project_root = find_project_root()?;
     let config = ReaperPluginConfig::load(&project_root)?;
     let mut emitter = TomlErrorEmitter::<String, String>::new();
 
     let plugins: collections::HashMap<String, path::PathBuf> = if !plugins.is_empty() {
-        let mut map = config.extension_plugins().to_owned();
-        map.retain(|k, _| plugins.contains(k.as_ref()));
-        if map.is_empty() {
-            anyhow::bail!(
-                "The following plugin(s) were not found: {}\n\nTip: run `cargo reaper list` to view the available plugins.",
-                plugins.join(", ")
-            )
+        let mut keys = config
+            .extension_plugins()
+            .keys()
+            .map(|plugin_name| plugin_name.as_ref().as_str())
+            .collect::<Vec<_>>();
+        keys.sort();
+        let expansions = expand_plugin_patterns(plugins, &keys)?;
+        if dry_run && report.is_none() {
+            for (plugin, matched) in &expansions {
+                println!(
+                    "     {} `{plugin}` -> {}",
+                    "Matched".green().bold(),
+                    matched.join(", ")
+                );
+            }
         }
+        let mut map = config.extension_plugins().to_owned();
+        map.retain(|k, _| plugins.iter().any(|plugin| plugin.matches(k.as_ref())));
         map.into_iter()
             .map(|(key, val)| (key.into_inner(), val.into_inner()))
             .collect()
@@ -36,30 +120,8 @@ pub(crate) fn clean(
             .map(|(key, val)| (key.into_inner(), val.into_inner()))
             .collect()
     };
-    let mut removal_failures = 0;
-    for plugin_name in plugins.keys() {
-        println!("    {} {}", "Removing".magenta().bold(), plugin_name);
-        if let Err(err) = remove_plugin_symlink(
-            plugin_name,
-            &TargetOs::add_plugin_ext(&TargetOs::host(), plugin_name),
-            dry_run,
-        ) {
-            removal_failures += 1;
-            eprintln!("{}: {err}", "error (benign)".magenta());
-        }
-    }
-    println!(
-        "     {} {} symlink(s)",
-        if dry_run {
-            "Summary".green().bold()
-        } else {
-            "Removed".green().bold()
-        },
-        plugins.len() - removal_failures
-    );
-
-    if remove_artifacts {
-        let mut package_args: Vec<String> = Vec::with_capacity(plugins.len());
+    let (renamed_artifacts, packages) = if remove_artifacts {
+        let mut packages: Vec<String> = Vec::with_capacity(plugins.len());
         for (plugin_name, manifest_dir) in plugins.iter() {
             let manifest_file = manifest_dir.join("Cargo.toml");
             let manifest_file_content = fs::read_to_string(&manifest_file).map_err(|err| {
@@ -82,7 +144,23 @@ pub(crate) fn clean(
                 .as_mut()
                 .complete_from_path_and_workspace::<cargo_toml::Value>(&manifest_file, None);
             if let Some(package) = manifest.as_ref().package.as_ref() {
-                package_args.extend(["-p".into(), package.name.clone()]);
+                packages.push(package.name.clone());
+            } else if manifest.as_ref().workspace.is_some()
+                && let Some(package) = virtual_workspace_package(&manifest_file, plugin_name)
+            {
+                packages.push(package);
+            } else if manifest.as_ref().workspace.is_some() {
+                emitter.insert_err(
+                    manifest_file.to_string_lossy().to_string(),
+                    manifest_file_content,
+                    format!("`{}` is not a package", plugin_name),
+                    manifest.span(),
+                    Some("expected manifest path to a package containing a dynamic library target"),
+                    None,
+                    Some(
+                        "help: no member of this virtual workspace could be identified as the owner of the plugin's library, try pointing the plugin at the member's directory instead",
+                    ),
+                );
             } else {
                 emitter.insert_err(
                     manifest_file.to_string_lossy().to_string(),
@@ -98,24 +176,666 @@ pub(crate) fn clean(
             }
         }
         emitter.emit()?;
+        packages.sort();
+        packages.dedup();
+        (renamed_artifacts(&project_root, plugins.keys()), packages)
+    } else {
+        (Vec::new(), Vec::new())
+    };
+    let foreign_files = foreign_plugin_files(plugins.keys(), user_plugins_dirs);
+    if !all_extensions {
+        for file in &foreign_files {
+            status_println!(
+                "{}: '{}' has the extension of another operating system, pass `--all-extensions` to remove it",
+                "warning".yellow().bold(),
+                file.display()
+            );
+        }
+    }
+
+    if remove_artifacts && !dry_run && !yes {
+        require_terminal("`cargo reaper clean --remove-artifacts`")?;
+        let mut plugin_names = plugins.keys().collect::<Vec<_>>();
+        plugin_names.sort();
+        status_println!("The following will be removed:");
+        for symlink_path in plugin_names
+            .into_iter()
+            .flat_map(|plugin_name| {
+                user_plugins_dirs.iter().map(|user_plugins_dir| {
+                    user_plugins_dir.join(TargetOs::host().add_plugin_ext(plugin_name))
+                })
+            })
+            .filter(|symlink_path| symlink_path.is_symlink())
+        {
+            status_println!("  {}", symlink_path.display());
+        }
+        for file in foreign_files.iter().filter(|_| all_extensions) {
+            status_println!("  {}", file.display());
+        }
+        for artifact in &renamed_artifacts {
+            status_println!("  {}", artifact.display());
+        }
+        status_println!(
+            "  the artifacts of the package(s) cleaned by `cargo clean`: {}",
+            packages.join(", ")
+        );
+        if !confirm("Remove the symlinks and artifacts above?")? {
+            anyhow::bail!("nothing was removed");
+        }
+    }
+
+    let mut plugin_names = plugins.keys().collect::<Vec<_>>();
+    plugin_names.sort();
+    let mut symlinks_removed = 0;
+    let mut failures = 0;
+    for plugin_name in plugin_names {
+        if report.is_none() && user_plugins_dirs.len() == 1 {
+            println!("    {} {}", "Removing".magenta().bold(), plugin_name);
+        }
+        let plugin_file_name = TargetOs::add_plugin_ext(&TargetOs::host(), plugin_name);
+        // A symlink missing from every directory isn't an action, but is still reported as a
+        // benign error.
+        let removals =
+            remove_plugin_symlink(plugin_name, &plugin_file_name, user_plugins_dirs, dry_run)
+                .unwrap_or_else(|err| {
+                    failures += 1;
+                    eprintln!("{}: {err}", "error (benign)".magenta());
+                    Vec::new()
+                });
+        for (symlink_path, outcome) in removals {
+            if report.is_none() && user_plugins_dirs.len() > 1 {
+                println!(
+                    "    {} {plugin_name} ({})",
+                    "Removing".magenta().bold(),
+                    symlink_path.display()
+                );
+            }
+            match &outcome {
+                Ok(()) => symlinks_removed += 1,
+                Err(err) => {
+                    failures += 1;
+                    eprintln!("{}: {err}", "error".magenta());
+                }
+            }
+            if let Some(report) = &mut report {
+                report.record(
+                    serde_json::json!({ "action": "remove-symlink", "path": symlink_path }),
+                    outcome.map_err(|err| format!("{err:#}")),
+                );
+            }
+        }
+    }
+    let mut summary = format!("{symlinks_removed} symlink(s)");
+    if all_extensions {
+        let foreign_files_removed =
+            remove_foreign_plugin_files(&foreign_files, dry_run, report.as_mut());
+        failures += foreign_files.len() - foreign_files_removed;
+        summary.push_str(&format!(", {foreign_files_removed} foreign file(s)"));
+    }
 
-        let mut cargo = process::Command::new("cargo");
-        let mut cargo_clean = cargo
-            .arg("clean")
-            .args(&package_args)
-            .stdin(process::Stdio::inherit())
-            .stdout(process::Stdio::inherit())
-            .stderr(process::Stdio::inherit());
+    let mut freed = None;
+    if remove_artifacts {
+        let (artifacts_removed, mut size) =
+            remove_renamed_artifacts(&renamed_artifacts, dry_run, report.as_mut());
+        failures += renamed_artifacts.len() - artifacts_removed;
+        summary.push_str(&format!(", {artifacts_removed} artifact(s)"));
+        let (cargo_summary, cargo_succeeded) =
+            cargo_clean(&project_root, &packages, dry_run, report.as_mut())?;
+        if !cargo_succeeded {
+            failures += 1;
+        }
+        if let Some((files, cargo_size)) = cargo_summary {
+            summary.push_str(&format!(", {files} file(s) with `cargo clean`"));
+            size += cargo_size;
+        }
+        summary.push_str(&format!(" ({})", format_size(size)));
+        freed = Some(size);
+    }
+    if let Some(report) = report {
+        report.print(freed);
+        return Ok(failures);
+    }
+    println!(
+        "     {} {summary}",
         if dry_run {
-            cargo_clean = cargo_clean.arg("--dry-run");
+            "Summary".green().bold()
+        } else {
+            "Removed".green().bold()
+        },
+    );
+    if dry_run {
+        println!(
+            "{}: no files deleted due to --dry-run",
+            "warning".yellow().bold()
+        );
+    }
+
+    Ok(failures)
+}
+
+/// The member of the virtual workspace at `manifest_file` that owns the library target of the
+/// plugin named `plugin_name`, as listed by `cargo metadata`: the only member with a dynamic
+/// library target, or else the one whose package or library name matches the plugin key without
+/// its `reaper_` prefix.
+fn virtual_workspace_package(manifest_file: &path::Path, plugin_name: &str) -> Option<String> {
+    let output = process::Command::new("cargo")
+        .args([
+            "metadata",
+            "--no-deps",
+            "--format-version",
+            "1",
+            "--manifest-path",
+        ])
+        .arg(manifest_file)
+        .stderr(process::Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let metadata = serde_json::from_slice::<serde_json::Value>(&output.stdout).ok()?;
+    let candidates = metadata["packages"]
+        .as_array()?
+        .iter()
+        .filter_map(|package| {
+            let lib = package["targets"].as_array()?.iter().find(|target| {
+                target["crate_types"]
+                    .as_array()
+                    .is_some_and(|crate_types| crate_types.iter().any(|ty| ty == "cdylib"))
+            })?;
+            Some((package["name"].as_str()?, lib["name"].as_str()?))
+        })
+        .collect::<Vec<_>>();
+    if let [(package, _)] = candidates.as_slice() {
+        return Some(package.to_string());
+    }
+
+    // e.g. `reaper_my_plugin` matches the `my-plugin` package, or the `_my_plugin` library.
+    let normalize = |name: &str| name.replace('-', "_").trim_matches('_').to_string();
+    let plugin_name = normalize(plugin_name.strip_prefix("reaper_").unwrap_or(plugin_name));
+    let mut matching = candidates.into_iter().filter(|(package, lib)| {
+        normalize(package) == plugin_name || normalize(lib) == plugin_name
+    });
+    match (matching.next(), matching.next()) {
+        (Some((package, _)), None) => Some(package.to_string()),
+        _ => None,
+    }
+}
+
+/// The files of each of the `plugin_names` in the `user_plugins_dirs` with the extension of another
+/// operating system than the host (e.g. `reaper_my_plugin.dll` on Linux), such as cross compiled
+/// plugins copied there manually, sorted by path.
+fn foreign_plugin_files<'a>(
+    plugin_names: impl Iterator<Item = &'a String>,
+    user_plugins_dirs: &[path::PathBuf],
+) -> Vec<path::PathBuf> {
+    let mut files = plugin_names
+        .flat_map(|plugin_name| {
+            TargetOs::ALL
+                .into_iter()
+                .filter(|target_os| *target_os != TargetOs::host())
+                .flat_map(move |target_os| {
+                    user_plugins_dirs
+                        .iter()
+                        .map(move |dir| dir.join(target_os.add_plugin_ext(plugin_name)))
+                })
+        })
+        .filter(|file| file.symlink_metadata().is_ok())
+        .collect::<Vec<_>>();
+    files.sort();
+    files
+}
+
+/// Remove the `files` with foreign extensions, see [`foreign_plugin_files`], returning how many of
+/// them were removed.
+fn remove_foreign_plugin_files(
+    files: &[path::PathBuf],
+    dry_run: bool,
+    mut report: Option<&mut Report>,
+) -> usize {
+    let mut removed = Vec::with_capacity(files.len());
+    for file in files {
+        if report.is_none() {
+            println!("    {} {}", "Removing".magenta().bold(), file.display());
+        }
+        let is_symlink = file.is_symlink();
+        let outcome = if dry_run {
+            Ok(())
+        } else {
+            fs::remove_file(file)
+        };
+        match &outcome {
+            Ok(()) => removed.push(file.as_path()),
+            Err(err) => eprintln!(
+                "{}: failed to remove '{}':\n{err:#?}",
+                "error".magenta(),
+                file.display()
+            ),
+        }
+        if let Some(report) = report.as_deref_mut() {
+            report.record(
+                serde_json::json!({
+                    "action": if is_symlink { "remove-symlink" } else { "remove-file" },
+                    "path": file,
+                }),
+                outcome.map_err(|err| err.to_string()),
+            );
+        }
+    }
+    if !dry_run {
+        registry::forget(removed.iter().copied());
+    }
+    removed.len()
+}
+
+/// The artifacts `cargo-reaper` renamed for each of the `plugin_names` (e.g.
+/// `reaper_my_plugin.so`) in every profile directory of Cargo's target directory, including those
+/// of cross compilation targets, which `cargo clean -p` doesn't know about.
+fn renamed_artifacts<'a>(
+    project_root: &path::Path,
+    plugin_names: impl Iterator<Item = &'a String>,
+) -> Vec<path::PathBuf> {
+    let target_dir = project_target_dir(project_root);
+    let mut artifacts = plugin_names
+        .flat_map(|plugin_name| {
+            TargetOs::ALL.into_iter().flat_map(|target_os| {
+                plugin_artifacts(&target_dir, &target_os.add_plugin_ext(plugin_name))
+            })
+        })
+        .collect::<Vec<_>>();
+    artifacts.sort();
+    artifacts
+}
+
+/// Remove the renamed `artifacts`, see [`renamed_artifacts`], returning how many of them were
+/// removed and their total size in bytes.
+fn remove_renamed_artifacts(
+    artifacts: &[path::PathBuf],
+    dry_run: bool,
+    mut report: Option<&mut Report>,
+) -> (usize, u64) {
+    let mut removed = 0;
+    let mut size = 0;
+    for artifact in artifacts {
+        if report.is_none() {
+            println!("    {} {}", "Removing".magenta().bold(), artifact.display());
+        }
+        let artifact_size = fs::metadata(artifact).map_or(0, |metadata| metadata.len());
+        let outcome = if dry_run {
+            Ok(())
+        } else {
+            fs::remove_file(artifact)
+        };
+        if let Err(err) = &outcome {
+            eprintln!(
+                "{}: failed to remove '{}':\n{err:#?}",
+                "error".magenta(),
+                artifact.display()
+            );
+        }
+        let removed_artifact = outcome.is_ok();
+        if let Some(report) = report.as_deref_mut() {
+            report.record(
+                serde_json::json!({
+                    "action": "remove-artifact",
+                    "path": artifact,
+                    "size": artifact_size,
+                }),
+                outcome.map_err(|err| err.to_string()),
+            );
+        }
+        if !removed_artifact {
+            continue;
+        }
+        removed += 1;
+        size += artifact_size;
+    }
+    (removed, size)
+}
+
+/// Run `cargo clean` for each of the `packages`, forwarding its output, and return the number of
+/// files it removed and their total size in bytes, as reported in its summary line (e.g.
+/// `Removed 42 files, 1.5MiB total`) if it could be parsed, along with whether it succeeded.
+fn cargo_clean(
+    project_root: &path::Path,
+    packages: &[String],
+    dry_run: bool,
+    report: Option<&mut Report>,
+) -> anyhow::Result<(Option<(u64, u64)>, bool)> {
+    let mut cargo = process::Command::new("cargo");
+    let mut cargo_clean = cargo
+        .arg("clean")
+        .args(packages.iter().flat_map(|package| ["-p", package]))
+        .envs(
+            env::var_os("CARGO_TARGET_DIR")
+                .map(|_| ("CARGO_TARGET_DIR", project_target_dir(project_root))),
+        )
+        .stdin(process::Stdio::inherit())
+        .stdout(util::inherit_stdout())
+        .stderr(process::Stdio::piped());
+    if dry_run {
+        cargo_clean = cargo_clean.arg("--dry-run");
+    }
+    let output = cargo_clean.output()?;
+    if let Some(report) = report {
+        for package in packages {
+            report.record(
+                serde_json::json!({ "action": "cargo-clean-package", "package": package }),
+                if output.status.success() {
+                    Ok(())
+                } else {
+                    Err(format!("`cargo clean` failed ({})", output.status))
+                },
+            );
+        }
+    }
+
+    let mut summary = None;
+    for line in String::from_utf8_lossy(&output.stderr).lines() {
+        // e.g. `     Removed 42 files, 1.5MiB total`, or `     Summary ...` for `--dry-run`.
+        let parsed = line
+            .trim_start()
+            .strip_prefix(if dry_run { "Summary " } else { "Removed " })
+            .and_then(|rest| rest.split_once(", "))
+            .and_then(|(files, size)| {
+                let files = files.split_whitespace().next()?.parse().ok()?;
+                let size = parse_size(size.strip_suffix(" total")?)?;
+                Some((files, size))
+            });
+        match parsed {
+            Some(parsed) => summary = Some(parsed),
+            // Cargo's own `--dry-run` warning is replaced by the one printed after the summary.
+            None if dry_run && line.contains("no files deleted due to --dry-run") => {}
+            None => eprintln!("{line}"),
+        }
+    }
+    Ok((summary, output.status.success()))
+}
+
+/// Parse a size printed by Cargo, e.g. `1.5MiB`, in bytes.
+fn parse_size(size: &str) -> Option<u64> {
+    const UNITS: &[(&str, u64); 5] = &[
+        ("TiB", 1 << 40),
+        ("GiB", 1 << 30),
+        ("MiB", 1 << 20),
+        ("KiB", 1 << 10),
+        ("B", 1),
+    ];
+
+    UNITS.iter().find_map(|(unit, multiplier)| {
+        let value: f64 = size.strip_suffix(unit)?.trim().parse().ok()?;
+        Some((value * *multiplier as f64) as u64)
+    })
+}
+
+/// Remove the symlinks in the `UserPlugins` directory whose targets no longer exist, e.g. those
+/// left behind by renamed plugin keys or a removed target directory. Only the symlinks pointing
+/// into the project's target directory are removed, unless passed `--all-orphans`, in which case
+/// every dangling `reaper_*` symlink is removed once confirmed.
+pub(crate) fn clean_orphans(
+    all: bool,
+    dry_run: bool,
+    yes: bool,
+    format: ListFormat,
+    user_plugins_dirs: &[path::PathBuf],
+) -> anyhow::Result<usize> {
+    let report = Report::for_format(format, dry_run);
+    let target_dir = if all {
+        None
+    } else {
+        Some(project_target_dir(&find_project_root()?))
+    };
+    let orphans = user_plugins_dirs
+        .iter()
+        .flat_map(|user_plugins_dir| orphaned_symlinks(user_plugins_dir, target_dir.as_deref()))
+        .collect::<Vec<_>>();
+    if all && !dry_run && !yes && !orphans.is_empty() {
+        require_terminal("`cargo reaper clean --all-orphans`")?;
+        for (symlink_path, target) in &orphans {
+            status_println!("{} -> {}", symlink_path.display(), target.display());
+        }
+        if !confirm(&format!(
+            "Remove {} dangling symlink(s) from {}?",
+            orphans.len(),
+            user_plugins_dirs
+                .iter()
+                .map(|user_plugins_dir| format!("'{}'", user_plugins_dir.display()))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))? {
+            anyhow::bail!("no symlinks were removed");
+        }
+    }
+
+    remove_symlinks(&orphans, "orphaned", dry_run, report)
+}
+
+/// Remove the symlinks in the `UserPlugins` directory that still resolve into the project's target
+/// directory, but whose file names no longer correspond to any configured plugin key, e.g. after
+/// removing a plugin from the configuration file. This complements `--orphans`, which removes the
+/// symlinks whose targets no longer exist.
+pub(crate) fn clean_stale(
+    dry_run: bool,
+    format: ListFormat,
+    user_plugins_dirs: &[path::PathBuf],
+) -> anyhow::Result<usize> {
+    let report = Report::for_format(format, dry_run);
+    let project_root = find_project_root()?;
+    let config = ReaperPluginConfig::load(&project_root)?;
+    let configured = config
+        .extension_plugins()
+        .keys()
+        .map(|plugin_name| TargetOs::host().add_plugin_ext(plugin_name.as_ref()))
+        .collect::<collections::HashSet<_>>();
+    let target_dir = project_target_dir(&project_root);
+    let stale = user_plugins_dirs
+        .iter()
+        .flat_map(|user_plugins_dir| {
+            unconfigured_symlinks(user_plugins_dir, &target_dir, &configured)
+        })
+        .collect::<Vec<_>>();
+    remove_symlinks(&stale, "stale", dry_run, report)
+}
+
+/// Remove the symlinks recorded in the registry of symlinks `cargo-reaper` has created, regardless
+/// of the project they were created from, once confirmed unless passed `--yes`. Registry entries
+/// whose symlinks no longer exist, or no longer point to the recorded target, are pruned.
+pub(crate) fn clean_all_projects(
+    dry_run: bool,
+    yes: bool,
+    format: ListFormat,
+) -> anyhow::Result<usize> {
+    let mut report = Report::for_format(format, dry_run);
+    let mut registry = Registry::load();
+    let (links, pruned): (Vec<_>, Vec<_>) =
+        registry.links().iter().cloned().partition(|link| {
+            read_link_target(&link.path).is_ok_and(|target| target == link.target)
+        });
+
+    for link in &pruned {
+        match &mut report {
+            Some(report) => report.record(
+                serde_json::json!({ "action": "prune-registry-entry", "path": link.path }),
+                Ok(()),
+            ),
+            None => println!(
+                "     {} registry entry {} (no longer exists, or points elsewhere)",
+                "Pruning".magenta().bold(),
+                link.path.display()
+            ),
+        }
+    }
+    if !dry_run {
+        registry.forget(pruned.iter().map(|link| link.path.as_path()));
+    }
+
+    if !dry_run && !yes && !links.is_empty() {
+        require_terminal("`cargo reaper clean --all-projects`")?;
+        for link in &links {
+            status_println!(
+                "{} -> {}{}",
+                link.path.display(),
+                link.target.display(),
+                link.project
+                    .as_ref()
+                    .map(|project| format!(" (from '{}')", project.display()))
+                    .unwrap_or_default()
+            );
+        }
+        if !confirm(&format!(
+            "Remove {} symlink(s) created by `cargo-reaper` for any project?",
+            links.len()
+        ))? {
+            anyhow::bail!("no symlinks were removed");
+        }
+    }
+
+    let symlinks = links
+        .into_iter()
+        .map(|link| (link.path, link.target))
+        .collect::<Vec<_>>();
+    remove_symlinks(&symlinks, "registered", dry_run, report)
+}
+
+/// Remove the `symlinks` (along with their targets) cleaned by `--orphans`, `--stale` or
+/// `--all-projects`, summarizing them as `kind` symlinks, or printing the `report` if any, and
+/// return the number of removals that failed.
+fn remove_symlinks(
+    symlinks: &[(path::PathBuf, path::PathBuf)],
+    kind: &str,
+    dry_run: bool,
+    mut report: Option<Report>,
+) -> anyhow::Result<usize> {
+    let mut removed = Vec::with_capacity(symlinks.len());
+    for (symlink_path, target) in symlinks {
+        if report.is_none() {
+            println!(
+                "    {} {} -> {}",
+                "Removing".magenta().bold(),
+                symlink_path.display(),
+                target.display()
+            );
         }
-        cargo_clean.status()?;
-    } else if dry_run {
+        let outcome = if dry_run {
+            Ok(())
+        } else {
+            fs::remove_file(symlink_path)
+        };
+        match &outcome {
+            Ok(()) => removed.push(symlink_path.as_path()),
+            Err(err) => eprintln!(
+                "{}: failed to remove symlink '{}':\n{err:#?}",
+                "error".magenta(),
+                symlink_path.display()
+            ),
+        }
+        if let Some(report) = &mut report {
+            report.record(
+                serde_json::json!({
+                    "action": "remove-symlink",
+                    "path": symlink_path,
+                    "target": target,
+                }),
+                outcome.map_err(|err| err.to_string()),
+            );
+        }
+    }
+    if !dry_run {
+        registry::forget(removed.iter().copied());
+    }
+    let failures = symlinks.len() - removed.len();
+    if let Some(report) = report {
+        report.print(None);
+        return Ok(failures);
+    }
+    println!(
+        "     {} {} {kind} symlink(s)",
+        if dry_run {
+            "Summary".green().bold()
+        } else {
+            "Removed".green().bold()
+        },
+        removed.len()
+    );
+    if dry_run {
         println!(
             "{}: no files deleted due to --dry-run",
             "warning".yellow().bold()
         );
     }
 
+    Ok(failures)
+}
+
+/// Return an error unless stdin is a terminal, which `operation` asks for confirmation on,
+/// explaining that `--yes` skips the confirmation.
+fn require_terminal(operation: &str) -> anyhow::Result<()> {
+    if !io::stdin().is_terminal() {
+        anyhow::bail!(
+            "{operation} asks for confirmation before removing anything, but stdin is not a terminal. Pass `--yes` to proceed without confirmation, or `--dry-run` to only display what would be removed"
+        );
+    }
     Ok(())
 }
+
+/// Ask the user to confirm `question` on stdin, prompting on stderr when stdout is reserved for
+/// JSON.
+fn confirm(question: &str) -> anyhow::Result<bool> {
+    if util::JSON_MESSAGE_FORMAT.load(sync::atomic::Ordering::Relaxed) {
+        eprint!("{question} [y/N] ");
+        io::stderr().flush()?;
+    } else {
+        print!("{question} [y/N] ");
+        io::stdout().flush()?;
+    }
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes" | "Yes"))
+}
+
+/// The `keys` each of the `patterns` matches, as resolved by `cargo reaper clean --plugin` and
+/// `cargo reaper unlink`, or an error listing the patterns that match none of them.
+pub(crate) fn expand_plugin_patterns<'a, 'k>(
+    patterns: &'a [PluginPattern],
+    keys: &[&'k str],
+) -> anyhow::Result<Vec<(&'a PluginPattern, Vec<&'k str>)>> {
+    let expansions = patterns
+        .iter()
+        .map(|pattern| {
+            let matched = keys
+                .iter()
+                .copied()
+                .filter(|key| pattern.matches(key))
+                .collect::<Vec<_>>();
+            (pattern, matched)
+        })
+        .collect::<Vec<_>>();
+    let not_found = expansions
+        .iter()
+        .filter(|(_, matched)| matched.is_empty())
+        .map(|(pattern, _)| *pattern)
+        .cloned()
+        .collect::<Vec<_>>();
+    if !not_found.is_empty() {
+        anyhow::bail!(
+            "The following plugin(s) were not found: {}\n\n{}",
+            not_found
+                .iter()
+                .map(PluginPattern::to_string)
+                .collect::<Vec<_>>()
+                .join(", "),
+            not_found_tip(&not_found)
+        )
+    }
+    Ok(expansions)
+}
+
+/// The tip printed when no plugin matches `patterns`, suggesting to loosen them when they contain
+/// wildcards.
+pub(crate) fn not_found_tip(patterns: &[PluginPattern]) -> &'static str {
+    if patterns.iter().any(PluginPattern::is_glob) {
+        "Tip: loosen the pattern, or run `cargo reaper list` to view the available plugins."
+    } else {
+        "Tip: run `cargo reaper list` to view the available plugins."
+    }
+}
@@ -0,0 +1,371 @@
+use std::{env, fs, path};
+
+use crate::{
+    command::run::resolve_reaper,
+    config::{CONFIG_FILE_NAMES, ReaperPluginConfig},
+    error::TomlErrorEmitter,
+    util::{self, Colorize, find_project_root, validate_plugin},
+};
+
+/// What the checks of `cargo reaper doctor` are run against.
+struct Environment {
+    /// The REAPER executable passed to `--exec`, if any.
+    reaper: Option<path::PathBuf>,
+
+    /// The portable REAPER installation passed to `--portable`, if any.
+    portable: Option<path::PathBuf>,
+}
+
+/// The outcome of a check.
+enum Outcome {
+    /// The check passed, with what was found, e.g. a resolved path.
+    Pass(String),
+
+    /// The check failed, with the reason and how to fix it.
+    Fail { reason: String, hint: String },
+
+    /// The check doesn't apply, e.g. because a check it depends on failed.
+    Skip(String),
+}
+
+/// A check run by `cargo reaper doctor`.
+struct Check {
+    name: &'static str,
+
+    /// Whether `cargo reaper doctor` exits with a non-zero status code when the check fails.
+    critical: bool,
+
+    run: fn(&Environment) -> Outcome,
+}
+
+/// The checks run by `cargo reaper doctor`, in order.
+const CHECKS: &[Check] = &[
+    Check {
+        name: "REAPER executable",
+        critical: true,
+        run: reaper_executable,
+    },
+    Check {
+        name: "REAPER version",
+        critical: false,
+        run: reaper_version,
+    },
+    Check {
+        name: "resource directory",
+        critical: true,
+        run: resource_directory,
+    },
+    Check {
+        name: "UserPlugins directory",
+        critical: true,
+        run: user_plugins_directory,
+    },
+    Check {
+        name: "symlinks",
+        critical: true,
+        run: symlinks,
+    },
+    #[cfg(target_os = "linux")]
+    Check {
+        name: "headless tooling",
+        critical: false,
+        run: headless_tooling,
+    },
+    Check {
+        name: "configuration file",
+        critical: true,
+        run: configuration_file,
+    },
+    Check {
+        name: "plugin manifests",
+        critical: true,
+        run: plugin_manifests,
+    },
+];
+
+/// The REAPER executable `cargo reaper run` launches can be resolved.
+fn reaper_executable(environment: &Environment) -> Outcome {
+    match resolve_reaper(environment.reaper.clone(), environment.portable.as_deref()) {
+        Ok((reaper, source)) if reaper.is_file() => {
+            Outcome::Pass(format!("{} ({source})", reaper.display()))
+        }
+        Ok((reaper, source)) => Outcome::Fail {
+            reason: format!("'{}' ({source}) does not exist", reaper.display()),
+            hint: "pass the path to an existing REAPER executable to `--exec`".to_string(),
+        },
+        Err(_) => Outcome::Fail {
+            reason: "unable to locate the REAPER executable".to_string(),
+            hint: "install REAPER from https://www.reaper.fm/download.php, add it to `$PATH`, or pass its path to `--exec`".to_string(),
+        },
+    }
+}
+
+/// The version of the REAPER executable can be detected, which `--min-reaper-version` requires.
+fn reaper_version(environment: &Environment) -> Outcome {
+    let Ok((reaper, _)) =
+        resolve_reaper(environment.reaper.clone(), environment.portable.as_deref())
+    else {
+        return Outcome::Skip("no REAPER executable".to_string());
+    };
+    match util::reaper_version(&reaper) {
+        Some(version) => Outcome::Pass(version.to_string()),
+        None => Outcome::Fail {
+            reason: format!("unable to determine the version of '{}'", reaper.display()),
+            hint: "`--min-reaper-version` can't be used with this executable".to_string(),
+        },
+    }
+}
+
+/// Whether a file can be created in `dir`.
+fn is_writable(dir: &path::Path) -> bool {
+    tempfile::NamedTempFile::new_in(dir).is_ok()
+}
+
+/// REAPER's resource directory exists and is writable.
+fn resource_directory(environment: &Environment) -> Outcome {
+    let resource_dir = match util::resource_dir(environment.portable.as_deref()) {
+        Ok(resource_dir) => resource_dir,
+        Err(err) => {
+            return Outcome::Fail {
+                reason: format!("{err:#}"),
+                hint: "launch REAPER once to initialize it".to_string(),
+            };
+        }
+    };
+    if !resource_dir.is_dir() {
+        Outcome::Fail {
+            reason: format!("'{}' does not exist", resource_dir.display()),
+            hint: "launch REAPER once to initialize it".to_string(),
+        }
+    } else if !is_writable(&resource_dir) {
+        Outcome::Fail {
+            reason: format!("'{}' is not writable", resource_dir.display()),
+            hint: "fix the permissions of the directory".to_string(),
+        }
+    } else {
+        Outcome::Pass(resource_dir.display().to_string())
+    }
+}
+
+/// The `UserPlugins` directory plugin(s) are symlinked to exists and is writable.
+fn user_plugins_directory(environment: &Environment) -> Outcome {
+    let Ok(resource_dir) = util::resource_dir(environment.portable.as_deref()) else {
+        return Outcome::Skip("no resource directory".to_string());
+    };
+    let user_plugins_dir = resource_dir.join("UserPlugins");
+    if !user_plugins_dir.is_dir() {
+        Outcome::Fail {
+            reason: format!("'{}' does not exist", user_plugins_dir.display()),
+            hint: "launch REAPER once to initialize it, or create the directory".to_string(),
+        }
+    } else if !is_writable(&user_plugins_dir) {
+        Outcome::Fail {
+            reason: format!("'{}' is not writable", user_plugins_dir.display()),
+            hint: "fix the permissions of the directory".to_string(),
+        }
+    } else {
+        Outcome::Pass(user_plugins_dir.display().to_string())
+    }
+}
+
+/// Symlinks can be created, which plugin(s) are installed with. This requires a privilege on
+/// Windows.
+fn symlinks(_: &Environment) -> Outcome {
+    let probe = tempfile::tempdir().and_then(|temp_dir| {
+        let original = temp_dir.path().join("original");
+        fs::write(&original, [])?;
+        util::os::symlink(&original, &temp_dir.path().join("link"))
+    });
+    match probe {
+        Ok(()) => Outcome::Pass("supported".to_string()),
+        Err(err) => Outcome::Fail {
+            reason: format!("unable to create a symlink: {err}"),
+            hint: if cfg!(windows) {
+                "enable Developer Mode, grant the SeCreateSymbolicLinkPrivilege privilege, or run as an administrator"
+            } else {
+                "check that the temporary directory is on a file system that supports symlinks"
+            }
+            .to_string(),
+        },
+    }
+}
+
+/// The programs `cargo reaper run --headless` depends on are on `$PATH`.
+#[cfg(target_os = "linux")]
+fn headless_tooling(_: &Environment) -> Outcome {
+    use crate::{
+        cli,
+        command::run::{XDOTOOL, headless},
+    };
+
+    let xvfb = headless::backend(cli::Backend::Xvfb);
+    let missing = [(xvfb.program(), xvfb.packages()), (XDOTOOL, [XDOTOOL; 3])]
+        .into_iter()
+        .filter(|(program, _)| which::which(program).is_err())
+        .collect::<Vec<_>>();
+    if missing.is_empty() {
+        return Outcome::Pass(format!("`{}` and `{XDOTOOL}` found", xvfb.program()));
+    }
+    let programs = missing
+        .iter()
+        .map(|(program, _)| format!("`{program}`"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let packages = |i: usize| {
+        missing
+            .iter()
+            .map(|(_, packages)| packages[i])
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    Outcome::Fail {
+        reason: format!("{programs} not found on `$PATH`, which `--headless` requires"),
+        hint: format!(
+            "install with `apt install {}` (Debian/Ubuntu), `dnf install {}` (Fedora) or `pacman -S {}` (Arch Linux)",
+            packages(0),
+            packages(1),
+            packages(2)
+        ),
+    }
+}
+
+/// The `cargo-reaper` configuration file of the current project can be found and loaded.
+/// Outside of a project, files that look like a misnamed configuration file are reported.
+fn configuration_file(_: &Environment) -> Outcome {
+    match find_project_root() {
+        Ok(project_root) => match ReaperPluginConfig::load(&project_root) {
+            Ok(config) => Outcome::Pass(config.file().display().to_string()),
+            Err(err) => Outcome::Fail {
+                reason: format!("{err:#}"),
+                hint:
+                    "see https://cloud-scythe-labs.github.io/cargo-reaper/configuration-file.html"
+                        .to_string(),
+            },
+        },
+        Err(_) => {
+            let current_dir = env::current_dir().unwrap_or_default();
+            let misnamed = fs::read_dir(&current_dir)
+                .into_iter()
+                .flatten()
+                .filter_map(Result::ok)
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .find(|file_name| {
+                    file_name
+                        .trim_start_matches('.')
+                        .split('.')
+                        .next()
+                        .is_some_and(|stem| stem.eq_ignore_ascii_case("reaper"))
+                });
+            match misnamed {
+                Some(file_name) if file_name == CONFIG_FILE_NAMES[0] => Outcome::Fail {
+                    reason: format!("`{file_name}` is not next to a `Cargo.toml`"),
+                    hint: "add a `Cargo.toml`, or rename it to `reaper.toml`".to_string(),
+                },
+                Some(file_name) => Outcome::Fail {
+                    reason: format!("`{file_name}` is not a configuration file name"),
+                    hint: "rename it to `reaper.toml`".to_string(),
+                },
+                None => Outcome::Skip("not in a `cargo-reaper` project".to_string()),
+            }
+        }
+    }
+}
+
+/// The manifest of every plugin in the configuration file is valid, reporting the diagnostics of
+/// those that aren't.
+fn plugin_manifests(_: &Environment) -> Outcome {
+    let Ok(config) = find_project_root().and_then(|root| ReaperPluginConfig::load(&root)) else {
+        return Outcome::Skip("no configuration file".to_string());
+    };
+    let mut emitter = TomlErrorEmitter::<String, String>::new();
+    let mut failures = Vec::new();
+    for (plugin_name, manifest_dir) in config.extension_plugins() {
+        let manifest_file = config
+            .root()
+            .join(manifest_dir.get_ref())
+            .join("Cargo.toml");
+        let result = fs::read_to_string(&manifest_file)
+            .map_err(|err| anyhow::anyhow!("failed to read '{}': {err}", manifest_file.display()))
+            .and_then(|manifest_file_content| {
+                validate_plugin(
+                    &mut emitter,
+                    config.file(),
+                    config.contents(),
+                    plugin_name,
+                    &manifest_file,
+                    &manifest_file_content,
+                )
+            });
+        if let Err(err) = result {
+            failures.push(format!("`{}`: {err}", plugin_name.as_ref()));
+        }
+    }
+    let errors = match emitter.report() {
+        Ok(errors) => errors,
+        Err(err) => return Outcome::Skip(format!("unable to report diagnostics: {err}")),
+    };
+    if failures.is_empty() && errors == 0 {
+        Outcome::Pass(format!(
+            "{} plugin(s) valid",
+            config.extension_plugins().len()
+        ))
+    } else {
+        failures.extend((errors > 0).then(|| format!("{errors} error(s) reported above")));
+        Outcome::Fail {
+            reason: failures.join(", "),
+            hint: "run `cargo reaper list --check` for details".to_string(),
+        }
+    }
+}
+
+/// Run every check, printing whether each passed (`✓`) or failed (`✗`) along with how to fix it,
+/// and return an error if any critical check failed. Non-critical failures are marked `!`.
+pub(crate) fn doctor(
+    reaper: Option<path::PathBuf>,
+    portable: Option<path::PathBuf>,
+) -> anyhow::Result<()> {
+    let environment = Environment { reaper, portable };
+    let width = CHECKS
+        .iter()
+        .map(|check| check.name.len())
+        .max()
+        .unwrap_or_default();
+    let mut passed = 0;
+    let mut failures = 0;
+    let mut critical_failures = 0;
+    for check in CHECKS {
+        match (check.run)(&environment) {
+            Outcome::Pass(found) => {
+                passed += 1;
+                println!("  {} {:width$}  {found}", "✓".green().bold(), check.name)
+            }
+            Outcome::Skip(reason) => println!(
+                "  {} {:width$}  {}",
+                "-".dimmed(),
+                check.name,
+                format!("skipped, {reason}").dimmed()
+            ),
+            Outcome::Fail { reason, hint } => {
+                failures += 1;
+                let mark = if check.critical {
+                    critical_failures += 1;
+                    "✗".red().bold()
+                } else {
+                    "!".yellow().bold()
+                };
+                println!("  {mark} {:width$}  {reason}", check.name);
+                println!("    {:width$}  {}: {hint}", "", "help".cyan().bold());
+            }
+        }
+    }
+    println!();
+    println!(
+        "{passed} check(s) passed, {failures} failed, {} skipped",
+        CHECKS.len() - passed - failures
+    );
+
+    if critical_failures > 0 {
+        anyhow::bail!("`cargo reaper doctor` found {critical_failures} critical problem(s)");
+    }
+    Ok(())
+}
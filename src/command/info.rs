@@ -0,0 +1,225 @@
+use std::{fmt, fs, path, sync};
+
+use crate::{
+    cli::ListFormat,
+    command::list::{Artifact, DEFAULT_PROFILES},
+    config::ReaperPluginConfig,
+    error::TomlErrorEmitter,
+    util::{
+        self, Colorize, LinkStatus, ManifestDetails, PluginManifest, TargetOs, find_project_root,
+        os::user_plugins_dir, project_target_dir, validate_plugin,
+    },
+};
+
+/// How similar a plugin key must be to the one passed to `cargo reaper info` to be suggested,
+/// as a Jaro similarity (the same threshold `clap` suggests subcommands with).
+const SUGGESTION_THRESHOLD: f64 = 0.7;
+
+/// Everything `cargo reaper info` knows about a plugin.
+struct PluginInfo {
+    manifest: PluginManifest,
+    package: String,
+
+    /// The configuration file the plugin is declared in.
+    config_file: path::PathBuf,
+
+    /// The line and column of the plugin's key in the configuration file, starting at 1.
+    config_location: (usize, usize),
+
+    /// The manifest directory, as written in the configuration file.
+    manifest_dir: path::PathBuf,
+
+    /// The plugin's `Cargo.toml`, canonicalized when it exists.
+    manifest_path: path::PathBuf,
+
+    /// The name of the library Cargo builds on the host before it is renamed, if the library
+    /// is named.
+    lib_file_name: Option<String>,
+
+    /// Whether the plugin is symlinked to the `UserPlugins` directory, unless the directory
+    /// can't be found.
+    link_status: Option<LinkStatus>,
+
+    /// The plugin's artifact for each of the profiles passed to `cargo reaper info`.
+    artifacts: Vec<Artifact>,
+}
+impl PluginInfo {
+    fn to_json(&self) -> serde_json::Value {
+        let mut json = self.manifest.to_json(
+            &self.package,
+            &self.manifest_path,
+            self.link_status.as_ref(),
+        );
+        let (line, column) = self.config_location;
+        json["config"] = serde_json::json!({
+            "file": self.config_file,
+            "line": line,
+            "column": column,
+            "manifest_dir": self.manifest_dir,
+        });
+        json["lib_file_name"] = serde_json::json!(self.lib_file_name);
+        json["artifacts"] = self.artifacts.iter().map(Artifact::to_json).collect();
+        json
+    }
+}
+impl fmt::Display for PluginInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (line, column) = self.config_location;
+        writeln!(f, "{:#}", self.manifest)?;
+        writeln!(f)?;
+        writeln!(f, "{:>12} {}", "Package:".green().bold(), self.package)?;
+        writeln!(
+            f,
+            "{:>12} {}:{line}:{column} ({})",
+            "Config:".green().bold(),
+            self.config_file.display(),
+            self.manifest_dir.display()
+        )?;
+        writeln!(
+            f,
+            "{:>12} {}",
+            "Manifest:".green().bold(),
+            self.manifest_path.display()
+        )?;
+        if let Some(ref lib_file_name) = self.lib_file_name {
+            writeln!(f, "{:>12} {lib_file_name}", "Built as:".green().bold())?;
+        }
+        let key = self.manifest.name();
+        write!(
+            f,
+            "{:>12} {} (Linux), {} (macOS), {} (Windows)",
+            "Renamed to:".green().bold(),
+            TargetOs::Linux.add_plugin_ext(key),
+            TargetOs::MacOs.add_plugin_ext(key),
+            TargetOs::Windows.add_plugin_ext(key)
+        )?;
+        if let Some(ref link_status) = self.link_status {
+            write!(f, "\n{:>12} {link_status}", "UserPlugins:".green().bold())?;
+        }
+        for (i, artifact) in self.artifacts.iter().enumerate() {
+            let label = if i == 0 { "Artifacts:" } else { "" };
+            write!(f, "\n{:>12} {artifact}", label.green().bold())?;
+        }
+        Ok(())
+    }
+}
+
+/// The 1-based line and column of the byte `offset` in `contents`.
+fn location(contents: &str, offset: usize) -> (usize, usize) {
+    let before = &contents[..offset.min(contents.len())];
+    let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
+/// Print everything known about the plugin named `key` to stdout, in `format`, along with the
+/// artifacts built with each of `profiles` (`debug` and `release` when empty). A plugin with a
+/// similar key is suggested when there's no plugin named `key`.
+pub(crate) fn info(key: &str, format: ListFormat, profiles: Vec<String>) -> anyhow::Result<()> {
+    if format == ListFormat::Json {
+        util::JSON_MESSAGE_FORMAT.store(true, sync::atomic::Ordering::Relaxed);
+    }
+    let project_root = find_project_root()?;
+    let config = ReaperPluginConfig::load(&project_root)?;
+    let Some((plugin_name, manifest_dir)) = config
+        .extension_plugins()
+        .iter()
+        .find(|(plugin_name, _)| plugin_name.as_ref() == key)
+    else {
+        let suggestion = config
+            .extension_plugins()
+            .keys()
+            .map(|plugin_name| {
+                (
+                    strsim::jaro(key, plugin_name.as_ref()),
+                    plugin_name.as_ref(),
+                )
+            })
+            .filter(|(similarity, _)| *similarity > SUGGESTION_THRESHOLD)
+            .max_by(|(a, _), (b, _)| a.total_cmp(b));
+        match suggestion {
+            Some((_, suggestion)) => anyhow::bail!(
+                "The following plugin(s) were not found: {key}\n\nTip: a plugin with a similar key exists: `{suggestion}`"
+            ),
+            None => anyhow::bail!(
+                "The following plugin(s) were not found: {key}\n\nTip: run `cargo reaper list` to view the available plugins."
+            ),
+        }
+    };
+
+    let manifest_file = config
+        .root()
+        .join(manifest_dir.get_ref())
+        .join("Cargo.toml");
+    let manifest_file_content = fs::read_to_string(&manifest_file).map_err(|err| {
+        anyhow::anyhow!(
+            "Failed to read manifest '{}' for plugin '{}':\n{err:#?}",
+            manifest_file.display(),
+            plugin_name.as_ref()
+        )
+    })?;
+    let mut emitter = TomlErrorEmitter::<String, String>::new();
+    let mut manifest = validate_plugin(
+        &mut emitter,
+        config.file(),
+        config.contents(),
+        plugin_name,
+        &manifest_file,
+        &manifest_file_content,
+    )?;
+    emitter.emit()?;
+    let _ = manifest
+        .as_mut()
+        .complete_from_path_and_workspace::<cargo_toml::Value>(&manifest_file, None);
+    let Some(package) = manifest.as_ref().package.as_ref() else {
+        anyhow::bail!(
+            "`{}` is not a package ({})",
+            plugin_name.as_ref(),
+            manifest_file.display()
+        );
+    };
+
+    let details = ManifestDetails::of(manifest.as_ref());
+    let target_os = TargetOs::host();
+    let lib_file_name = details.lib_name.as_deref().map(|lib_name| {
+        target_os
+            .plugin_file_name(&target_os.add_plugin_ext(lib_name))
+            .into_owned()
+    });
+    let target_dir = project_target_dir(&project_root);
+    let profiles = if profiles.is_empty() {
+        DEFAULT_PROFILES.map(String::from).to_vec()
+    } else {
+        profiles
+    };
+    let info = PluginInfo {
+        manifest: PluginManifest::new(
+            plugin_name.as_ref().to_string(),
+            package.version().to_string(),
+            package.authors().to_owned(),
+            package.description().map(|desc| desc.to_string()),
+        )
+        .with_details(details),
+        package: package.name().to_string(),
+        config_file: config.file().clone(),
+        config_location: location(config.contents(), plugin_name.span().start),
+        manifest_dir: manifest_dir.get_ref().clone(),
+        manifest_path: manifest_file.canonicalize().unwrap_or(manifest_file),
+        lib_file_name,
+        link_status: user_plugins_dir()
+            .ok()
+            .map(|user_plugins_dir| LinkStatus::of(key, &user_plugins_dir, &target_dir)),
+        artifacts: profiles
+            .iter()
+            .map(|profile| Artifact::probe(&target_dir, profile, key))
+            .collect(),
+    };
+
+    match format {
+        ListFormat::Human => println!("\n{info}"),
+        ListFormat::Json => println!("{}", info.to_json()),
+    }
+    Ok(())
+}
@@ -1,29 +1,301 @@
-use std::path;
+use std::{fs, path};
 
-use crate::util::{Colorize, os::symlink_plugin};
+use crate::{
+    command::{list::Artifact, run::resolve_reaper},
+    config::{ReaperPluginConfig, SymlinkStyle},
+    util::{
+        Colorize, Replace, TargetOs, binary_architecture, find_project_root, host_architecture,
+        os::{symlink_plugin, user_plugins_dir},
+        project_target_dir, status_println, validate_library,
+    },
+};
 
-pub(crate) fn link(paths: Vec<path::PathBuf>) -> anyhow::Result<()> {
-    paths
-        .into_iter()
-        .filter_map(|p| match p.canonicalize() {
-            Ok(path) => Some(path),
-            Err(err) => {
-                eprintln!(
-                    "{}: failed to canonicalize path `{}`:\n\n{err:#?}",
-                    "error".magenta(),
-                    p.display()
+/// The key of a plugin without an artifact, along with where its artifact was looked for.
+type Missing = (String, String);
+
+/// Symlink plugin(s) to the `UserPlugins` directory by the `paths` of their artifacts, and by the
+/// `plugins` keys of the configuration file, whose renamed artifacts are resolved in the directory
+/// of `profile` in Cargo's target directory, or else in the `release` then `debug` directory.
+///
+/// Plugin(s) are symlinked by relative paths when `relative`, or when run within a project whose
+/// configuration file's `symlink_style` is `relative`. An entry already in the way of a symlink
+/// (a regular file, or a symlink to another file) is only replaced when passed `--force`.
+///
+/// Paths that are directories are replaced by the plugin(s) found in them, see
+/// [`discover_plugins`]. Unless passed `--skip-validation`, only the files that are dynamic
+/// libraries for the host, built for the architecture of REAPER, are symlinked.
+///
+/// Plugin(s) are symlinked to the `to` directory if any, which is created when passed `--create`,
+/// instead of the default `UserPlugins` directory.
+///
+/// When passed `--all`, every plugin of the configuration file with an artifact is symlinked, and
+/// the plugins without one are reported, failing only when passed `--strict`.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn link(
+    mut paths: Vec<path::PathBuf>,
+    plugins: Vec<String>,
+    all: bool,
+    strict: bool,
+    profile: Option<String>,
+    recursive: bool,
+    relative: bool,
+    force: bool,
+    skip_validation: bool,
+    to: Option<path::PathBuf>,
+    create: bool,
+) -> anyhow::Result<()> {
+    if !plugins.is_empty() {
+        paths.extend(resolve_artifacts(&plugins, profile.as_deref())?);
+    }
+    let mut missing = Vec::new();
+    if all {
+        let artifacts;
+        (artifacts, missing) = resolve_all_artifacts(profile.as_deref())?;
+        paths.extend(artifacts);
+        for (plugin_name, locations) in &missing {
+            status_println!(
+                "{}: no artifact of `{plugin_name}` was found at {locations}",
+                "warning".yellow().bold()
+            );
+        }
+    }
+    let relative = relative
+        || find_project_root()
+            .and_then(|project_root| ReaperPluginConfig::load(&project_root))
+            .is_ok_and(|config| config.symlink_style() == SymlinkStyle::Relative);
+    let replace = if force {
+        Replace::All
+    } else {
+        Replace::Nothing
+    };
+
+    // The directory passed to `--to` is canonicalized, so that the symlinks are reported by
+    // where they actually are.
+    let user_plugins_dir = match to {
+        Some(dir) => {
+            if create {
+                fs::create_dir_all(&dir).map_err(|err| {
+                    anyhow::anyhow!("failed to create directory '{}': {err}", dir.display())
+                })?;
+            } else if !dir.is_dir() {
+                anyhow::bail!(
+                    "The directory '{}' passed to `--to` does not exist\n\nTip: pass `--create` to create it.",
+                    dir.display()
                 );
-                None
             }
-        })
-        .for_each(|plugin_path| {
-            if let Err(err) = symlink_plugin(&plugin_path) {
+            fs::canonicalize(&dir)?
+        }
+        None => user_plugins_dir()?,
+    };
+    let mut plugin_paths = Vec::with_capacity(paths.len());
+    for p in paths {
+        match p.canonicalize() {
+            Ok(path) if path.is_dir() => plugin_paths.extend(discover_plugins(&path, recursive)?),
+            Ok(path) => plugin_paths.push(path),
+            Err(err) => eprintln!(
+                "{}: failed to canonicalize path `{}`:\n\n{err:#?}",
+                "error".magenta(),
+                p.display()
+            ),
+        }
+    }
+    // Plugins must match the architecture of the REAPER executable if it can be detected, which
+    // may differ from the host's, e.g. when running an x86_64 REAPER under emulation.
+    let architecture = (!skip_validation && !plugin_paths.is_empty()).then(|| {
+        resolve_reaper(None, None)
+            .ok()
+            .and_then(|(reaper, _)| binary_architecture(&reaper))
+            .unwrap_or_else(host_architecture)
+    });
+    let (mut linked, mut already_linked, mut failed) = (0, 0, 0);
+    for plugin_path in plugin_paths {
+        if let Some(architecture) = architecture
+            && let Err(err) = validate_library(&plugin_path, TargetOs::host(), architecture)
+        {
+            failed += 1;
+            eprintln!(
+                "{}: refusing to symlink `{}` to the `UserPlugins` directory:\n\n{err:#}\n\nTip: pass `--skip-validation` to symlink it anyway.",
+                "error".magenta(),
+                plugin_path.display()
+            );
+            continue;
+        }
+        match symlink_plugin(&plugin_path, &user_plugins_dir, relative, replace) {
+            Ok(true) => linked += 1,
+            Ok(false) => already_linked += 1,
+            Err(err) => {
+                failed += 1;
                 eprintln!(
-                    "{}: failed to symlink `{}` to the `UserPlugins` directory:\n\n{err:#?}",
+                    "{}: failed to symlink `{}` to the `UserPlugins` directory:\n\n{err:#}",
                     "error".magenta(),
                     plugin_path.display()
                 )
             }
-        });
+        }
+    }
+
+    if all {
+        status_println!(
+            "     {} {linked} plugin(s) linked, {already_linked} already linked, {} without an artifact{}",
+            "Summary".green().bold(),
+            missing.len(),
+            match failed {
+                0 => String::new(),
+                failed => format!(", {failed} failed"),
+            }
+        );
+        if strict && !missing.is_empty() {
+            anyhow::bail!(
+                "`cargo reaper link --all --strict` found no artifact of {} plugin(s)\n\nTip: {}",
+                missing.len(),
+                build_tip(profile.as_deref())
+            );
+        }
+    }
     Ok(())
 }
+
+/// The plugin files in `dir` (and the directories below it when `recursive`), i.e. the files named
+/// `reaper_*` with the plugin extension of the host, sorted by path. Every other entry is reported
+/// as skipped along with the reason, and finding no plugin at all is an error, so that a mistyped
+/// directory doesn't silently link nothing.
+fn discover_plugins(dir: &path::Path, recursive: bool) -> anyhow::Result<Vec<path::PathBuf>> {
+    let plugin_ext = TargetOs::host().add_plugin_ext("");
+    let mut plugin_paths = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let mut entries = fs::read_dir(&dir)
+            .map_err(|err| anyhow::anyhow!("failed to read directory '{}': {err}", dir.display()))?
+            .collect::<Result<Vec<_>, _>>()?;
+        entries.sort_by_key(fs::DirEntry::path);
+        for entry in entries {
+            let path = entry.path();
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            let skipped = if path.is_dir() {
+                if recursive {
+                    dirs.push(path);
+                    continue;
+                }
+                "a directory, pass `--recursive` to descend into it".to_string()
+            } else if !file_name.ends_with(&plugin_ext) {
+                format!("not a `{plugin_ext}` file")
+            } else if !file_name.starts_with("reaper_") {
+                "not prefixed by `reaper_`, which REAPER requires".to_string()
+            } else {
+                plugin_paths.push(path);
+                continue;
+            };
+            status_println!(
+                "    {} {} ({skipped})",
+                "Skipping".yellow().bold(),
+                path.display()
+            );
+        }
+    }
+    if plugin_paths.is_empty() {
+        anyhow::bail!(
+            "No plugin was found in '{}'{}, expected files named `reaper_*{plugin_ext}`",
+            dir.display(),
+            if recursive { " or below it" } else { "" }
+        );
+    }
+    plugin_paths.sort();
+    Ok(plugin_paths)
+}
+
+/// The renamed artifact of each of the `plugins` keys, see [`link`].
+fn resolve_artifacts(
+    plugins: &[String],
+    profile: Option<&str>,
+) -> anyhow::Result<Vec<path::PathBuf>> {
+    let project_root = find_project_root()?;
+    let config = ReaperPluginConfig::load(&project_root)?;
+    let not_found = plugins
+        .iter()
+        .filter(|plugin_name| {
+            !config
+                .extension_plugins()
+                .keys()
+                .any(|key| key.as_ref() == plugin_name.as_str())
+        })
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+    if !not_found.is_empty() {
+        anyhow::bail!(
+            "The following plugin(s) were not found: {}\n\nTip: run `cargo reaper list` to view the available plugins.",
+            not_found.join(", ")
+        );
+    }
+
+    let target_dir = project_target_dir(&project_root);
+    plugins
+        .iter()
+        .map(|plugin_name| {
+            find_artifact(&target_dir, profile, plugin_name).map_err(|locations| {
+                anyhow::anyhow!(
+                    "No artifact of `{plugin_name}` was found at {locations}\n\nTip: {}",
+                    build_tip(profile)
+                )
+            })
+        })
+        .collect()
+}
+
+/// The renamed artifact of every plugin of the configuration file that has one, sorted by key,
+/// along with where the artifacts of the others were looked for, see `cargo reaper link --all`.
+fn resolve_all_artifacts(
+    profile: Option<&str>,
+) -> anyhow::Result<(Vec<path::PathBuf>, Vec<Missing>)> {
+    let project_root = find_project_root()?;
+    let config = ReaperPluginConfig::load(&project_root)?;
+    let target_dir = project_target_dir(&project_root);
+    let mut plugin_names = config
+        .extension_plugins()
+        .keys()
+        .map(|plugin_name| plugin_name.as_ref().as_str())
+        .collect::<Vec<_>>();
+    plugin_names.sort();
+    let mut artifacts = Vec::with_capacity(plugin_names.len());
+    let mut missing = Vec::new();
+    for plugin_name in plugin_names {
+        match find_artifact(&target_dir, profile, plugin_name) {
+            Ok(artifact) => artifacts.push(artifact),
+            Err(locations) => missing.push((plugin_name.to_string(), locations)),
+        }
+    }
+    Ok((artifacts, missing))
+}
+
+/// The renamed artifact of the plugin named `plugin_name` in the directory of `profile` in
+/// `target_dir`, or else in the `release` then `debug` directory, or else where it was looked for.
+fn find_artifact(
+    target_dir: &path::Path,
+    profile: Option<&str>,
+    plugin_name: &str,
+) -> Result<path::PathBuf, String> {
+    let profiles = profile.map_or_else(|| vec!["release", "debug"], |profile| vec![profile]);
+    let artifacts = profiles
+        .iter()
+        .map(|profile| Artifact::probe(target_dir, profile, plugin_name))
+        .collect::<Vec<_>>();
+    match artifacts.iter().find_map(Artifact::built_path) {
+        Some(artifact) => Ok(artifact.to_path_buf()),
+        None => Err(artifacts
+            .iter()
+            .map(|artifact| format!("'{}'", artifact.path().display()))
+            .collect::<Vec<_>>()
+            .join(" or ")),
+    }
+}
+
+/// The tip printed when a plugin has no artifact built with `profile`.
+fn build_tip(profile: Option<&str>) -> String {
+    format!(
+        "did you run `cargo reaper build{}`?",
+        match profile {
+            None | Some("dev" | "debug" | "test") => String::new(),
+            Some("release" | "bench") => " --release".to_string(),
+            Some(profile) => format!(" --profile {profile}"),
+        }
+    )
+}
@@ -1,67 +1,459 @@
-use std::fs;
+use std::{env, fmt, fs, path, slice, sync, time};
 
 use crate::{
+    cli::{ListFormat, PluginPattern},
+    command::clean::not_found_tip,
     config::ReaperPluginConfig,
     error::TomlErrorEmitter,
-    util::{Colorize, PluginManifest, find_project_root, validate_plugin},
+    util::{
+        self, BINARY_NAME, Colorize, LinkStatus, ManifestDetails, PluginManifest, TargetOs,
+        find_project_root, format_size,
+        os::{locate_global_default, user_plugins_dir},
+        project_target_dir, reaper_version, validate_plugin, warn_stale_plugins,
+    },
 };
 
-/// Print available extension plugins to stdout.
-pub(crate) fn list() -> anyhow::Result<()> {
-    let config = ReaperPluginConfig::load(&find_project_root()?)?;
-    let mut emitter = TomlErrorEmitter::<String, String>::new();
-    let mut plugins: Vec<String> = Vec::new();
-    for (plugin_name, manifest_dir) in config.extension_plugins().iter() {
-        let manifest_file = manifest_dir.get_ref().join("Cargo.toml");
-        let manifest_file_content = fs::read_to_string(&manifest_file).map_err(|err| {
-            anyhow::anyhow!(
-                "Failed to read manifest '{}' for plugin '{}':\n{err:#?}",
-                manifest_file.display(),
-                plugin_name.as_ref()
-            )
-        })?;
-        let mut manifest = validate_plugin(
-            &mut emitter,
-            config.file(),
-            config.contents(),
-            plugin_name,
-            &manifest_file,
-            &manifest_file_content,
-        )?;
-        let _ = manifest
-            .as_mut()
-            .complete_from_path_and_workspace::<cargo_toml::Value>(&manifest_file, None);
-        if let Some(package) = manifest.as_ref().package.as_ref() {
-            plugins.push(
-                PluginManifest::new(
-                    plugin_name.as_ref().to_string(),
-                    package.version().to_string(),
-                    package.authors().to_owned(),
-                    package.description().map(|desc| desc.to_string()),
-                )
-                .to_string(),
-            );
+/// The profiles artifacts are looked for with, unless passed `--profile`.
+pub(crate) const DEFAULT_PROFILES: &[&str; 2] = &["debug", "release"];
+
+/// A plugin printed by `cargo reaper list`.
+struct ListedPlugin {
+    manifest: PluginManifest,
+    package: String,
+    manifest_path: path::PathBuf,
+    link_status: Option<LinkStatus>,
+    artifacts: Vec<Artifact>,
+
+    /// Whether the manifest details are shown (`--verbose`).
+    verbose: bool,
+}
+impl ListedPlugin {
+    fn to_json(&self) -> serde_json::Value {
+        let mut json = self.manifest.to_json(
+            &self.package,
+            &self.manifest_path,
+            self.link_status.as_ref(),
+        );
+        json["artifacts"] = self.artifacts.iter().map(Artifact::to_json).collect();
+        json
+    }
+}
+impl fmt::Display for ListedPlugin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.verbose {
+            write!(f, "{:#}", self.manifest)?;
         } else {
-            emitter.insert_err(
-                manifest_file.to_string_lossy().to_string(),
-                manifest_file_content,
-                format!("`{}` is not a package", plugin_name.as_ref()),
-                manifest.span(),
-                Some("expected manifest path to a package containing a dynamic library target"),
-                None,
-                Some("help: is this a workspace? try adding the `[workspace.package]` attribute"),
+            write!(f, "{}", self.manifest)?;
+        }
+        if let Some(ref link_status) = self.link_status {
+            write!(f, "\n\nUserPlugins: {link_status}")?;
+        }
+        if !self.artifacts.is_empty() {
+            write!(f, "\n\nArtifacts:")?;
+            for artifact in &self.artifacts {
+                write!(f, "\n  {artifact}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A plugin that could not be loaded, listed by `cargo reaper list --check`.
+struct BrokenPlugin {
+    key: String,
+    manifest_path: path::PathBuf,
+    reason: String,
+}
+impl BrokenPlugin {
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "key": self.key,
+            "manifest_path": self.manifest_path,
+            "error": self.reason,
+        })
+    }
+}
+impl fmt::Display for BrokenPlugin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}: {}\n\nManifest: {}",
+            self.key.blue(),
+            "broken".red(),
+            self.reason,
+            self.manifest_path.display()
+        )
+    }
+}
+
+/// A plugin's renamed artifact in a profile directory of Cargo's target directory.
+pub(crate) struct Artifact {
+    /// The name of the profile directory, e.g. `debug`.
+    profile: String,
+
+    /// Where the artifact is (or would be) built.
+    path: path::PathBuf,
+
+    /// The artifact's size in bytes and modification time, if it was built.
+    built: Option<(u64, time::SystemTime)>,
+}
+impl Artifact {
+    /// Look for the artifact of the plugin named `plugin_name` built with `profile`.
+    pub(crate) fn probe(target_dir: &path::Path, profile: &str, plugin_name: &str) -> Self {
+        // The `dev` and `test` profiles are built to the `debug` directory, and `bench` to
+        // `release`.
+        let profile = match profile {
+            "dev" | "test" => "debug",
+            "bench" => "release",
+            profile => profile,
+        };
+        let path = target_dir
+            .join(profile)
+            .join(TargetOs::host().add_plugin_ext(plugin_name));
+        let built = fs::metadata(&path)
+            .ok()
+            .filter(|meta| meta.is_file())
+            .and_then(|meta| Some((meta.len(), meta.modified().ok()?)));
+        Self {
+            profile: profile.to_string(),
+            path,
+            built,
+        }
+    }
+
+    /// Where the artifact is (or would be) built.
+    pub(crate) fn path(&self) -> &path::Path {
+        &self.path
+    }
+
+    /// The path of the artifact, if it was built.
+    pub(crate) fn built_path(&self) -> Option<&path::Path> {
+        self.built.is_some().then_some(self.path.as_path())
+    }
+
+    pub(crate) fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "profile": self.profile,
+            "path": self.path,
+            "built": self.built.is_some(),
+            "size": self.built.map(|(size, _)| size),
+            "modified": self
+                .built
+                .map(|(_, modified)| humantime::format_rfc3339_seconds(modified).to_string()),
+        })
+    }
+}
+impl fmt::Display for Artifact {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some((size, modified)) = self.built else {
+            return write!(f, "{}: {}", self.profile, "not built".red());
+        };
+        // Ages are rounded down to the second, e.g. `3m 12s ago`.
+        let age = modified
+            .elapsed()
+            .map(|age| humantime::format_duration(time::Duration::from_secs(age.as_secs())));
+        write!(
+            f,
+            "{}: {} ({}, ",
+            self.profile,
+            self.path.display(),
+            format_size(size)
+        )?;
+        match age {
+            Ok(age) => write!(f, "built {age} ago)"),
+            Err(_) => write!(f, "built {})", humantime::format_rfc3339_seconds(modified)),
+        }
+    }
+}
+
+/// The config file of the current project, or those of every project below the current directory
+/// when passed `--recursive`.
+fn load_configs(recursive: bool) -> anyhow::Result<Vec<ReaperPluginConfig>> {
+    if recursive {
+        let configs = ReaperPluginConfig::discover(&env::current_dir()?)?;
+        if configs.is_empty() {
+            anyhow::bail!(
+                "Unable to find any `reaper.toml` or `.reaper.toml` file below the current directory."
             );
         }
+        Ok(configs)
+    } else {
+        Ok(vec![ReaperPluginConfig::load(&find_project_root()?)?])
     }
+}
 
-    emitter.emit()?;
-    plugins.sort();
+/// Print the sorted keys of the extension plugins (those matching `pattern`, if any) to stdout,
+/// one per line (`--quiet`). Plugin manifests are neither read nor validated.
+pub(crate) fn list_keys(pattern: Option<&PluginPattern>, recursive: bool) -> anyhow::Result<()> {
+    let configs = load_configs(recursive)?;
+    let mut keys = configs
+        .iter()
+        .flat_map(|config| config.extension_plugins().keys())
+        .map(|plugin_name| plugin_name.as_ref())
+        .filter(|key| pattern.is_none_or(|pattern| pattern.matches(key)))
+        .collect::<Vec<_>>();
+    keys.sort();
+    for key in keys {
+        println!("{key}");
+    }
+    Ok(())
+}
 
-    println!(
-        "\n{}:\n\n{}",
-        "Available Plugins".green().bold(),
-        plugins.join("\n\n--\n\n")
-    );
+/// Print available extension plugins (those whose key matches `pattern`, if any) to stdout, in
+/// `format`, along with the artifacts built with each of `profiles` (`debug` and `release` when
+/// empty).
+///
+/// When passed `--verbose`, the license, repository, MSRV, crate types and library name of each
+/// plugin are shown too. When passed `--check`, manifests that can't be read or parsed are
+/// reported as diagnostics instead of aborting, every plugin is listed (including the broken
+/// ones), and an error is returned once everything was printed if any problem was found.
+///
+/// When passed `--recursive`, the plugins of every config file below the current directory are
+/// printed, grouped by config file.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn list(
+    pattern: Option<&PluginPattern>,
+    no_staleness_check: bool,
+    format: ListFormat,
+    profiles: Vec<String>,
+    check: bool,
+    verbose: bool,
+    recursive: bool,
+) -> anyhow::Result<()> {
+    if format == ListFormat::Json {
+        util::JSON_MESSAGE_FORMAT.store(true, sync::atomic::Ordering::Relaxed);
+    }
+    let current_dir = env::current_dir()?;
+    let configs = load_configs(recursive)?;
+    let mut emitter = TomlErrorEmitter::<String, String>::new();
+    let user_plugins_dir = user_plugins_dir();
+    let profiles = if profiles.is_empty() {
+        DEFAULT_PROFILES.map(String::from).to_vec()
+    } else {
+        profiles
+    };
+    let listed = configs
+        .iter()
+        .map(|config| {
+            let plugins = config
+                .extension_plugins()
+                .iter()
+                .filter(|(plugin_name, _)| {
+                    pattern.is_none_or(|pattern| pattern.matches(plugin_name.as_ref()))
+                })
+                .collect::<Vec<_>>();
+            (config, plugins)
+        })
+        .collect::<Vec<_>>();
+    if let Some(pattern) = pattern
+        && listed.iter().all(|(_, plugins)| plugins.is_empty())
+    {
+        anyhow::bail!(
+            "The following plugin(s) were not found: {pattern}\n\n{}",
+            not_found_tip(slice::from_ref(pattern))
+        );
+    }
+    let mut groups: Vec<(&ReaperPluginConfig, Vec<ListedPlugin>, Vec<BrokenPlugin>)> = Vec::new();
+    for (config, listed) in listed {
+        let root = config
+            .root()
+            .strip_prefix(&current_dir)
+            .unwrap_or(config.root());
+        let target_dir = project_target_dir(config.root());
+        let mut plugins: Vec<ListedPlugin> = Vec::new();
+        let mut broken: Vec<BrokenPlugin> = Vec::new();
+        for (plugin_name, manifest_dir) in listed {
+            let manifest_file = root.join(manifest_dir.get_ref()).join("Cargo.toml");
+            let mut insert_broken = |emitter: &mut TomlErrorEmitter<String, String>,
+                                     reason: String,
+                                     path: String,
+                                     contents: String,
+                                     span| {
+                emitter.insert_err(
+                    path,
+                    contents,
+                    format!("`{}` could not be loaded", plugin_name.as_ref()),
+                    span,
+                    Some(reason.clone()),
+                    None,
+                    None::<String>,
+                );
+                broken.push(BrokenPlugin {
+                    key: plugin_name.as_ref().to_string(),
+                    manifest_path: manifest_file
+                        .canonicalize()
+                        .unwrap_or_else(|_| manifest_file.clone()),
+                    reason,
+                });
+            };
+            let manifest_file_content = match fs::read_to_string(&manifest_file) {
+                Ok(content) => content,
+                Err(err) if check => {
+                    insert_broken(
+                        &mut emitter,
+                        format!("failed to read '{}': {err}", manifest_file.display()),
+                        config.file().to_string_lossy().to_string(),
+                        config.contents().to_string(),
+                        manifest_dir.span(),
+                    );
+                    continue;
+                }
+                Err(err) => anyhow::bail!(
+                    "Failed to read manifest '{}' for plugin '{}':\n{err:#?}",
+                    manifest_file.display(),
+                    plugin_name.as_ref()
+                ),
+            };
+            if check && let Err(err) = cargo_toml::Manifest::from_str(&manifest_file_content) {
+                let (reason, span) = match &err {
+                    cargo_toml::Error::Parse(err) => (err.message().to_string(), err.span()),
+                    err => (err.to_string(), None),
+                };
+                insert_broken(
+                    &mut emitter,
+                    format!("failed to parse manifest: {reason}"),
+                    manifest_file.to_string_lossy().to_string(),
+                    manifest_file_content,
+                    span.unwrap_or(0..0),
+                );
+                continue;
+            }
+            let mut manifest = validate_plugin(
+                &mut emitter,
+                config.file(),
+                config.contents(),
+                plugin_name,
+                &manifest_file,
+                &manifest_file_content,
+            )?;
+            let _ = manifest
+                .as_mut()
+                .complete_from_path_and_workspace::<cargo_toml::Value>(&manifest_file, None);
+            if let Some(package) = manifest.as_ref().package.as_ref() {
+                plugins.push(ListedPlugin {
+                    manifest: PluginManifest::new(
+                        plugin_name.as_ref().to_string(),
+                        package.version().to_string(),
+                        package.authors().to_owned(),
+                        package.description().map(|desc| desc.to_string()),
+                    )
+                    .with_details(ManifestDetails::of(manifest.as_ref())),
+                    package: package.name().to_string(),
+                    manifest_path: manifest_file.canonicalize().unwrap_or(manifest_file),
+                    link_status: user_plugins_dir.as_ref().ok().map(|user_plugins_dir| {
+                        LinkStatus::of(plugin_name.as_ref(), user_plugins_dir, &target_dir)
+                    }),
+                    artifacts: profiles
+                        .iter()
+                        .map(|profile| Artifact::probe(&target_dir, profile, plugin_name.as_ref()))
+                        .collect(),
+                    verbose,
+                });
+            } else {
+                emitter.insert_err(
+                    manifest_file.to_string_lossy().to_string(),
+                    manifest_file_content,
+                    format!("`{}` is not a package", plugin_name.as_ref()),
+                    manifest.span(),
+                    Some("expected manifest path to a package containing a dynamic library target"),
+                    None,
+                    Some(
+                        "help: is this a workspace? try adding the `[workspace.package]` attribute",
+                    ),
+                );
+                if check {
+                    broken.push(BrokenPlugin {
+                        key: plugin_name.as_ref().to_string(),
+                        manifest_path: manifest_file.canonicalize().unwrap_or(manifest_file),
+                        reason: "not a package".to_string(),
+                    });
+                }
+            }
+        }
+        plugins.sort_by(|a, b| a.manifest.cmp(&b.manifest));
+        broken.sort_by(|a, b| a.key.cmp(&b.key));
+        groups.push((config, plugins, broken));
+    }
+
+    let errors = if check {
+        emitter.report()?
+    } else {
+        emitter.emit()?;
+        0
+    };
+
+    match format {
+        ListFormat::Human => {
+            for (config, plugins, broken) in &groups {
+                let heading = if recursive {
+                    format!(
+                        "Available Plugins ({})",
+                        config
+                            .file()
+                            .strip_prefix(&current_dir)
+                            .unwrap_or(config.file())
+                            .display()
+                    )
+                } else {
+                    "Available Plugins".to_string()
+                };
+                println!(
+                    "\n{}:\n\n{}",
+                    heading.green().bold(),
+                    plugins
+                        .iter()
+                        .map(ListedPlugin::to_string)
+                        .chain(broken.iter().map(BrokenPlugin::to_string))
+                        .collect::<Vec<_>>()
+                        .join("\n\n--\n\n")
+                );
+            }
+        }
+        ListFormat::Json => println!(
+            "{}",
+            serde_json::Value::Array(
+                groups
+                    .iter()
+                    .flat_map(|(config, plugins, broken)| {
+                        plugins
+                            .iter()
+                            .map(ListedPlugin::to_json)
+                            .chain(broken.iter().map(BrokenPlugin::to_json))
+                            .map(move |mut json| {
+                                if recursive {
+                                    json["config_file"] = serde_json::json!(config.file());
+                                }
+                                json
+                            })
+                    })
+                    .collect()
+            )
+        ),
+    }
 
+    if format == ListFormat::Human
+        && let Ok(reaper) = which::which(BINARY_NAME).or_else(|_| locate_global_default())
+    {
+        println!(
+            "\n{}\n  {} ({})",
+            "REAPER:".green().bold(),
+            reaper.display(),
+            reaper_version(&reaper).map_or_else(
+                || "unknown version".to_string(),
+                |version| format!("version {version}")
+            )
+        );
+    }
+
+    if !no_staleness_check {
+        let user_plugins_dir = user_plugins_dir?;
+        for config in &configs {
+            warn_stale_plugins(config.root(), config, &user_plugins_dir)?;
+        }
+    }
+
+    if errors > 0 {
+        anyhow::bail!("`cargo reaper list --check` found {errors} problem(s)");
+    }
     Ok(())
 }
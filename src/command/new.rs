@@ -1,40 +1,274 @@
-use std::{fs, path};
+use std::{fs, path, process};
 
-use crate::{cli::PluginTemplate, util::Colorize};
+use clap::ValueEnum;
 
-pub(crate) fn new(template: PluginTemplate, path: path::PathBuf) -> anyhow::Result<()> {
+use crate::{
+    cli::{PluginPattern, PluginTemplate, Vcs},
+    config::{CONFIG_FILE_NAMES, TemplateSource, UserConfig},
+    util::Colorize,
+};
+
+/// Rust keywords, which can't be used as package names.
+const KEYWORDS: &[&str; 51] = &[
+    "Self", "abstract", "as", "async", "await", "become", "box", "break", "const", "continue",
+    "crate", "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if",
+    "impl", "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub",
+    "ref", "return", "self", "static", "struct", "super", "trait", "true", "try", "type", "typeof",
+    "unsafe", "unsized", "use", "virtual", "where", "while",
+];
+
+/// Names of the directories of Cargo's target directory, which can't be used as package names.
+const RESERVED_NAMES: &[&str; 5] = &["build", "deps", "examples", "incremental", "test"];
+
+/// The template a new plugin project is created from.
+#[derive(Debug)]
+pub(crate) enum Template {
+    /// A template bundled with `cargo-reaper`.
+    Bundled(PluginTemplate),
+
+    /// A git repository, checked out at `branch` or `rev`, or else at its default branch.
+    Git {
+        url: String,
+        branch: Option<String>,
+        rev: Option<String>,
+    },
+
+    /// A local directory.
+    Path(path::PathBuf),
+}
+impl Template {
+    /// The template selected by the arguments of `cargo reaper new`, i.e. `--template-git` or
+    /// `--template-path`, or else the template named by `--template`.
+    pub(crate) fn from_args(
+        name: String,
+        git: Option<String>,
+        path: Option<path::PathBuf>,
+        branch: Option<String>,
+        rev: Option<String>,
+    ) -> anyhow::Result<Self> {
+        match (git, path) {
+            (Some(url), _) => Ok(Self::Git { url, branch, rev }),
+            (None, Some(dir)) => Ok(Self::Path(dir)),
+            (None, None) => Self::named(&name),
+        }
+    }
+
+    /// The template named `name` in the `[templates]` table of the user config file, or else the
+    /// bundled template of that name.
+    fn named(name: &str) -> anyhow::Result<Self> {
+        let config = UserConfig::load()?;
+        if let Some(source) = config.templates().get(name) {
+            return Ok(match source.clone() {
+                TemplateSource::Git { url, branch, rev } => Self::Git { url, branch, rev },
+                TemplateSource::Path { path } => Self::Path(path),
+            });
+        }
+        if let Ok(template) = PluginTemplate::from_str(name, false) {
+            return Ok(Self::Bundled(template));
+        }
+        anyhow::bail!(
+            "unknown template `{name}`, the available templates are: {}\n\nTip: add templates to the `[templates]` table of the user config file{}.",
+            available_templates(&config)
+                .iter()
+                .map(|(name, _)| format!("`{name}`"))
+                .collect::<Vec<_>>()
+                .join(", "),
+            UserConfig::file()
+                .map(|file| format!(" '{}'", file.display()))
+                .unwrap_or_default()
+        )
+    }
+}
+
+/// Print the templates available to `cargo reaper new --template`, along with where they come
+/// from.
+pub(crate) fn list_templates() -> anyhow::Result<()> {
+    let templates = available_templates(&UserConfig::load()?);
+    let width = templates
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or_default();
+    for (name, source) in templates {
+        println!("{:<width$}  {source}", name.bold());
+    }
+    Ok(())
+}
+
+/// The names of the templates available to `cargo reaper new --template` along with where they
+/// come from: the bundled templates, unless shadowed by a template of the same name in the user
+/// config file, followed by those of the user config file.
+fn available_templates(config: &UserConfig) -> Vec<(String, String)> {
+    PluginTemplate::value_variants()
+        .iter()
+        .filter(|template| !config.templates().contains_key(&template.to_string()))
+        .map(|template| {
+            let source = match template {
+                PluginTemplate::Ext => "bundled extension plugin template",
+                PluginTemplate::Vst => "bundled VST plugin template",
+            };
+            (template.to_string(), source.to_string())
+        })
+        .chain(
+            config
+                .templates()
+                .iter()
+                .map(|(name, source)| (name.clone(), source.to_string())),
+        )
+        .collect()
+}
+
+/// Create a new plugin project in `path` from `template`, whose package is named `name`, or else
+/// after the directory, sanitized into a valid package name, initialized with `vcs`.
+pub(crate) fn new(
+    template: Template,
+    name: Option<String>,
+    vcs: Vcs,
+    path: path::PathBuf,
+) -> anyhow::Result<()> {
     if path.exists() {
         anyhow::bail!("project path already exists");
     }
 
-    let package_name = path
-        .components()
-        .next_back()
-        .ok_or_else(|| anyhow::anyhow!("failed to produce package name from directory"))?
-        .as_os_str()
-        .to_string_lossy();
-    println!(
-        "    {} dynamically linked library (cdylib) `{}` REAPER {:?} plugin package",
-        "Creating".green().bold(),
-        package_name,
-        &template
-    );
-    new_from_template(template, &path, &package_name)
+    let given_name = match &name {
+        Some(name) => name.clone(),
+        None => path
+            .components()
+            .next_back()
+            .ok_or_else(|| anyhow::anyhow!("failed to produce package name from directory"))?
+            .as_os_str()
+            .to_string_lossy()
+            .into_owned(),
+    };
+    let package_name = sanitize_package_name(&given_name);
+    validate_package_name(&package_name)
+        .map_err(|err| {
+            if package_name != given_name {
+                anyhow::anyhow!("{err} (sanitized from `{given_name}`)")
+            } else {
+                err
+            }
+        })
+        .map_err(|err| match name {
+            Some(_) => err,
+            None => anyhow::anyhow!(
+                "{err}\n\nTip: pass `--name` to choose a package name other than the directory's."
+            ),
+        })?;
+    if package_name != given_name {
+        println!(
+            "{}: the package name `{given_name}` was changed to `{package_name}`",
+            "warning".yellow().bold(),
+        );
+    }
+    // Unlike package names, library names can't contain `-`.
+    let lib_name = package_name.replace('-', "_");
+    if lib_name != package_name {
+        println!(
+            "{}: the library name was changed to `{lib_name}`, since it can't contain `-`",
+            "warning".yellow().bold(),
+        );
+    }
+    match &template {
+        Template::Bundled(template) => println!(
+            "    {} dynamically linked library (cdylib) `{}` REAPER {:?} plugin package",
+            "Creating".green().bold(),
+            package_name,
+            template
+        ),
+        Template::Git { url, .. } => println!(
+            "    {} dynamically linked library (cdylib) `{}` REAPER plugin package from `{url}`",
+            "Creating".green().bold(),
+            package_name,
+        ),
+        Template::Path(dir) => println!(
+            "    {} dynamically linked library (cdylib) `{}` REAPER plugin package from '{}'",
+            "Creating".green().bold(),
+            package_name,
+            dir.display()
+        ),
+    }
+    new_from_template(&template, &path, &package_name, &lib_name, vcs)
         .map_err(|err| anyhow::anyhow!("failed to create new REAPER plugin project: {err:?}"))
 }
 
-/// Downloads and initializes the REAPER extension plugin template.
+/// Derive a package name from `name` by lowercasing it, replacing whitespace by `_`, and removing
+/// any other character Cargo doesn't accept in package names.
+fn sanitize_package_name(name: &str) -> String {
+    name.trim()
+        .chars()
+        .filter_map(|char| match char {
+            char if char.is_whitespace() => Some('_'),
+            char if char.is_ascii_alphanumeric() || char == '-' || char == '_' => {
+                Some(char.to_ascii_lowercase())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Return an error unless `name` is a valid package name, by the rules Cargo applies: it must be
+/// non-empty, only contain ASCII letters, numbers, `-` and `_`, not start with a digit, and be
+/// neither a Rust keyword nor the name of a directory of Cargo's target directory.
+fn validate_package_name(name: &str) -> anyhow::Result<()> {
+    if name.is_empty() {
+        anyhow::bail!("the package name cannot be empty");
+    }
+    if let Some(char) = name
+        .chars()
+        .find(|char| !char.is_ascii_alphanumeric() && *char != '-' && *char != '_')
+    {
+        anyhow::bail!(
+            "invalid character `{char}` in package name `{name}`, characters must be letters, numbers, `-` or `_`"
+        );
+    }
+    if name.starts_with(|char: char| char.is_ascii_digit()) {
+        anyhow::bail!("the package name `{name}` cannot start with a digit");
+    }
+    if KEYWORDS.contains(&name) {
+        anyhow::bail!("the package name `{name}` cannot be used, it is a Rust keyword");
+    }
+    if RESERVED_NAMES.contains(&name) {
+        anyhow::bail!(
+            "the package name `{name}` cannot be used, it conflicts with Cargo's build directory names"
+        );
+    }
+    Ok(())
+}
+
+/// Initializes the project at `destination` from `template`, named after `package_name` and
+/// `lib_name`. The template is prepared in a temporary directory, which is only moved to
+/// `destination` once it's complete, so that no half-created project is left behind on failure.
 pub(crate) fn new_from_template(
-    template: PluginTemplate,
+    template: &Template,
     destination: &path::PathBuf,
     package_name: &str,
+    lib_name: &str,
+    vcs: Vcs,
 ) -> anyhow::Result<()> {
+    let mut workspace = Workspace::enclosing(destination)?;
     let temp_dir = tempfile::tempdir()?;
-    template.extract(&temp_dir)?;
-
-    fs::rename(temp_dir.path(), destination)?;
+    let project_dir = temp_dir.path();
+    match template {
+        Template::Bundled(template) => template.extract(project_dir)?,
+        Template::Git { url, branch, rev } => {
+            clone_template(url, branch.as_deref(), rev.as_deref(), project_dir)?
+        }
+        Template::Path(dir) => {
+            if !dir.is_dir() {
+                anyhow::bail!("the template directory '{}' does not exist", dir.display());
+            }
+            copy_template(dir, project_dir, true)?
+        }
+    }
 
-    let cargo_toml_path = destination.join("cargo.toml");
+    // Bundled templates name their manifest `cargo.toml`, so that Cargo doesn't consider them
+    // packages of this one.
+    let cargo_toml_path = ["Cargo.toml", "cargo.toml"]
+        .map(|file_name| project_dir.join(file_name))
+        .into_iter()
+        .find(|path| path.is_file())
+        .ok_or_else(|| anyhow::anyhow!("the template does not contain a `Cargo.toml` manifest"))?;
     let mut cargo_toml = fs::read_to_string(&cargo_toml_path)?.parse::<toml_edit::DocumentMut>()?;
     if let Some(package) = cargo_toml.get_mut("package")
         && let Some(name) = package.get_mut("name")
@@ -44,34 +278,464 @@ pub(crate) fn new_from_template(
     if let Some(lib) = cargo_toml.get_mut("lib")
         && let Some(name) = lib.get_mut("name")
     {
-        *name = toml_edit::value(package_name);
+        *name = toml_edit::value(lib_name);
+    }
+    if let Some(workspace) = &mut workspace {
+        workspace.adapt_member_manifest(&mut cargo_toml);
     }
     fs::write(&cargo_toml_path, cargo_toml.to_string())
-        .and_then(|_| fs::rename(&cargo_toml_path, destination.join("Cargo.toml")))?;
+        .and_then(|_| fs::rename(&cargo_toml_path, project_dir.join("Cargo.toml")))?;
 
-    if let PluginTemplate::Ext = template {
-        let reaper_toml_path = destination.join("reaper.toml");
+    let reaper_toml_path = project_dir.join("reaper.toml");
+    if reaper_toml_path.is_file() {
         let mut reaper_toml =
             fs::read_to_string(&reaper_toml_path)?.parse::<toml_edit::DocumentMut>()?;
         if let Some(extension_plugins) = reaper_toml
             .get_mut("extension_plugins")
             .and_then(toml_edit::Item::as_table_mut)
         {
+            // The plugin of the template's own package is replaced by that of the new package.
+            extension_plugins.retain(|_, path| {
+                !path
+                    .as_str()
+                    .is_some_and(|path| matches!(path, "." | "./" | "./."))
+            });
             extension_plugins.insert(
-                &(package_name.starts_with("reaper_"))
-                    .then(|| package_name.into())
-                    .unwrap_or(format!("reaper_{package_name}")),
+                &(lib_name.starts_with("reaper_"))
+                    .then(|| lib_name.into())
+                    .unwrap_or(format!("reaper_{lib_name}")),
                 toml_edit::value("./."),
             );
         }
-        fs::write(&reaper_toml_path, reaper_toml.to_string())?;
+        match &mut workspace {
+            // The plugin(s) are declared in the configuration file of the workspace instead, so
+            // that it isn't shadowed by one of its members.
+            Some(workspace) => {
+                workspace.add_plugins(&reaper_toml)?;
+                fs::remove_file(&reaper_toml_path)?;
+            }
+            None => fs::write(&reaper_toml_path, reaper_toml.to_string())?,
+        }
+    } else if let Template::Git { .. } | Template::Path(_) = template {
+        anyhow::bail!("the template does not contain a `reaper.toml` configuration file");
+    }
+
+    if let Some(parent) = destination.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(project_dir, destination)?;
+
+    if let Some(workspace) = workspace {
+        workspace.save()?;
+        if vcs == Vcs::Git {
+            println!(
+                "    {} git repository initialization, '{}' is a member of the workspace '{}'",
+                "Skipping".yellow().bold(),
+                destination.display(),
+                workspace.root.display()
+            );
+        }
+        return Ok(());
+    }
+
+    if vcs == Vcs::None {
+        return Ok(());
     }
 
     fs::write(destination.join(".gitignore"), "/target")?;
 
+    // Like Cargo, don't nest a repository within an existing one. Worktrees and submodules
+    // have a `.git` file rather than a directory, so any `.git` entry counts.
+    if let Some(repository) = destination
+        .canonicalize()?
+        .ancestors()
+        .skip(1)
+        .find(|dir| dir.join(".git").exists())
+    {
+        println!(
+            "    {} git repository initialization, '{}' is already within the git repository '{}'",
+            "Skipping".yellow().bold(),
+            destination.display(),
+            repository.display()
+        );
+        return Ok(());
+    }
     gix::init(destination).map_err(|err| {
         anyhow::anyhow!("failed to initialize REAPER plugin project as a git repository: {err:?}")
     })?;
 
     Ok(())
 }
+
+/// The Cargo workspace a new project is created in, along with the changes to its files that make
+/// the project a member of it.
+struct Workspace {
+    /// The root of the workspace, i.e. the directory containing its `Cargo.toml` manifest.
+    root: path::PathBuf,
+
+    /// The path of the new project relative to `root`, as listed in `workspace.members`.
+    member: String,
+
+    /// The manifest of the workspace, with the new project added to its members.
+    manifest: toml_edit::DocumentMut,
+
+    /// Whether `manifest` has been changed, i.e. the new project isn't matched by a glob pattern
+    /// of `workspace.members` already.
+    manifest_changed: bool,
+
+    /// The path and contents of the workspace's configuration file, if the new project declares
+    /// any plugins.
+    config: Option<(path::PathBuf, toml_edit::DocumentMut)>,
+
+    /// The summary of the changes to the files of the workspace, and the new project's manifest,
+    /// i.e. how the file was changed, its path, and what was changed.
+    changes: Vec<(&'static str, path::PathBuf, String)>,
+}
+impl Workspace {
+    /// The workspace enclosing `destination`, like Cargo the first ancestor directory whose
+    /// `Cargo.toml` manifest has a `[workspace]` table, unless it excludes `destination`.
+    fn enclosing(destination: &path::Path) -> anyhow::Result<Option<Self>> {
+        let destination = path::absolute(destination)?;
+        let Some((root, manifest)) = destination.ancestors().skip(1).find_map(|dir| {
+            let manifest = fs::read_to_string(dir.join("Cargo.toml"))
+                .ok()?
+                .parse::<toml_edit::DocumentMut>()
+                .ok()?;
+            manifest
+                .contains_key("workspace")
+                .then(|| (dir.to_path_buf(), manifest))
+        }) else {
+            return Ok(None);
+        };
+        let member = destination
+            .strip_prefix(&root)?
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let matches = |key: &str| {
+            manifest["workspace"]
+                .get(key)
+                .and_then(toml_edit::Item::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(toml_edit::Value::as_str)
+                .find(|pattern| {
+                    pattern.trim_start_matches("./") == member
+                        || pattern
+                            .parse::<PluginPattern>()
+                            .is_ok_and(|pattern| pattern.matches(&member))
+                })
+                .map(String::from)
+        };
+        if matches("exclude").is_some() {
+            return Ok(None);
+        }
+        let matched_by = matches("members");
+
+        let mut workspace = Self {
+            manifest_changed: false,
+            config: None,
+            changes: Vec::new(),
+            root,
+            member,
+            manifest,
+        };
+        match matched_by {
+            Some(pattern) => println!(
+                "    {} adding `{}` to `workspace.members`, it's already matched by `{pattern}`",
+                "Skipping".yellow().bold(),
+                workspace.member
+            ),
+            None => {
+                let members = workspace.manifest["workspace"]
+                    .as_table_like_mut()
+                    .ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "the `workspace` of '{}' is not a table",
+                            workspace.root.join("Cargo.toml").display()
+                        )
+                    })?
+                    .entry("members")
+                    .or_insert(toml_edit::value(toml_edit::Array::new()));
+                members
+                    .as_array_mut()
+                    .ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "the `workspace.members` of '{}' is not an array",
+                            workspace.root.join("Cargo.toml").display()
+                        )
+                    })?
+                    .push(workspace.member.as_str());
+                workspace.manifest_changed = true;
+                workspace.changes.push((
+                    "Updated",
+                    workspace.root.join("Cargo.toml"),
+                    format!("added `{}` to `workspace.members`", workspace.member),
+                ));
+            }
+        }
+        Ok(Some(workspace))
+    }
+
+    /// Adapt the `manifest` of the new project to the workspace: the dependencies of the workspace
+    /// are inherited from it, and tables that are only respected in the root manifest of a
+    /// workspace are removed.
+    fn adapt_member_manifest(&mut self, manifest: &mut toml_edit::DocumentMut) {
+        let mut adjustments = Vec::new();
+        for key in ["workspace", "profile", "patch", "replace"] {
+            if manifest.remove(key).is_some() {
+                adjustments.push(format!("removed `[{key}]`"));
+            }
+        }
+
+        let workspace_dependencies = self.manifest["workspace"]
+            .get("dependencies")
+            .and_then(toml_edit::Item::as_table_like);
+        let mut inherited = Vec::new();
+        if let Some(workspace_dependencies) = workspace_dependencies
+            && let Some(dependencies) = manifest
+                .get_mut("dependencies")
+                .and_then(toml_edit::Item::as_table_like_mut)
+        {
+            for (name, dependency) in dependencies.iter_mut() {
+                if !workspace_dependencies.contains_key(&name) {
+                    continue;
+                }
+                // Features and optionality can be set by members, unlike the source.
+                let mut inherited_dependency = toml_edit::InlineTable::new();
+                inherited_dependency.insert("workspace", true.into());
+                if let Some(dependency) = dependency.as_table_like() {
+                    for key in ["features", "optional", "default-features"] {
+                        if let Some(value) = dependency.get(key).and_then(toml_edit::Item::as_value)
+                        {
+                            inherited_dependency.insert(key, value.clone());
+                        }
+                    }
+                }
+                *dependency = toml_edit::value(inherited_dependency);
+                inherited.push(format!("`{name}`"));
+            }
+        }
+        if !inherited.is_empty() {
+            adjustments.push(format!(
+                "inherited {} from `workspace.dependencies`",
+                inherited.join(", ")
+            ));
+        }
+
+        if !adjustments.is_empty() {
+            self.changes.push((
+                "Adjusted",
+                self.root.join(&self.member).join("Cargo.toml"),
+                adjustments.join(", "),
+            ));
+        }
+    }
+
+    /// Add the plugin(s) declared by the `config` of the new project to the configuration file of
+    /// the workspace, which is created from `config` if it doesn't exist.
+    fn add_plugins(&mut self, config: &toml_edit::DocumentMut) -> anyhow::Result<()> {
+        let existing_file = CONFIG_FILE_NAMES
+            .iter()
+            .map(|config_file_name| self.root.join(config_file_name))
+            .find(|config_file| config_file.is_file());
+        let exists = existing_file.is_some();
+        let (file, mut workspace_config) = match existing_file {
+            Some(file) => {
+                let workspace_config = fs::read_to_string(&file)?
+                    .parse::<toml_edit::DocumentMut>()
+                    .map_err(|err| {
+                        anyhow::anyhow!("failed to parse '{}':\n{err}", file.display())
+                    })?;
+                (file, workspace_config)
+            }
+            None => {
+                let mut workspace_config = config.clone();
+                if let Some(extension_plugins) = workspace_config
+                    .get_mut("extension_plugins")
+                    .and_then(toml_edit::Item::as_table_like_mut)
+                {
+                    extension_plugins.clear();
+                }
+                (self.root.join(CONFIG_FILE_NAMES[1]), workspace_config)
+            }
+        };
+
+        let extension_plugins = workspace_config
+            .entry("extension_plugins")
+            .or_insert(toml_edit::table())
+            .as_table_like_mut()
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "the `extension_plugins` of '{}' is not a table",
+                    file.display()
+                )
+            })?;
+        let mut added = Vec::new();
+        for (plugin_name, plugin_path) in config
+            .get("extension_plugins")
+            .and_then(toml_edit::Item::as_table_like)
+            .into_iter()
+            .flat_map(|plugins| plugins.iter())
+        {
+            if extension_plugins.contains_key(plugin_name) {
+                anyhow::bail!(
+                    "the plugin `{plugin_name}` is already declared in '{}'\n\nTip: pass `--name` to choose another package name.",
+                    file.display()
+                );
+            }
+            // Paths are relative to the configuration file, i.e. the root of the workspace.
+            let plugin_path = match plugin_path.as_str().unwrap_or(".") {
+                "." | "./" | "./." => self.member.clone(),
+                plugin_path => format!("{}/{}", self.member, plugin_path.trim_start_matches("./")),
+            };
+            extension_plugins.insert(plugin_name, toml_edit::value(plugin_path));
+            added.push(format!("`{plugin_name}`"));
+        }
+        if !added.is_empty() {
+            self.changes.push((
+                if exists { "Updated" } else { "Created" },
+                file.clone(),
+                format!("added {} to `extension_plugins`", added.join(", ")),
+            ));
+        }
+        self.config = Some((file, workspace_config));
+        Ok(())
+    }
+
+    /// Write the changes to the files of the workspace, and print a summary of every changed file.
+    fn save(&self) -> anyhow::Result<()> {
+        if self.manifest_changed {
+            fs::write(self.root.join("Cargo.toml"), self.manifest.to_string())?;
+        }
+        if let Some((file, config)) = &self.config {
+            fs::write(file, config.to_string())?;
+        }
+        for (change, file, summary) in &self.changes {
+            println!(
+                "{} '{}' ({summary})",
+                format!("{change:>12}").green().bold(),
+                file.display()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Clone the template repository at `url` into `dir`, checked out at `branch` or `rev`, and remove
+/// its history, so that the new project starts out without any.
+///
+/// This runs `git`, since `gix` is built without its networking features.
+fn clone_template(
+    url: &str,
+    branch: Option<&str>,
+    rev: Option<&str>,
+    dir: &path::Path,
+) -> anyhow::Result<()> {
+    let git = |configure: &dyn Fn(&mut process::Command)| {
+        let mut git = process::Command::new("git");
+        configure(&mut git);
+        git.stdin(process::Stdio::null())
+            .env("GIT_TERMINAL_PROMPT", "0")
+            .output()
+            .map_err(|err| {
+                anyhow::anyhow!("failed to run `git`, which is required by `--template-git`: {err}")
+            })
+    };
+
+    println!("     {} {url}", "Cloning".green().bold());
+    let output = git(&|git| {
+        git.args(["clone", "--quiet"]);
+        // A revision may be any commit, which requires the full history to check it out.
+        if rev.is_none() {
+            git.args(["--depth", "1"]);
+        }
+        if let Some(branch) = branch {
+            git.args(["--branch", branch]);
+        }
+        git.args(["--", url]).arg(dir);
+    })?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "failed to clone the template repository `{url}`:\n{}",
+            String::from_utf8_lossy(&output.stderr).trim_end()
+        );
+    }
+
+    if let Some(rev) = rev {
+        let output = git(&|git| {
+            git.arg("-C").arg(dir).args([
+                "rev-parse",
+                "--verify",
+                "--quiet",
+                &format!("{rev}^{{commit}}"),
+            ]);
+        })?;
+        if !output.status.success() {
+            anyhow::bail!("the revision `{rev}` does not exist in the template repository `{url}`");
+        }
+        let commit = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        let output = git(&|git| {
+            git.arg("-C")
+                .arg(dir)
+                .args(["checkout", "--quiet", "--detach", &commit]);
+        })?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "failed to check out the revision `{rev}` of the template repository `{url}`:\n{}",
+                String::from_utf8_lossy(&output.stderr).trim_end()
+            );
+        }
+    }
+
+    fs::remove_dir_all(dir.join(".git"))?;
+    Ok(())
+}
+
+/// Copy the contents of the template directory `src` into `dst`, following symlinks, so that the
+/// new project contains their targets rather than links into the template. Cargo's target
+/// directory and the git repository are skipped at the `root` of the template.
+fn copy_template(src: &path::Path, dst: &path::Path, root: bool) -> anyhow::Result<()> {
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        if root && (entry.file_name() == "target" || entry.file_name() == ".git") {
+            continue;
+        }
+        let path = entry.path();
+        let metadata = fs::metadata(&path).map_err(|err| {
+            anyhow::anyhow!(
+                "failed to copy '{}' from the template: {err}",
+                path.display()
+            )
+        })?;
+        let destination = dst.join(entry.file_name());
+        if metadata.is_dir() {
+            fs::create_dir(&destination)?;
+            copy_template(&path, &destination, false)?;
+        } else {
+            fs::copy(&path, &destination).map_err(|err| {
+                anyhow::anyhow!(
+                    "failed to copy '{}' from the template: {err}",
+                    path.display()
+                )
+            })?;
+            // The names in the manifest and configuration file are changed in place.
+            let mut permissions = metadata.permissions();
+            if permissions.readonly() {
+                cfg_select! {
+                    unix => {
+                        use std::os::unix::fs::PermissionsExt;
+                        permissions.set_mode(permissions.mode() | 0o200);
+                    }
+                    _ => {
+                        #[allow(clippy::permissions_set_readonly_false)]
+                        permissions.set_readonly(false);
+                    }
+                }
+                fs::set_permissions(&destination, permissions)?;
+            }
+        }
+    }
+    Ok(())
+}
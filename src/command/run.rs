@@ -1,10 +1,294 @@
-use std::{io, path, process, thread, time};
+use std::{collections, fmt, fs, io, path, process, sync, thread, time};
 
+pub(crate) mod event;
+#[cfg(target_os = "linux")]
+pub(crate) mod headless;
+mod hooks;
+#[cfg(windows)]
+mod job;
+mod junit;
+mod progress;
+#[cfg(unix)]
+mod sanitizer;
+#[cfg(target_os = "linux")]
+mod valgrind;
+
+use self::{event::Event, progress::Progress};
 use crate::{
     cli,
-    util::{self, BINARY_NAME, Colorize},
+    config::ReaperPluginConfig,
+    util::{self, BINARY_NAME, Colorize, TargetOs, find_project_root, status_println},
 };
 
+/// Warn about plugins that are older than their sources, or symlinked to another build than the
+/// newest one, since REAPER is run without building them. Nothing is checked outside of a
+/// `cargo-reaper` project, or when passed `--no-staleness-check`.
+pub(crate) fn warn_stale_plugins(
+    no_staleness_check: bool,
+    portable: Option<&path::Path>,
+) -> anyhow::Result<()> {
+    if no_staleness_check {
+        return Ok(());
+    }
+    if let Ok(project_root) = find_project_root() {
+        let config = ReaperPluginConfig::load(&project_root)?;
+        let user_plugins_dir = util::resource_dir(portable)?.join("UserPlugins");
+        util::warn_stale_plugins(&project_root, &config, &user_plugins_dir)?;
+        for plugin_name in config.extension_plugins().keys() {
+            if let Some(artifact) = util::newest_artifact(
+                &util::project_target_dir(&project_root),
+                &TargetOs::host().add_plugin_ext(plugin_name.as_ref()),
+            ) {
+                util::warn_mismatched_symlink(&artifact, &user_plugins_dir);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Warn about plugins that are symlinked to another build than the `artifacts` that were just
+/// built, e.g. because they were built for a cross compilation target, and aren't symlinked.
+pub(crate) fn warn_mismatched_symlinks(
+    artifacts: &[path::PathBuf],
+    portable: Option<&path::Path>,
+) -> anyhow::Result<()> {
+    let user_plugins_dir = util::resource_dir(portable)?.join("UserPlugins");
+    for artifact in artifacts {
+        util::warn_mismatched_symlink(artifact, &user_plugins_dir);
+    }
+    Ok(())
+}
+
+/// The arguments passed to `cargo build` before running REAPER, instrumenting plugin(s) when
+/// passed `--sanitizer`.
+pub(crate) fn build_args(args: &cli::RunArgs) -> anyhow::Result<Vec<String>> {
+    let build_args = args.build_args();
+    #[cfg(unix)]
+    if let Some(sanitizer) = args.sanitizer {
+        return sanitizer::build_args(sanitizer, build_args);
+    }
+    Ok(build_args)
+}
+
+/// Create a throwaway resource directory for `--fresh-config`, which REAPER is pointed at in
+/// place of a portable installation.
+pub(crate) fn fresh_config(args: &mut cli::RunArgs) -> anyhow::Result<Option<FreshConfig>> {
+    if !args.fresh_config {
+        return Ok(None);
+    }
+    let fresh_config = FreshConfig::new(args.keep_config)?;
+    args.portable = Some(fresh_config.temp_dir.path().to_path_buf());
+    Ok(Some(fresh_config))
+}
+
+/// Copy the contents of `--install-config` into REAPER's resource path, reporting each file
+/// copied. Existing files are skipped unless passed `--force`, or the resource path was created
+/// by `--fresh-config`.
+pub(crate) fn install_config(args: &cli::RunArgs) -> anyhow::Result<()> {
+    let Some(config_dir) = args.install_config.as_deref() else {
+        return Ok(());
+    };
+    if !config_dir.is_dir() {
+        anyhow::bail!(
+            "REAPER configuration directory '{}' does not exist",
+            config_dir.display()
+        );
+    }
+    let resource_dir = util::resource_dir(args.portable.as_deref())?;
+    let overwrite = args.force || args.fresh_config;
+
+    let mut dirs = vec![config_dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let from = entry.path();
+            let to = resource_dir.join(from.strip_prefix(config_dir)?);
+            if entry.file_type()?.is_dir() {
+                dirs.push(from);
+                continue;
+            }
+            if to.exists() && !overwrite {
+                status_println!(
+                    "{}: skipping '{}', it already exists (pass `--force` to overwrite it)",
+                    "warning".yellow().bold(),
+                    to.display()
+                );
+                continue;
+            }
+            to.parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| fs::copy(&from, &to))
+                .map_err(|err| {
+                    anyhow::anyhow!(
+                        "failed to copy '{}' to '{}':\n{err:#?}",
+                        from.display(),
+                        to.display()
+                    )
+                })?;
+            status_println!(
+                "      {} {} -> {}",
+                "Copied".green().bold(),
+                from.display(),
+                to.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Apply the defaults in the `[run]` table of the `cargo-reaper` configuration file to the flags
+/// that were not passed on the command line, or set by their environment variable, returning the
+/// flags each default was applied as. Nothing is applied outside of a `cargo-reaper` project.
+pub(crate) fn apply_config_defaults(
+    args: &mut cli::RunArgs,
+    matches: &clap::ArgMatches,
+) -> anyhow::Result<Vec<String>> {
+    let Ok(project_root) = find_project_root() else {
+        return Ok(Vec::new());
+    };
+    let config = ReaperPluginConfig::load(&project_root)?;
+    let defaults = config.run();
+    // Defaults that conflict with `--detach` are not applied when detaching.
+    let detach = args.detach;
+    let conflicts_with_detach = |id: &str| {
+        detach
+            && matches!(
+                id,
+                "timeout"
+                    | "window_title"
+                    | "window_class"
+                    | "wait_for_file"
+                    | "fail_on_window"
+                    | "require_plugin_loaded"
+                    | "headless"
+                    | "stdin"
+                    | "stdout"
+                    | "stderr"
+                    | "after"
+            )
+    };
+    let is_default = |id: &str| {
+        let is_passed = matches!(
+            matches.value_source(id),
+            Some(clap::parser::ValueSource::CommandLine | clap::parser::ValueSource::EnvVariable)
+        );
+        !(is_passed || conflicts_with_detach(id))
+    };
+    let mut applied = Vec::new();
+
+    if let Some(exec) = defaults.exec.as_deref()
+        && is_default("reaper")
+    {
+        args.reaper = Some(project_root.join(exec));
+        applied.push(format!("--exec {}", exec.display()));
+    }
+    if let Some(project) = defaults.project.as_deref()
+        && is_default("project")
+    {
+        args.project = vec![project_root.join(project)];
+        applied.push(format!("--open {}", project.display()));
+    }
+    if let Some(timeout) = defaults.timeout
+        && is_default("timeout")
+    {
+        args.timeout = Some(timeout);
+        applied.push(format!("--timeout {}", humantime::format_duration(timeout)));
+    }
+    // Only one of the windows to locate is applied, so it never conflicts with another flag.
+    if let Some(window_title) = defaults.window_title.as_ref()
+        && is_default("window_title")
+        && is_default("window_regex")
+    {
+        args.window_title = Some(window_title.clone());
+        applied.push(format!("--locate-window {window_title}"));
+    }
+    #[cfg(target_os = "linux")]
+    if let Some(window_class) = defaults.window_class.as_ref()
+        && is_default("window_class")
+    {
+        args.window_class = Some(window_class.clone());
+        applied.push(format!("--locate-class {window_class}"));
+    }
+    if let Some(wait_for_file) = defaults.wait_for_file.as_deref()
+        && is_default("wait_for_file")
+    {
+        args.wait_for_file = Some(project_root.join(wait_for_file));
+        applied.push(format!("--wait-for-file {}", wait_for_file.display()));
+    }
+    if let Some(fail_on_window) = defaults.fail_on_window.as_ref()
+        && is_default("fail_on_window")
+    {
+        args.fail_on_window = fail_on_window.clone();
+        applied.extend(
+            fail_on_window
+                .iter()
+                .map(|title| format!("--fail-on-window {title}")),
+        );
+    }
+    if let Some(plugins) = defaults.require_plugin_loaded.as_ref()
+        && is_default("require_plugin_loaded")
+    {
+        args.require_plugin_loaded = Some(plugins.clone());
+        applied.push(format!("--require-plugin-loaded {}", plugins.join(" ")));
+    }
+    #[cfg(target_os = "linux")]
+    {
+        if let Some(headless) = defaults.headless
+            && is_default("headless")
+        {
+            args.headless = headless;
+            if headless {
+                applied.push("--headless".to_string());
+            }
+        }
+        if let Some(display) = defaults.display.as_ref()
+            && is_default("display")
+        {
+            args.display = display.clone();
+            applied.push(format!("--display {display}"));
+        }
+    }
+    for (id, stdio, default) in [
+        ("stdin", &mut args.stdin, defaults.stdin.as_ref()),
+        ("stdout", &mut args.stdout, defaults.stdout.as_ref()),
+        ("stderr", &mut args.stderr, defaults.stderr.as_ref()),
+    ] {
+        if let Some(default) = default
+            && is_default(id)
+        {
+            *stdio = default.clone();
+            applied.push(format!("--{id} {default}"));
+        }
+    }
+    if let Some(env) = defaults.env.as_ref()
+        && is_default("env")
+    {
+        args.env = env
+            .iter()
+            .map(|(key, value)| cli::EnvVar {
+                key: key.clone(),
+                value: value.clone(),
+            })
+            .collect();
+        applied.extend(
+            env.iter()
+                .map(|(key, value)| format!("--env {key}={value}")),
+        );
+    }
+    for (id, hooks, default) in [
+        ("before", &mut args.before, defaults.before.as_ref()),
+        ("after", &mut args.after, defaults.after.as_ref()),
+    ] {
+        if let Some(default) = default
+            && is_default(id)
+        {
+            *hooks = default.clone();
+            applied.extend(default.iter().map(|hook| format!("--{id} {hook}")));
+        }
+    }
+    Ok(applied)
+}
+
 /// Launch the REAPER binary application. The current working directory takes priority,
 /// but if the binary file is not on `$PATH`, the global default location will be used.
 ///
@@ -14,354 +298,2634 @@ use crate::{
 ///
 /// This is run automatically when running the `cargo reaper run` command.
 pub(crate) fn run(
-    override_binary: Option<path::PathBuf>,
-    project: Option<path::PathBuf>,
-    timeout: Option<time::Duration>,
-    stdin: cli::Stdio,
-    stdout: cli::Stdio,
-    stderr: cli::Stdio,
-) -> anyhow::Result<()> {
-    override_binary
-        .inspect(|reaper| {
-            println!(
-                "{}: overridng REAPER executable path ({})",
-                "warning".yellow().bold(),
-                reaper.display()
-            )
-        })
-        .or_else(|| which::which(BINARY_NAME).ok())
-        .map_or_else(
-            || run_global_default(project.as_ref(), timeout.as_ref(), stdin, stdout, stderr),
-            |reaper| {
-                println!(
-                    "     {} REAPER executable ({})",
-                    "Running".green().bold(),
-                    reaper.display(),
+    mut args: cli::RunArgs,
+    fresh_config: Option<FreshConfig>,
+) -> anyhow::Result<RunOutcome> {
+    prepare(&mut args)?;
+    #[cfg(target_os = "linux")]
+    if args.valgrind {
+        status_println!(
+            "{}: REAPER runs extremely slowly under `valgrind`, consider a generous `--timeout`{}",
+            "warning".yellow().bold(),
+            if args.fresh_config {
+                ""
+            } else {
+                ", and `--fresh-config` to avoid loading other plugins"
+            }
+        );
+    }
+    let ephemeral = args
+        .ephemeral
+        .then(|| EphemeralProjects::new(&mut args.project, args.keep))
+        .transpose()?;
+    let script = args
+        .script
+        .as_deref()
+        .map(|script| install_script(script, args.portable.as_deref()))
+        .transpose()?;
+    let splash_log = SplashLog::new(
+        args.splash_log.clone(),
+        args.require_plugin_loaded.clone(),
+        args.fail_on_log_pattern.clone(),
+    )?;
+    if let Some(marker) = args.wait_for_file.as_deref()
+        && !args.no_clean_marker
+    {
+        remove_marker_file(marker)?;
+    }
+    let reaper = locate_reaper(args.reaper.clone(), args.portable.as_deref(), false)
+        .map_err(|err| anyhow::anyhow!("While attempting to run REAPER executable: {err:?}"))?;
+    if let Some(min_version) = args.min_reaper_version.as_ref() {
+        require_reaper_version(&reaper, min_version)?;
+    }
+    let display = hook_display(&args).map(String::from);
+    if !hooks::run(
+        hooks::Stage::Before,
+        &args.before,
+        None,
+        display.as_deref(),
+        args.strict_hooks,
+    ) && args.strict_hooks
+    {
+        hooks::run(
+            hooks::Stage::After,
+            &args.after,
+            None,
+            display.as_deref(),
+            args.strict_hooks,
+        );
+        anyhow::bail!("a `--before` hook failed, so REAPER was not launched");
+    }
+    let start = time::SystemTime::now();
+    let mut reaper_pid = None;
+    let outcome = io::Result::Ok(reaper)
+        .and_then(|reaper| {
+            if !args.new_instance && !args.no_new_instance && is_running(&reaper) {
+                if args.kill_existing {
+                    kill_existing(&reaper, args.kill_grace)?;
+                } else {
+                    status_println!(
+                        "{}: REAPER is already running, plugin(s) will not be reloaded into the existing instance unless it is closed first, or `--kill-existing` or `--new-instance` is passed",
+                        "warning".yellow().bold(),
+                    );
+                }
+            }
+            // The handler is only installed once when running several sessions, e.g. test cases.
+            match ctrlc::set_handler(|| INTERRUPTED.store(true, sync::atomic::Ordering::SeqCst)) {
+                Ok(()) | Err(ctrlc::Error::MultipleHandlers) => {}
+                Err(err) => return Err(io::Error::other(err)),
+            }
+            let mut session =
+                spawn(&reaper, &mut args, script.as_deref(), splash_log, fresh_config)?;
+            reaper_pid = Some(session.reaper.id());
+            if args.detach {
+                status_println!(
+                    "    {} REAPER (pid {}, {})",
+                    "Detached".green().bold(),
+                    session.reaper.id(),
+                    reaper.display()
                 );
-
-                timeout.map_or_else(
-                    || {
-                        run_reaper(
-                            &reaper,
-                            project.as_ref(),
-                            stdin.into(),
-                            stdout.into(),
-                            stderr.into(),
-                        )?
-                        .wait()
-                    },
-                    |timeout| {
-                        let (mut reaper, start) = run_reaper(
-                            &reaper,
-                            project.as_ref(),
-                            stdin.into(),
-                            stdout.into(),
-                            stderr.into(),
-                        )
-                        .map(|reaper| (reaper, time::Instant::now()))?;
-
-                        loop {
-                            match reaper.try_wait()? {
-                                Some(status) => break Ok(status),
-                                None if start.elapsed() >= timeout => {
-                                    reaper.kill()?;
-                                    break reaper.wait();
-                                }
-                                None => thread::sleep(time::Duration::from_secs(1)),
-                            }
+                return Ok(RunOutcome {
+                    status: 0,
+                    reason: RunReason::Detached,
+                });
+            }
+            let mut outcome = if args.debugger.is_some() {
+                wait_for_debugger(&mut session)?
+            } else {
+                wait(&mut session, &args)?
+            };
+            #[cfg(target_os = "linux")]
+            if args.valgrind && valgrind::report(start) && outcome.status == 0 {
+                outcome.status = valgrind::ERROR_EXIT_CODE;
+            }
+            #[cfg(unix)]
+            if args.sanitizer.is_some() && sanitizer::report(start) && outcome.status == 0 {
+                outcome.status = 1;
+            }
+            if let Some(report) = args.report_junit.as_deref() {
+                match junit::write(
+                    report,
+                    &junit::suite_name(),
+                    &session.assertions.results,
+                    session.assertions.start.elapsed(),
+                    &session
+                        .log_scanner
+                        .as_ref()
+                        .map(LogScanner::output)
+                        .unwrap_or_default(),
+                ) {
+                    Ok(()) => status_println!(
+                        "       {} JUnit report {}",
+                        "Wrote".green().bold(),
+                        report.display()
+                    ),
+                    Err(err) => {
+                        eprintln!(
+                            "{}: failed to write JUnit report '{}': {err}",
+                            "error".magenta(),
+                            report.display()
+                        );
+                        if outcome.status == 0 {
+                            outcome.status = 1;
                         }
-                    },
-                )
-            },
-        )
+                    }
+                }
+            }
+            Ok(outcome)
+        });
+    // `--after` hooks run however the session ended, e.g. when it failed to start.
+    let after_hooks_succeeded = hooks::run(
+        hooks::Stage::After,
+        &args.after,
+        reaper_pid,
+        hook_display(&args),
+        args.strict_hooks,
+    );
+    let mut outcome = outcome
         .map_err(|err| anyhow::anyhow!("While attempting to run REAPER executable: {err:?}"))?;
+    if args.strict_hooks && !after_hooks_succeeded && outcome.status == 0 {
+        outcome.status = 1;
+    }
+
+    if let Some(tee) = args.tee.as_deref() {
+        status_println!(
+            "       {} REAPER's output to {}",
+            "Saved".green().bold(),
+            tee.display()
+        );
+    }
+    if let Some(log_file) = args.log_file.as_deref() {
+        status_println!(
+            "      {} REAPER's output to {}",
+            "Logged".green().bold(),
+            log_file.display()
+        );
+        #[cfg(target_os = "linux")]
+        if args.headless {
+            let backend = headless::backend(args.backend);
+            status_println!(
+                "      {} {}'s output to {}",
+                "Logged".green().bold(),
+                backend.program(),
+                headless::log_file(log_file, backend.as_ref()).display()
+            );
+        }
+    }
+
+    match &outcome.reason {
+        RunReason::Exited(status) => {
+            if args.render
+                && let Some(project) = args.project.first()
+            {
+                report_render(project, start, *status)?;
+            }
+            if !status.success() {
+                eprintln!(
+                    "{}: REAPER exited unsuccessfully ({status})",
+                    "error".magenta()
+                );
+            }
+        }
+        RunReason::ErrorWindowLocated(error_window) => {
+            eprintln!("{}: located error {error_window}", "error".magenta());
+        }
+        RunReason::LogPatternMatched(line) => {
+            eprintln!(
+                "{}: REAPER's output matched `--fail-on-log-pattern`: {line}",
+                "error".magenta()
+            );
+        }
+        _ => {}
+    }
+    if let Some(ephemeral) = ephemeral {
+        ephemeral.finish();
+    }
+    event::emit(Event::Finished {
+        status: outcome.status,
+        reason: outcome.reason.name(),
+    });
 
+    Ok(outcome)
+}
+
+/// The display REAPER is attached to, as given to `--before` and `--after` hooks, when running
+/// headless on a known display.
+fn hook_display(
+    #[cfg_attr(not(target_os = "linux"), expect(unused_variables))] args: &cli::RunArgs,
+) -> Option<&str> {
+    cfg_select! {
+        target_os = "linux" => {
+            (args.headless && args.display != cli::AUTO_XSERVER_DISPLAY)
+                .then_some(args.display.as_str())
+        }
+        _ => None,
+    }
+}
+
+/// Validate the combinations of `args` that can't be expressed by `clap` (e.g. because they can
+/// be configured), probe for the programs the run depends on, and apply the sanitizer's
+/// environment.
+fn prepare(args: &mut cli::RunArgs) -> anyhow::Result<()> {
+    #[cfg(target_os = "linux")]
+    if args.headless && args.debugger.is_some() {
+        anyhow::bail!("`--debugger` can't be used when running REAPER with `--headless`");
+    }
+    #[cfg(target_os = "linux")]
+    preflight(args)?;
+    // The sanitizer's environment is applied before `--env`, which can override it.
+    #[cfg(unix)]
+    if let Some(sanitizer) = args.sanitizer {
+        args.env.splice(0..0, sanitizer::env(sanitizer)?);
+    }
+    for project in args.project.iter_mut() {
+        *project = resolve_project(project)?;
+    }
+    if args.render && args.project.len() > 1 {
+        anyhow::bail!(
+            "`--render` renders a single project, but {} were passed to `--open`",
+            args.project.len()
+        );
+    }
+    let is_scanned = |stdio: &cli::Stdio| matches!(stdio, cli::Stdio::Inherit | cli::Stdio::Piped);
+    if !args.fail_on_log_pattern.is_empty()
+        && args.log_file.is_none()
+        && !is_scanned(&args.stdout)
+        && !is_scanned(&args.stderr)
+    {
+        anyhow::bail!(
+            "`--fail-on-log-pattern` requires REAPER's stdout or stderr to be captured, but neither is `inherit` or `piped`"
+        );
+    }
     Ok(())
 }
 
-#[allow(clippy::too_many_arguments)]
-#[cfg(target_os = "linux")]
-pub(crate) fn run_headless(
-    override_binary: Option<path::PathBuf>,
-    project: Option<path::PathBuf>,
-    display: String,
-    window_title: Option<String>,
-    keep_going: bool,
-    timeout: Option<time::Duration>,
-    stdin: cli::Stdio,
-    stdout: cli::Stdio,
-    stderr: cli::Stdio,
+/// The absolute path to a project passed to `--open` (or set in the configuration file), which
+/// must be an existing regular file, warning when it doesn't look like a REAPER project.
+fn resolve_project(project: &path::Path) -> anyhow::Result<path::PathBuf> {
+    let resolved = project.canonicalize().map_err(|err| {
+        anyhow::anyhow!("project file `{}` does not exist: {err}", project.display())
+    })?;
+    if !resolved.is_file() {
+        anyhow::bail!(
+            "project file `{}` is not a regular file",
+            resolved.display()
+        );
+    }
+    if !resolved
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("rpp"))
+    {
+        status_println!(
+            "{}: project file `{}` does not have an `.rpp` extension, REAPER may not open it as a project",
+            "warning".yellow().bold(),
+            resolved.display()
+        );
+    }
+    Ok(resolved)
+}
+
+/// Print the commands `cargo reaper run` would execute (`--dry-run`): `cargo build` with
+/// `build_args` (unless passed `--no-build`), the virtual display server when running headless,
+/// and REAPER, along with REAPER's working directory and the environment variables it is given.
+/// Nothing is spawned, and nothing is copied into REAPER's resource path. Temporary directories
+/// that would be created are shown as `<temporary directory>`.
+pub(crate) fn dry_run(
+    mut args: cli::RunArgs,
+    build_args: Option<Vec<String>>,
 ) -> anyhow::Result<()> {
-    override_binary
-        .inspect(|reaper| {
+    let temp_dir = path::Path::new("<temporary directory>");
+    prepare(&mut args)?;
+    if let Some(build_args) = build_args {
+        print_command(process::Command::new("cargo").arg("build").args(build_args));
+    }
+    if args.fresh_config {
+        args.portable = Some(temp_dir.to_path_buf());
+    }
+    if args.ephemeral {
+        for project in args.project.iter_mut() {
+            *project = temp_dir.join(project.file_name().unwrap_or_default());
+        }
+    }
+    let script = args
+        .script
+        .as_deref()
+        .map(|script| installed_script_path(script, args.portable.as_deref()))
+        .transpose()?;
+    let splash_log = args.splash_log.clone().or_else(|| {
+        args.require_plugin_loaded
+            .is_some()
+            .then(|| temp_dir.join("splash.log"))
+    });
+    let reaper = locate_reaper(args.reaper.clone(), args.portable.as_deref(), true)
+        .map_err(|err| anyhow::anyhow!("While attempting to run REAPER executable: {err:?}"))?;
+    if let Some(min_version) = args.min_reaper_version.as_ref() {
+        require_reaper_version(&reaper, min_version)?;
+    }
+
+    #[cfg(target_os = "linux")]
+    if args.headless {
+        let mut backend = headless::backend(args.backend);
+        let program = args
+            .xvfb_path
+            .clone()
+            .unwrap_or_else(|| backend.program().into());
+        print_command(&headless::dry_run_command(
+            backend.as_mut(),
+            &program,
+            &mut args,
+        ));
+        args.env.push(cli::EnvVar {
+            key: "DISPLAY".to_string(),
+            value: args.display.clone(),
+        });
+    }
+    let command = reaper_command(&reaper, &args, script.as_deref(), splash_log.as_deref())?;
+    for hook in &args.before {
+        print_command(&hooks::command(hook, None, hook_display(&args)));
+    }
+    print_command(&command);
+    for hook in &args.after {
+        print_command(&hooks::command(hook, None, hook_display(&args)));
+    }
+    println!(
+        "          {} {}",
+        "in".green().bold(),
+        std::env::current_dir()?.display()
+    );
+    for (key, value) in command.get_envs() {
+        if let Some(value) = value {
             println!(
-                "{}: overridng REAPER executable path ({})",
-                "warning".yellow().bold(),
-                reaper.display()
+                "        {} {}={}",
+                "with".green().bold(),
+                key.display(),
+                value.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Print the command line of a command that would be run by `--dry-run`.
+fn print_command(command: &process::Command) {
+    let command_line = std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(|arg| {
+            let arg = arg.to_string_lossy();
+            // Arguments are quoted so that the command line can be pasted into a shell.
+            if !arg.is_empty()
+                && arg
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c))
+            {
+                arg.into_owned()
+            } else {
+                format!("'{}'", arg.replace('\'', r"'\''"))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    println!("   {} {command_line}", "Would run".green().bold());
+}
+
+/// Probe for the programs a headless run depends on, failing with install hints before REAPER is
+/// launched rather than with a spawn failure, or a window that is never found.
+#[cfg(target_os = "linux")]
+fn preflight(args: &mut cli::RunArgs) -> anyhow::Result<()> {
+    /// Resolve `program` from `$PATH`, or bail with the packages that provide it.
+    fn require(program: &str, reason: &str, packages: [&str; 3]) -> anyhow::Result<path::PathBuf> {
+        which::which(program).map_err(|_| {
+            let [apt, dnf, pacman] = packages;
+            anyhow::anyhow!(
+                "`{program}` was not found on `$PATH`, which is required {reason}. Install it with your distribution's package manager, e.g.\n  Debian/Ubuntu: `apt install {apt}`\n  Fedora: `dnf install {dnf}`\n  Arch Linux: `pacman -S {pacman}`"
             )
         })
-        .or_else(|| which::which(BINARY_NAME).ok())
+    }
+
+    if args.valgrind {
+        require(
+            valgrind::VALGRIND,
+            "by `--valgrind`",
+            [valgrind::VALGRIND; 3],
+        )?;
+    }
+    if !args.headless {
+        return Ok(());
+    }
+    let backend = headless::backend(args.backend);
+    args.xvfb_path = Some(match args.xvfb_path.take() {
+        Some(_) if args.backend != cli::Backend::Xvfb => {
+            anyhow::bail!("`--xvfb-path` can only be used with `--backend xvfb`")
+        }
+        Some(xvfb) if xvfb.is_file() => xvfb,
+        Some(xvfb) => anyhow::bail!("`Xvfb` executable '{}' does not exist", xvfb.display()),
+        None => require(
+            backend.program(),
+            "to run REAPER headless",
+            backend.packages(),
+        )?,
+    });
+    if backend.is_nested() && std::env::var_os("DISPLAY").is_none() {
+        anyhow::bail!(
+            "`{}` renders into a window on the host display, but `DISPLAY` is not set",
+            backend.program()
+        );
+    }
+    if args.backend == cli::Backend::Weston && std::env::var_os("XDG_RUNTIME_DIR").is_none() {
+        anyhow::bail!(
+            "`{}` requires `XDG_RUNTIME_DIR` to be set",
+            backend.program()
+        );
+    }
+    if args.window_title.is_some()
+        || args.window_regex.is_some()
+        || args.window_class.is_some()
+        || !args.fail_on_window.is_empty()
+        || !args.fail_on_window_regex.is_empty()
+        || args.timeout_after_startup
+    {
+        require(
+            XDOTOOL,
+            "to locate windows",
+            ["xdotool", "xdotool", "xdotool"],
+        )?;
+    }
+    if args.screenshot.is_some() {
+        require(
+            Screenshot::IMPORT,
+            "by `--screenshot`",
+            ["imagemagick", "ImageMagick", "imagemagick"],
+        )?;
+    }
+    if args.record.is_some() {
+        require(
+            Recorder::FFMPEG,
+            "by `--record`",
+            ["ffmpeg", "ffmpeg-free", "ffmpeg"],
+        )?;
+    }
+    Ok(())
+}
+
+/// Remove a stale `--wait-for-file` marker file left behind by a previous run, so it cannot be
+/// mistaken for the marker file being created.
+fn remove_marker_file(marker: &path::Path) -> anyhow::Result<()> {
+    match fs::remove_file(marker) {
+        Ok(()) => {
+            status_println!(
+                "     {} stale marker file {}",
+                "Removed".green().bold(),
+                marker.display()
+            );
+            Ok(())
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(anyhow::anyhow!(
+            "failed to remove stale marker file '{}':\n{err:#?}",
+            marker.display()
+        )),
+    }
+}
+
+/// The path a ReaScript is copied to by `install_script`.
+fn installed_script_path(
+    script: &path::Path,
+    portable: Option<&path::Path>,
+) -> anyhow::Result<path::PathBuf> {
+    if !script.is_file() {
+        anyhow::bail!("ReaScript '{}' does not exist", script.display());
+    }
+    Ok(util::resource_dir(portable)?
+        .join("Scripts")
+        .join("cargo-reaper")
+        .join(script.file_name().ok_or_else(|| {
+            anyhow::anyhow!(
+                "Unable to get ReaScript file name from path '{}'",
+                script.display()
+            )
+        })?))
+}
+
+/// Copy a ReaScript into the `Scripts` directory of REAPER's resource path, returning the path
+/// to the copy. Scripts are copied into a `cargo-reaper` subdirectory so that user scripts
+/// sharing the same file name are never overwritten.
+fn install_script(
+    script: &path::Path,
+    portable: Option<&path::Path>,
+) -> anyhow::Result<path::PathBuf> {
+    let installed_script = installed_script_path(script, portable)?;
+    let scripts_dir = installed_script.parent().unwrap_or(path::Path::new("."));
+    fs::create_dir_all(scripts_dir)
+        .and_then(|_| fs::copy(script, &installed_script))
+        .map_err(|err| {
+            anyhow::anyhow!(
+                "failed to copy ReaScript '{}' to '{}':\n{err:#?}",
+                script.display(),
+                scripts_dir.display()
+            )
+        })?;
+
+    status_println!(
+        "      {} ReaScript {} -> {}",
+        "Copied".green().bold(),
+        script.display(),
+        installed_script.display()
+    );
+
+    Ok(installed_script)
+}
+
+/// Print the exit status of a render, and the output files referenced by the project's
+/// `RENDER_FILE` that were written since `start`.
+fn report_render(
+    project: &path::Path,
+    start: time::SystemTime,
+    status: process::ExitStatus,
+) -> anyhow::Result<()> {
+    let project_contents = fs::read_to_string(project).map_err(|err| {
+        anyhow::anyhow!(
+            "Failed to read REAPER project '{}':\n{err:#?}",
+            project.display()
+        )
+    })?;
+    let project_dir = project.parent().unwrap_or(path::Path::new("."));
+    // An empty `RENDER_FILE` renders next to the project file.
+    let render_file = project_contents
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("RENDER_FILE "))
+        .map(|render_file| render_file.trim().trim_matches('"'))
+        .filter(|render_file| !render_file.is_empty())
         .map_or_else(
-            || {
-                run_global_default_headless(
-                    project.as_ref(),
-                    &display,
-                    window_title.as_deref(),
-                    keep_going,
-                    timeout.as_ref(),
-                    stdin,
-                    stdout,
-                    stderr,
-                )
-            },
-            |reaper| {
-                println!(
-                    "     {} REAPER executable ({})",
-                    "Running".green().bold(),
-                    reaper.display(),
-                );
+            || project_dir.to_path_buf(),
+            |render_file| project_dir.join(render_file),
+        );
 
-                timeout.map_or_else(
-                    || {
-                        run_reaper_headless(
-                            &reaper,
-                            project.as_ref(),
-                            &display,
-                            stdin,
-                            stdout,
-                            stderr,
-                        )
-                        .and_then(|(mut xvfb, mut reaper)| reaper.wait().and_then(|_| xvfb.wait()))
-                    },
-                    |timeout| {
-                        let ((mut xvfb, mut reaper), start) = run_reaper_headless(
-                            &reaper,
-                            project.as_ref(),
-                            &display,
-                            stdin,
-                            stdout,
-                            stderr,
+    let is_rendered = |path: &path::Path| {
+        fs::metadata(path)
+            .and_then(|meta| meta.modified())
+            .is_ok_and(|modified| modified >= start)
+    };
+    let outputs: Vec<path::PathBuf> = if render_file.is_dir() {
+        fs::read_dir(&render_file)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file() && is_rendered(path))
+            .collect()
+    } else {
+        is_rendered(&render_file)
+            .then_some(render_file)
+            .into_iter()
+            .collect()
+    };
+
+    status_println!(
+        "    {} {} ({status})",
+        "Rendered".green().bold(),
+        project.display()
+    );
+    if outputs.is_empty() {
+        status_println!(
+            "{}: no render output found for `RENDER_FILE`",
+            "warning".yellow().bold()
+        );
+    }
+    for output in outputs {
+        status_println!("      {} {}", "Output".green().bold(), output.display());
+    }
+    Ok(())
+}
+
+/// Where the REAPER executable was resolved from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ReaperSource {
+    /// Passed to `--exec`.
+    Exec,
+
+    /// Inside the portable installation passed to `--portable`.
+    Portable,
+
+    /// Found on `$PATH`.
+    Path,
+
+    /// The global default installation path.
+    GlobalDefault,
+}
+impl ReaperSource {
+    /// The name of the source, e.g. `global-default`.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::Exec => "exec",
+            Self::Portable => "portable",
+            Self::Path => "path",
+            Self::GlobalDefault => "global-default",
+        }
+    }
+}
+impl fmt::Display for ReaperSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Exec => write!(f, "passed to `--exec`"),
+            Self::Portable => write!(f, "in the portable installation"),
+            Self::Path => write!(f, "found on `$PATH`"),
+            Self::GlobalDefault => write!(f, "global default installation"),
+        }
+    }
+}
+
+/// Resolve the REAPER executable, preferring an explicit override, then a portable installation,
+/// then `$PATH`, and finally the global default installation path.
+pub(crate) fn resolve_reaper(
+    override_binary: Option<path::PathBuf>,
+    portable: Option<&path::Path>,
+) -> io::Result<(path::PathBuf, ReaperSource)> {
+    match override_binary
+        .map(|reaper| (reaper, ReaperSource::Exec))
+        .or_else(|| {
+            portable
+                .map(|portable| portable.join(util::os::PORTABLE_BINARY_PATH))
+                .filter(|reaper| reaper.is_file())
+                .map(|reaper| (reaper, ReaperSource::Portable))
+        })
+        .or_else(|| {
+            which::which(BINARY_NAME)
+                .ok()
+                .map(|reaper| (reaper, ReaperSource::Path))
+        }) {
+        Some(resolved) => Ok(resolved),
+        None => {
+            util::os::locate_global_default().map(|reaper| (reaper, ReaperSource::GlobalDefault))
+        }
+    }
+}
+
+/// Resolve the REAPER executable with [`resolve_reaper`], reporting it as run, or as used rather
+/// than run for `--dry-run`.
+fn locate_reaper(
+    override_binary: Option<path::PathBuf>,
+    portable: Option<&path::Path>,
+    dry_run: bool,
+) -> io::Result<path::PathBuf> {
+    let status = if dry_run {
+        "       Using"
+    } else {
+        "     Running"
+    };
+    let (reaper, source) = resolve_reaper(override_binary, portable)?;
+    if source == ReaperSource::Exec {
+        status_println!(
+            "{}: overridng REAPER executable path ({})",
+            "warning".yellow().bold(),
+            reaper.display()
+        );
+    }
+    status_println!(
+        "{} {}REAPER executable ({})",
+        status.green().bold(),
+        if source == ReaperSource::GlobalDefault {
+            "global default "
+        } else {
+            ""
+        },
+        reaper.display(),
+    );
+    Ok(reaper)
+}
+
+/// Abort unless the REAPER executable at `reaper` is at least `min_version`, as passed to
+/// `--min-reaper-version`.
+fn require_reaper_version(
+    reaper: &path::Path,
+    min_version: &cli::ReaperVersion,
+) -> anyhow::Result<()> {
+    match util::reaper_version(reaper) {
+        Some(version) if version >= *min_version => Ok(()),
+        Some(version) => anyhow::bail!(
+            "REAPER {version} ({}) is older than the minimum version {min_version} required by `--min-reaper-version`, update REAPER or pass a newer executable with `--exec`",
+            reaper.display()
+        ),
+        None => anyhow::bail!(
+            "unable to determine the version of the REAPER executable ({}) required by `--min-reaper-version`",
+            reaper.display()
+        ),
+    }
+}
+
+/// Terminate the running REAPER instance(s), asking them to exit and killing any that are still
+/// running after `grace` (`--kill-existing`).
+fn kill_existing(reaper: &path::Path, grace: time::Duration) -> io::Result<()> {
+    signal_existing(reaper, false)?;
+    let start = time::Instant::now();
+    while is_running(reaper) && start.elapsed() < grace {
+        thread::sleep(time::Duration::from_millis(100));
+    }
+    if is_running(reaper) {
+        signal_existing(reaper, true)?;
+        status_println!(
+            "{}: the running REAPER instance did not exit within {}, it was killed",
+            "warning".yellow().bold(),
+            humantime::format_duration(grace)
+        );
+    } else {
+        status_println!(
+            "     {} the running REAPER instance",
+            "Stopped".green().bold()
+        );
+    }
+    Ok(())
+}
+
+/// Signal every process with the same name as the REAPER executable to exit with `SIGTERM`, or
+/// kill it with `SIGKILL` when `force` is set.
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn signal_existing(reaper: &path::Path, force: bool) -> io::Result<()> {
+    const PKILL: &str = "pkill";
+
+    let Some(name) = reaper.file_name() else {
+        return Ok(());
+    };
+    process::Command::new(PKILL)
+        .arg(if force { "-KILL" } else { "-TERM" })
+        .arg("-x")
+        .arg(name)
+        .output()
+        .map(|_| ())
+}
+
+/// Ask every process with the same image name as the REAPER executable to close, or forcefully
+/// terminate it when `force` is set.
+#[cfg(target_os = "windows")]
+fn signal_existing(reaper: &path::Path, force: bool) -> io::Result<()> {
+    const TASKKILL: &str = "taskkill";
+
+    let Some(name) = reaper.file_name() else {
+        return Ok(());
+    };
+    process::Command::new(TASKKILL)
+        .args(force.then_some("/F"))
+        .arg("/IM")
+        .arg(name)
+        .output()
+        .map(|_| ())
+}
+
+/// Whether a process with the same name as the REAPER executable is running.
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn is_running(reaper: &path::Path) -> bool {
+    const PGREP: &str = "pgrep";
+
+    reaper.file_name().is_some_and(|name| {
+        process::Command::new(PGREP)
+            .arg("-x")
+            .arg(name)
+            .output()
+            .map(|output| output.status.success())
+            .unwrap_or(false)
+    })
+}
+
+/// Whether a process with the same image name as the REAPER executable is running.
+#[cfg(target_os = "windows")]
+fn is_running(reaper: &path::Path) -> bool {
+    const TASKLIST: &str = "tasklist";
+
+    reaper.file_name().is_some_and(|name| {
+        let name = name.to_string_lossy();
+        process::Command::new(TASKLIST)
+            .args(["/NH", "/FI"])
+            .arg(format!("IMAGENAME eq {name}"))
+            .output()
+            .map(|output| {
+                String::from_utf8_lossy(&output.stdout)
+                    .to_lowercase()
+                    .contains(&name.to_lowercase())
+            })
+            .unwrap_or(false)
+    })
+}
+
+/// REAPER's splash log (`-splashlog`), which records each extension as it is loaded.
+struct SplashLog {
+    /// The path passed to `-splashlog`.
+    path: path::PathBuf,
+
+    /// The directory containing the splash log when `--splash-log` is not passed, which is
+    /// removed once the log is reported.
+    temp_dir: Option<tempfile::TempDir>,
+
+    /// The plugins which must be mentioned by the splash log, if any.
+    required_plugins: Option<Vec<String>>,
+
+    /// The patterns no line of the splash log may match (`--fail-on-log-pattern`).
+    fail_patterns: Vec<regex::Regex>,
+}
+impl SplashLog {
+    /// Create a splash log at `path`, or a temporary one if only `required_plugins` are given.
+    /// When `required_plugins` is empty, the file names of all plugins in the `cargo-reaper`
+    /// configuration file are used.
+    fn new(
+        path: Option<path::PathBuf>,
+        required_plugins: Option<Vec<String>>,
+        fail_patterns: Vec<regex::Regex>,
+    ) -> anyhow::Result<Option<Self>> {
+        let required_plugins = match required_plugins {
+            Some(required_plugins) if required_plugins.is_empty() => {
+                let config = ReaperPluginConfig::load(&find_project_root()?)?;
+                Some(
+                    config
+                        .extension_plugins()
+                        .keys()
+                        .map(|plugin_name| TargetOs::host().add_plugin_ext(plugin_name.as_ref()))
+                        .collect(),
+                )
+            }
+            required_plugins => required_plugins,
+        };
+        match path {
+            Some(path) => {
+                if let Some(parent) = path
+                    .parent()
+                    .filter(|parent| !parent.as_os_str().is_empty())
+                {
+                    fs::create_dir_all(parent).map_err(|err| {
+                        anyhow::anyhow!(
+                            "failed to create splash log directory '{}':\n{err:#?}",
+                            parent.display()
                         )
-                        .map(|(xvfb, reaper)| ((xvfb, reaper), time::Instant::now()))?;
-
-                        let mut exit_code: i32 = if keep_going { 1 } else { Default::default() };
-
-                        loop {
-                            if let Some(window_title) = &window_title {
-                                const XDOTOOL: &str = "xdotool";
-                                const XDOTOOL_ARGS: &[&str; 2] = &["search", "--name"];
-                                if exit_code != 0
-                                    && process::Command::new(XDOTOOL)
-                                        .args(XDOTOOL_ARGS)
-                                        .arg(window_title)
-                                        .env("DISPLAY", &display)
-                                        .output()
-                                        .map(|output| output.status.success())
-                                        .unwrap_or(false)
-                                {
-                                    if keep_going {
-                                        exit_code = 0;
-                                    } else {
-                                        kill_and_exit(&mut reaper, &mut xvfb, 0)?;
-                                    }
-                                }
-                            }
-                            match reaper.try_wait()? {
-                                Some(_) if window_title.is_some() => {
-                                    kill_and_exit(&mut reaper, &mut xvfb, exit_code)?;
-                                }
-                                Some(status) => break Ok(status),
-                                None if start.elapsed() >= timeout => {
-                                    kill_and_exit(&mut reaper, &mut xvfb, exit_code)?;
-                                }
-                                None => thread::sleep(time::Duration::from_secs(1)),
-                            }
-                        }
+                    })?;
+                }
+                Ok(Some(Self {
+                    path,
+                    temp_dir: None,
+                    required_plugins,
+                    fail_patterns,
+                }))
+            }
+            None if required_plugins.is_some() => {
+                let temp_dir = tempfile::tempdir()?;
+                Ok(Some(Self {
+                    path: temp_dir.path().join("splash.log"),
+                    temp_dir: Some(temp_dir),
+                    required_plugins,
+                    fail_patterns,
+                }))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Whether the splash log was written since `since`, and reports REAPER's extension plugins as
+    /// loaded, i.e. it mentions every required plugin, or any extension plugin when none are
+    /// required.
+    fn reports_loaded(&self, since: time::SystemTime) -> bool {
+        let is_written = fs::metadata(&self.path)
+            .and_then(|metadata| metadata.modified())
+            .is_ok_and(|modified| modified >= since);
+        if !is_written {
+            return false;
+        }
+        let contents = fs::read_to_string(&self.path).unwrap_or_default();
+        match self.required_plugins.as_deref() {
+            Some(required_plugins) if !required_plugins.is_empty() => required_plugins
+                .iter()
+                .all(|plugin| contents.contains(plugin.as_str())),
+            _ => !Self::loaded_extensions(&contents).is_empty(),
+        }
+    }
+
+    /// The file names of the extensions the splash log mentions.
+    fn loaded_extensions(contents: &str) -> collections::BTreeSet<&str> {
+        let plugin_ext = TargetOs::host().add_plugin_ext("");
+        contents
+            .split(|c: char| c.is_whitespace() || c == '/' || c == '\\' || c == '"')
+            .filter(|file_name| {
+                file_name.len() > plugin_ext.len() && file_name.ends_with(&plugin_ext)
+            })
+            .collect()
+    }
+
+    /// Summarize the splash log and report plugins that it does not mention, or lines matching a
+    /// `--fail-on-log-pattern`, returning the exit code the session should exit with.
+    fn report(self, exit_code: i32, assertions: &mut Assertions) -> i32 {
+        let contents = fs::read_to_string(&self.path).unwrap_or_default();
+        if self.temp_dir.is_none() {
+            status_println!(
+                "  {} {} ({} extension(s) loaded)",
+                "Splash log".green().bold(),
+                self.path.display(),
+                Self::loaded_extensions(&contents).len()
+            );
+        }
+        let mut matched_line = None;
+        for pattern in &self.fail_patterns {
+            let line = contents.lines().find(|line| pattern.is_match(line));
+            assertions.record(
+                format!("splash log does not match `{pattern}`"),
+                line.map_or(Verdict::Passed, |line| {
+                    Verdict::Failed(format!("matched: {line}"))
+                }),
+            );
+            matched_line = matched_line.or(line);
+        }
+        let missing_plugins = self
+            .required_plugins
+            .iter()
+            .flatten()
+            .filter(|plugin| {
+                let loaded = contents.contains(plugin.as_str());
+                assertions.record(
+                    format!("plugin `{plugin}` loaded"),
+                    if loaded {
+                        Verdict::Passed
+                    } else {
+                        Verdict::Failed("not mentioned by the splash log".to_string())
                     },
-                )
-            },
-        )
-        .map_err(|err| anyhow::anyhow!("While attempting to run REAPER executable: {err:?}"))?;
+                );
+                !loaded
+            })
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        if let Some(required_plugins) = self.required_plugins.as_ref()
+            && missing_plugins.is_empty()
+        {
+            status_println!(
+                "    {} plugin(s) loaded: {}",
+                "Verified".green().bold(),
+                required_plugins.join(", ")
+            );
+        } else if !missing_plugins.is_empty() {
+            eprintln!(
+                "{}: the following plugin(s) were not loaded by REAPER: {}",
+                "error".magenta(),
+                missing_plugins.join(", ")
+            );
+        }
+        if let Some(line) = matched_line {
+            eprintln!(
+                "{}: REAPER's splash log matched `--fail-on-log-pattern`: {line}",
+                "error".magenta()
+            );
+            LOG_PATTERN_EXIT_CODE
+        } else if !missing_plugins.is_empty() {
+            1
+        } else {
+            exit_code
+        }
+    }
+}
 
-    Ok(())
+/// Check each file passed to `--expect-console` contains its text, reporting every unmet
+/// expectation together and returning the exit code the session should exit with.
+fn report_console_expectations(
+    expectations: &[cli::ConsoleExpectation],
+    exit_code: i32,
+    assertions: &mut Assertions,
+) -> i32 {
+    if expectations.is_empty() {
+        return exit_code;
+    }
+    let unmet = expectations
+        .iter()
+        .filter_map(|expectation| {
+            let reason = match fs::read_to_string(&expectation.file) {
+                Ok(contents) if contents.contains(&expectation.text) => None,
+                Ok(_) => Some("text not found"),
+                Err(_) => Some("file could not be read"),
+            };
+            assertions.record(
+                format!(
+                    "`{}` contains `{}`",
+                    expectation.file.display(),
+                    expectation.text
+                ),
+                reason.map_or(Verdict::Passed, |reason| {
+                    Verdict::Failed(reason.to_string())
+                }),
+            );
+            reason.map(|reason| (expectation, reason))
+        })
+        .collect::<Vec<_>>();
+    if unmet.is_empty() {
+        status_println!(
+            "    {} {} console expectation(s) met",
+            "Verified".green().bold(),
+            expectations.len()
+        );
+        return exit_code;
+    }
+    eprintln!(
+        "{}: {} of {} console expectation(s) were not met:",
+        "error".magenta(),
+        unmet.len(),
+        expectations.len()
+    );
+    for (expectation, reason) in unmet {
+        eprintln!(
+            "  {}: `{}` ({reason})",
+            expectation.file.display(),
+            expectation.text
+        );
+    }
+    1
 }
 
-/// Run the global default REAPER binary executable.
-fn run_global_default(
-    project: Option<&path::PathBuf>,
-    timeout: Option<&time::Duration>,
-    stdin: cli::Stdio,
-    stdout: cli::Stdio,
-    stderr: cli::Stdio,
-) -> io::Result<process::ExitStatus> {
-    util::os::locate_global_default().and_then(|reaper| {
-        println!(
-            "     {} global default REAPER executable ({})",
-            "Running".green().bold(),
-            reaper.display(),
+/// The throwaway resource directory created by `--fresh-config`.
+pub(crate) struct FreshConfig {
+    /// The resource directory, containing `reaper.ini` and `UserPlugins`.
+    temp_dir: tempfile::TempDir,
+
+    /// Whether the directory should be kept once REAPER exits (`--keep-config`).
+    keep: bool,
+}
+impl FreshConfig {
+    /// The contents of the minimal `reaper.ini`, REAPER fills in the defaults for everything else.
+    const REAPER_INI: &str = "[REAPER]\n";
+
+    fn new(keep: bool) -> anyhow::Result<Self> {
+        let temp_dir = tempfile::Builder::new().prefix("cargo-reaper-").tempdir()?;
+        fs::write(temp_dir.path().join("reaper.ini"), Self::REAPER_INI)
+            .and_then(|_| fs::create_dir(temp_dir.path().join("UserPlugins")))
+            .map_err(|err| {
+                anyhow::anyhow!(
+                    "failed to create fresh REAPER configuration in '{}':\n{err:#?}",
+                    temp_dir.path().display()
+                )
+            })?;
+        status_println!(
+            "     {} fresh REAPER configuration {}",
+            "Created".green().bold(),
+            temp_dir.path().display()
         );
+        Ok(Self { temp_dir, keep })
+    }
 
-        timeout.map_or_else(
-            || run_reaper(&reaper, project, stdin.into(), stdout.into(), stderr.into())?.wait(),
-            |timeout| {
-                let (mut reaper, start) =
-                    run_reaper(&reaper, project, stdin.into(), stdout.into(), stderr.into())
-                        .map(|reaper| (reaper, time::Instant::now()))?;
-
-                loop {
-                    match reaper.try_wait()? {
-                        Some(status) => break Ok(status),
-                        None if start.elapsed() >= *timeout => {
-                            reaper.kill()?;
-                            break reaper.wait();
-                        }
-                        None => thread::sleep(time::Duration::from_secs(1)),
+    /// Remove the resource directory, unless it should be kept.
+    fn finish(self) {
+        if self.keep {
+            status_println!(
+                "        {} fresh REAPER configuration {}",
+                "Kept".green().bold(),
+                self.temp_dir.keep().display()
+            );
+        } else if let Err(err) = self.temp_dir.close() {
+            status_println!(
+                "{}: failed to remove fresh REAPER configuration: {err}",
+                "warning".yellow().bold()
+            );
+        }
+    }
+}
+
+/// The temporary directory containing the copies of the projects opened with `--ephemeral`.
+struct EphemeralProjects {
+    /// The directory the projects were copied to.
+    temp_dir: tempfile::TempDir,
+
+    /// Whether the directory should be kept once REAPER exits (`--keep`).
+    keep: bool,
+}
+impl EphemeralProjects {
+    /// Copy each of `projects` into a temporary directory, along with its `.rpp-bak` backup if
+    /// there is one, replacing each path with the path to its copy.
+    fn new(projects: &mut [path::PathBuf], keep: bool) -> anyhow::Result<Self> {
+        let temp_dir = tempfile::Builder::new()
+            .prefix("cargo-reaper-projects-")
+            .tempdir()?;
+        for project in projects.iter_mut() {
+            let Some(file_name) = project.file_name() else {
+                anyhow::bail!("project '{}' is not a file", project.display());
+            };
+            let copy = temp_dir.path().join(file_name);
+            if copy.exists() {
+                anyhow::bail!(
+                    "`--ephemeral` can't open more than one project named `{}`",
+                    file_name.to_string_lossy()
+                );
+            }
+            let mut backup = project.clone().into_os_string();
+            backup.push("-bak");
+            let backup = path::PathBuf::from(backup);
+            fs::copy(&*project, &copy)
+                .and_then(|_| {
+                    if backup.is_file() {
+                        let mut backup_copy = copy.clone().into_os_string();
+                        backup_copy.push("-bak");
+                        fs::copy(&backup, backup_copy)?;
                     }
+                    Ok(())
+                })
+                .map_err(|err| {
+                    anyhow::anyhow!(
+                        "failed to copy project '{}' to '{}':\n{err:#?}",
+                        project.display(),
+                        temp_dir.path().display()
+                    )
+                })?;
+            *project = copy;
+        }
+        status_println!(
+            "      {} project(s) to {}",
+            "Copied".green().bold(),
+            temp_dir.path().display()
+        );
+        Ok(Self { temp_dir, keep })
+    }
+
+    /// Remove the directory, unless it should be kept.
+    fn finish(self) {
+        if self.keep {
+            status_println!(
+                "        {} project copies {}",
+                "Kept".green().bold(),
+                self.temp_dir.keep().display()
+            );
+        } else if let Err(err) = self.temp_dir.close() {
+            status_println!(
+                "{}: failed to remove project copies: {err}",
+                "warning".yellow().bold()
+            );
+        }
+    }
+}
+
+/// The child processes spawned for a REAPER session.
+struct Session {
+    /// The REAPER process.
+    reaper: process::Child,
+
+    /// The virtual display server REAPER is attached to, if running headless.
+    #[cfg(target_os = "linux")]
+    display_server: Option<process::Child>,
+
+    /// The splash log to verify loaded plugins against, if any.
+    splash_log: Option<SplashLog>,
+
+    /// The throwaway resource directory REAPER is run with, if any.
+    fresh_config: Option<FreshConfig>,
+
+    /// REAPER's captured output, scanned for `--fail-on-log-pattern`, if any.
+    log_scanner: Option<LogScanner>,
+
+    /// The files that must contain some text once REAPER exits (`--expect-console`).
+    console_expectations: Vec<cli::ConsoleExpectation>,
+
+    /// The results of the session's assertions, for `--report-junit`.
+    assertions: Assertions,
+
+    /// Screenshots of the virtual display to capture, if any.
+    #[cfg(target_os = "linux")]
+    screenshot: Option<Screenshot>,
+
+    /// The `ffmpeg` process recording the virtual display, if any.
+    #[cfg(target_os = "linux")]
+    recorder: Option<Recorder>,
+
+    /// How long to wait for a child process to exit after `SIGTERM` (or `WM_CLOSE` on Windows),
+    /// before killing it.
+    kill_grace: time::Duration,
+
+    /// Whether REAPER was spawned in its own process group, which is killed along with it.
+    #[cfg(unix)]
+    process_group: bool,
+
+    /// The Job Object REAPER is assigned to, which is killed along with it, and once dropped.
+    #[cfg(windows)]
+    job: Option<job::Job>,
+}
+impl Session {
+    /// Kill REAPER, followed by the recorder and virtual display server (if any).
+    fn kill(&mut self) -> io::Result<()> {
+        cfg_select! {
+            target_os = "linux" => {
+                terminate(&mut self.reaper, "REAPER", self.kill_grace, self.process_group)?;
+                if let Some(recorder) = self.recorder.take() {
+                    recorder.stop()?;
                 }
-            },
+                if let Some(display_server) = self.display_server.as_mut() {
+                    terminate(display_server, "virtual display server", self.kill_grace, false)?;
+                }
+            }
+            unix => {
+                terminate(&mut self.reaper, "REAPER", self.kill_grace, self.process_group)?;
+            }
+            _ => {
+                close(&mut self.reaper, "REAPER", self.kill_grace, self.job.as_ref())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Report the outcome of the session's assertions, returning the exit code the session
+    /// should exit with.
+    fn report(&mut self, exit_code: i32) -> i32 {
+        let exit_code = self.splash_log.take().map_or(exit_code, |splash_log| {
+            splash_log.report(exit_code, &mut self.assertions)
+        });
+        let exit_code = report_console_expectations(
+            &self.console_expectations,
+            exit_code,
+            &mut self.assertions,
+        );
+        if let Some(fresh_config) = self.fresh_config.take() {
+            fresh_config.finish();
+        }
+        exit_code
+    }
+
+    /// Capture a screenshot of the virtual display if passed `--screenshot`. Unless passed
+    /// `--screenshot-always`, only failed sessions are captured.
+    fn screenshot(
+        &mut self,
+        #[cfg_attr(not(target_os = "linux"), expect(unused_variables))] failed: bool,
+    ) {
+        #[cfg(target_os = "linux")]
+        if let Some(screenshot) = self.screenshot.as_mut()
+            && (failed || screenshot.always)
+        {
+            screenshot.capture();
+        }
+    }
+}
+
+/// Open a `--log-file` for appending, creating its parent directories, and write a header with
+/// the command line of the process whose output it captures and the current time.
+fn open_log_file(path: &path::Path, command: &process::Command) -> io::Result<fs::File> {
+    let open = || {
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(parent)?;
+        }
+        let mut log_file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        let command_line = std::iter::once(command.get_program())
+            .chain(command.get_args())
+            .map(|arg| arg.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join(" ");
+        io::Write::write_all(
+            &mut log_file,
+            format!(
+                "==> {} {command_line} <==\n",
+                humantime::format_rfc3339_millis(time::SystemTime::now())
+            )
+            .as_bytes(),
+        )?;
+        Ok(log_file)
+    };
+    open().map_err(|err: io::Error| {
+        io::Error::new(
+            err.kind(),
+            format!("failed to open log file '{}': {err}", path.display()),
         )
     })
 }
 
-#[allow(clippy::too_many_arguments)]
-#[cfg(target_os = "linux")]
-fn run_global_default_headless(
-    project: Option<&path::PathBuf>,
-    display: &str,
-    window_title: Option<&str>,
-    keep_going: bool,
-    timeout: Option<&time::Duration>,
-    stdin: cli::Stdio,
-    stdout: cli::Stdio,
-    stderr: cli::Stdio,
-) -> io::Result<process::ExitStatus> {
-    util::os::locate_global_default().and_then(|reaper| {
-        println!(
-            "     {} global default REAPER executable ({})",
-            "Running".green().bold(),
-            reaper.display(),
+/// Spawn REAPER, attaching it to a virtual display server when running headless.
+fn spawn(
+    reaper: &path::PathBuf,
+    args: &mut cli::RunArgs,
+    script: Option<&path::Path>,
+    splash_log: Option<SplashLog>,
+    fresh_config: Option<FreshConfig>,
+) -> io::Result<Session> {
+    let mut command = reaper_command(
+        reaper,
+        args,
+        script,
+        splash_log
+            .as_ref()
+            .map(|splash_log| splash_log.path.as_path()),
+    )?;
+    if args.debugger.is_some() {
+        status_println!(
+            "   {} REAPER with `{}`",
+            "Debugging".green().bold(),
+            command.get_program().display()
         );
+    }
+    command.stdin(args.stdin.input()?);
+    // Output scanned for `--fail-on-log-pattern`, or reported by `--report-junit`, is captured
+    // unless it is discarded. Both streams are written to `--log-file` instead, when passed.
+    let is_captured = !args.fail_on_log_pattern.is_empty() || args.report_junit.is_some();
+    let log_file = args
+        .log_file
+        .as_deref()
+        .map(|path| open_log_file(path, &command))
+        .transpose()?;
+    let tee = args
+        .tee
+        .as_deref()
+        .map(|path| open_log_file(path, &command))
+        .transpose()?;
+    let output = |stdio: &cli::Stdio, inherit: fn() -> process::Stdio| {
+        io::Result::Ok(match (log_file.as_ref(), stdio) {
+            _ if tee.is_some() => process::Stdio::piped(),
+            (Some(_), _) | (None, cli::Stdio::Inherit) if is_captured => process::Stdio::piped(),
+            (Some(log_file), _) => log_file.try_clone()?.into(),
+            (None, cli::Stdio::Inherit) => inherit(),
+            (None, stdio) => stdio.output()?,
+        })
+    };
+    command
+        .stdout(output(&args.stdout, util::inherit_stdout)?)
+        .stderr(output(&args.stderr, process::Stdio::inherit)?);
+    // The debugger is interactive, so it needs the terminal, and stays in the foreground process
+    // group to be able to read from it.
+    if args.debugger.is_some() {
+        command
+            .stdin(process::Stdio::inherit())
+            .stdout(process::Stdio::inherit())
+            .stderr(process::Stdio::inherit());
+    }
 
-        timeout.map_or_else(
-            || {
-                run_reaper_headless(&reaper, project, display, stdin, stdout, stderr)
-                    .and_then(|(mut xvfb, mut reaper)| reaper.wait().and_then(|_| xvfb.wait()))
-            },
-            |timeout| {
-                let ((mut xvfb, mut reaper), start) =
-                    run_reaper_headless(&reaper, project, display, stdin, stdout, stderr)
-                        .map(|(xvfb, reaper)| ((xvfb, reaper), time::Instant::now()))?;
-
-                let mut exit_code: i32 = if keep_going { 1 } else { Default::default() };
-
-                loop {
-                    if let Some(window_title) = &window_title {
-                        const XDOTOOL: &str = "xdotool";
-                        const XDOTOOL_ARGS: &[&str; 2] = &["search", "--name"];
-                        if exit_code != 0
-                            && process::Command::new(XDOTOOL)
-                                .args(XDOTOOL_ARGS)
-                                .arg(window_title)
-                                .env("DISPLAY", display)
-                                .output()
-                                .map(|output| output.status.success())
-                                .unwrap_or(false)
-                        {
-                            if keep_going {
-                                exit_code = 0;
-                            } else {
-                                kill_and_exit(&mut reaper, &mut xvfb, 0)?;
+    #[cfg(unix)]
+    if args.detach {
+        // SAFETY: `setsid` is async-signal-safe, and nothing else runs between fork and exec.
+        unsafe {
+            std::os::unix::process::CommandExt::pre_exec(&mut command, || {
+                nix::unistd::setsid().map(drop).map_err(io::Error::from)
+            });
+        }
+    } else if !args.no_process_group && args.debugger.is_none() {
+        std::os::unix::process::CommandExt::process_group(&mut command, 0);
+    }
+    #[cfg(windows)]
+    if args.detach {
+        use windows::Win32::System::Threading::{CREATE_NEW_PROCESS_GROUP, DETACHED_PROCESS};
+        std::os::windows::process::CommandExt::creation_flags(
+            &mut command,
+            (DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP).0,
+        );
+    } else {
+        // REAPER is resumed once it's assigned to its Job Object, so that any process it spawns
+        // belongs to the job as well.
+        std::os::windows::process::CommandExt::creation_flags(
+            &mut command,
+            windows::Win32::System::Threading::CREATE_SUSPENDED.0,
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    let display_server = args
+        .headless
+        .then(|| {
+            let mut backend = headless::backend(args.backend);
+            let program = args
+                .xvfb_path
+                .clone()
+                .unwrap_or_else(|| backend.program().into());
+            let display_server = headless::spawn(backend.as_mut(), &program, args);
+            command.env("DISPLAY", &args.display);
+            display_server
+        })
+        .transpose()?;
+
+    #[cfg(target_os = "linux")]
+    let recorder = args
+        .record
+        .as_ref()
+        .map(|path| Recorder::spawn(path, &args.display, &args.screen))
+        .transpose()?;
+
+    #[cfg(target_os = "linux")]
+    if let Some(display_server) = display_server.as_ref() {
+        event::emit(Event::DisplayServerSpawned {
+            pid: display_server.id(),
+            display: &args.display,
+        });
+    }
+
+    let mut child = command.spawn().map_err(|err| {
+        io::Error::new(
+            err.kind(),
+            format!(
+                "Command `{}` failed: {}",
+                path::Path::new(command.get_program()).display(),
+                err
+            ),
+        )
+    })?;
+    event::emit(Event::ReaperSpawned {
+        pid: child.id(),
+        path: reaper,
+    });
+    #[cfg(windows)]
+    let job = if args.detach {
+        None
+    } else {
+        let job = job::Job::assign(&child)
+            .inspect_err(|err| {
+                status_println!(
+                    "{}: failed to assign REAPER to a Job Object, it may outlive `cargo-reaper`: {err}",
+                    "warning".yellow().bold()
+                );
+            })
+            .ok();
+        if let Err(err) = job::resume(&child) {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(io::Error::new(
+                err.kind(),
+                format!("Failed to resume REAPER: {err}"),
+            ));
+        }
+        job
+    };
+    let log_scanner = (child.stdout.is_some() || child.stderr.is_some())
+        .then(|| LogScanner::spawn(&mut child, args, log_file.as_ref(), tee));
+
+    Ok(Session {
+        reaper: child,
+        #[cfg(target_os = "linux")]
+        display_server,
+        splash_log,
+        fresh_config,
+        log_scanner,
+        console_expectations: args.expect_console.clone(),
+        assertions: Assertions::default(),
+        #[cfg(target_os = "linux")]
+        screenshot: args.screenshot.clone().map(|path| Screenshot {
+            path,
+            display: args.display.clone(),
+            always: args.screenshot_always,
+            captures: 0,
+        }),
+        #[cfg(target_os = "linux")]
+        recorder,
+        kill_grace: args.kill_grace,
+        #[cfg(unix)]
+        process_group: !args.no_process_group && args.debugger.is_none() && !args.detach,
+        #[cfg(windows)]
+        job,
+    })
+}
+
+/// The command that runs the REAPER executable with the arguments derived from `args`, wrapped
+/// by `--debugger` or `--valgrind` when passed.
+fn reaper_command(
+    reaper: &path::Path,
+    args: &cli::RunArgs,
+    script: Option<&path::Path>,
+    splash_log: Option<&path::Path>,
+) -> io::Result<process::Command> {
+    let mut command = match args.debugger {
+        Some(debugger) => debugger_command(debugger, reaper)?,
+        #[cfg(target_os = "linux")]
+        None if args.valgrind => valgrind::command(reaper, &args.valgrind_args)?,
+        None => process::Command::new(reaper),
+    };
+    command
+        .args(args.new_instance.then_some("-newinst"))
+        .args(args.no_new_instance.then_some("-nonewinst"))
+        .args(args.no_render_dialog.then_some("-norenderdialog"))
+        .args(args.no_splash.then_some("-nosplash"))
+        .args(args.ignore_errors.then_some("-ignoreerrors"))
+        .args(
+            args.template
+                .iter()
+                .flat_map(|template| ["-template".as_ref(), template.as_os_str()]),
+        )
+        .args(
+            args.portable
+                .iter()
+                .flat_map(|portable| ["-cfgfile".into(), portable.join("reaper.ini")]),
+        )
+        .args(
+            splash_log
+                .iter()
+                .flat_map(|splash_log| ["-splashlog".as_ref(), splash_log.as_os_str()]),
+        )
+        .args(args.render.then_some("-renderproject"))
+        .args(args.project.iter())
+        .args(script)
+        .args(&args.reaper_args)
+        .envs(args.env.iter().map(|env| (&env.key, &env.value)));
+    Ok(command)
+}
+
+/// The command that runs the REAPER executable under `debugger` (`--debugger`), stopping at the
+/// entry point of each extension plugin once it is loaded. REAPER's arguments follow.
+fn debugger_command(debugger: cli::Debugger, reaper: &path::Path) -> io::Result<process::Command> {
+    const GDB: &str = "gdb";
+    const LLDB: &str = "lldb";
+    /// The function REAPER calls when loading an extension plugin.
+    const ENTRY_SYMBOL: &str = "ReaperPluginEntry";
+
+    let debugger = match debugger {
+        cli::Debugger::Gdb => GDB,
+        cli::Debugger::Lldb => LLDB,
+        cli::Debugger::Auto => {
+            let preferred = if cfg!(target_os = "macos") {
+                [LLDB, GDB]
+            } else {
+                [GDB, LLDB]
+            };
+            preferred
+                .into_iter()
+                .find(|debugger| which::which(debugger).is_ok())
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::NotFound,
+                        "neither `gdb` nor `lldb` was found on `$PATH`, which is required by `--debugger`",
+                    )
+                })?
+        }
+    };
+    let mut command = process::Command::new(debugger);
+    if debugger == GDB {
+        command
+            .args(["-ex", "set follow-fork-mode parent"])
+            .args(["-ex", "set breakpoint pending on"])
+            .arg("-ex")
+            .arg(format!("break {ENTRY_SYMBOL}"))
+            .args(["-ex", "run", "--args"])
+            .arg(reaper);
+    } else {
+        command
+            .args(["-o", "settings set target.process.follow-fork-mode parent"])
+            .arg("-o")
+            .arg(format!("breakpoint set --name {ENTRY_SYMBOL}"))
+            .args(["-o", "run", "--"])
+            .arg(reaper);
+    }
+    Ok(command)
+}
+
+/// Send `signal` to a child process, or its whole process group. Processes that have already
+/// exited are ignored.
+#[cfg(unix)]
+fn signal(
+    child: &process::Child,
+    signal: nix::sys::signal::Signal,
+    process_group: bool,
+) -> io::Result<()> {
+    use nix::{errno::Errno, sys::signal as sys_signal, unistd::Pid};
+
+    let pid = Pid::from_raw(child.id() as i32);
+    match if process_group {
+        sys_signal::killpg(pid, signal)
+    } else {
+        sys_signal::kill(pid, signal)
+    } {
+        Ok(()) | Err(Errno::ESRCH) => Ok(()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Send `SIGTERM` to a child process that is still running, letting it shut down gracefully
+/// (e.g. REAPER flushing its configuration), and `SIGKILL` it if it hasn't exited within `grace`.
+/// When `process_group` is set, the whole process group is signalled, and any processes left
+/// in it once the child has exited are killed.
+#[cfg(unix)]
+fn terminate(
+    child: &mut process::Child,
+    name: &str,
+    grace: time::Duration,
+    process_group: bool,
+) -> io::Result<()> {
+    use nix::sys::signal::Signal;
+
+    if child.try_wait()?.is_none() {
+        signal(child, Signal::SIGTERM, process_group)?;
+        let status = wait_for_exit(child, grace)?;
+        event::emit(Event::Killed {
+            process: name,
+            graceful: status.is_some(),
+        });
+        match status {
+            Some(status) => status_println!(
+                "     {} {name} within the grace period ({status})",
+                "Stopped".green().bold()
+            ),
+            None => {
+                signal(child, Signal::SIGKILL, process_group).and_then(|_| child.wait())?;
+                status_println!(
+                    "{}: {name} did not exit within {} of `SIGTERM`, it was killed",
+                    "warning".yellow().bold(),
+                    humantime::format_duration(grace)
+                );
+            }
+        }
+    }
+    if process_group {
+        signal(child, Signal::SIGKILL, true)?;
+    }
+    Ok(())
+}
+
+/// Ask a child process that is still running to exit by closing its windows (`WM_CLOSE`),
+/// letting it shut down gracefully (e.g. REAPER saving its state, so it doesn't offer to recover
+/// the project on its next launch), and kill it if it hasn't exited within `grace`. The processes
+/// left in its `job` (if any) are killed along with it.
+#[cfg(windows)]
+fn close(
+    child: &mut process::Child,
+    name: &str,
+    grace: time::Duration,
+    job: Option<&job::Job>,
+) -> io::Result<()> {
+    if child.try_wait()?.is_none() {
+        let status = if close_windows(child.id()) {
+            wait_for_exit(child, grace)?
+        } else {
+            None
+        };
+        event::emit(Event::Killed {
+            process: name,
+            graceful: status.is_some(),
+        });
+        match status {
+            Some(status) => status_println!(
+                "     {} {name} within the grace period ({status})",
+                "Stopped".green().bold()
+            ),
+            None => {
+                match job {
+                    Some(job) => job.terminate(),
+                    None => child.kill(),
+                }
+                .and_then(|_| child.wait())?;
+                status_println!(
+                    "{}: {name} did not exit within {} of `WM_CLOSE`, it was killed",
+                    "warning".yellow().bold(),
+                    humantime::format_duration(grace)
+                );
+            }
+        }
+    }
+    if let Some(job) = job {
+        job.terminate()?;
+    }
+    Ok(())
+}
+
+/// Wait up to `grace` for a child process to exit, returning its exit status if it did.
+fn wait_for_exit(
+    child: &mut process::Child,
+    grace: time::Duration,
+) -> io::Result<Option<process::ExitStatus>> {
+    let start = time::Instant::now();
+    loop {
+        match child.try_wait()? {
+            None if start.elapsed() < grace => thread::sleep(time::Duration::from_millis(100)),
+            status => return Ok(status),
+        }
+    }
+}
+
+/// Screenshots of the virtual display (`--screenshot`).
+#[cfg(target_os = "linux")]
+struct Screenshot {
+    /// The path of the first capture, subsequent captures are given numbered suffixes.
+    path: path::PathBuf,
+
+    /// The virtual display to capture.
+    display: String,
+
+    /// Whether every session should be captured, instead of only failed ones.
+    always: bool,
+
+    /// The number of captures taken so far.
+    captures: usize,
+}
+#[cfg(target_os = "linux")]
+impl Screenshot {
+    const IMPORT: &str = "import";
+
+    /// Capture the virtual display with ImageMagick's `import`, printing where the image was saved.
+    fn capture(&mut self) {
+        const IMPORT_ARGS: &[&str; 2] = &["-window", "root"];
+
+        self.captures += 1;
+        let path = match self.captures {
+            1 => self.path.clone(),
+            n => {
+                let mut file_name = self.path.file_stem().unwrap_or_default().to_os_string();
+                file_name.push(format!("-{n}"));
+                if let Some(ext) = self.path.extension() {
+                    file_name.push(".");
+                    file_name.push(ext);
+                }
+                self.path.with_file_name(file_name)
+            }
+        };
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            let _ = fs::create_dir_all(parent);
+        }
+        match process::Command::new(Self::IMPORT)
+            .args(IMPORT_ARGS)
+            .arg("-display")
+            .arg(&self.display)
+            .arg(&path)
+            .output()
+        {
+            Ok(output) if output.status.success() => status_println!(
+                "    {} screenshot {}",
+                "Captured".green().bold(),
+                path.display()
+            ),
+            Ok(output) => status_println!(
+                "{}: failed to capture screenshot '{}': {}",
+                "warning".yellow().bold(),
+                path.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            Err(err) => status_println!(
+                "{}: failed to capture screenshot '{}', is ImageMagick installed? ({err})",
+                "warning".yellow().bold(),
+                path.display()
+            ),
+        }
+    }
+}
+
+/// The program used to query the windows of the virtual display.
+#[cfg(target_os = "linux")]
+pub(crate) const XDOTOOL: &str = "xdotool";
+
+/// An `ffmpeg` process recording the virtual display (`--record`).
+#[cfg(target_os = "linux")]
+struct Recorder {
+    /// The `ffmpeg` process.
+    ffmpeg: process::Child,
+
+    /// The video file being recorded to.
+    path: path::PathBuf,
+}
+#[cfg(target_os = "linux")]
+impl Recorder {
+    const FFMPEG: &str = "ffmpeg";
+
+    /// Start recording `display` to `path`.
+    fn spawn(path: &path::Path, display: &str, screen: &cli::Screen) -> io::Result<Self> {
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(parent)?;
+        }
+
+        process::Command::new(Self::FFMPEG)
+            .args(["-y", "-loglevel", "error", "-f", "x11grab", "-video_size"])
+            .arg(format!("{}x{}", screen.width, screen.height))
+            .arg("-i")
+            .arg(display)
+            .args(["-pix_fmt", "yuv420p"])
+            .arg(path)
+            .stdin(process::Stdio::piped())
+            .stdout(process::Stdio::null())
+            .spawn()
+            .map(|ffmpeg| {
+                status_println!(
+                    "   {} virtual display {display} -> {}",
+                    "Recording".green().bold(),
+                    path.display()
+                );
+                Self {
+                    ffmpeg,
+                    path: path.to_path_buf(),
+                }
+            })
+            .map_err(|err| {
+                io::Error::new(
+                    err.kind(),
+                    format!("Command `{}` failed: {}", Self::FFMPEG, err),
+                )
+            })
+    }
+
+    /// Stop recording, letting `ffmpeg` finalize the video file.
+    fn stop(mut self) -> io::Result<()> {
+        // `ffmpeg` quits gracefully once `q` is written to its stdin.
+        if let Some(mut stdin) = self.ffmpeg.stdin.take() {
+            let _ = io::Write::write_all(&mut stdin, b"q");
+        }
+        let status = self.ffmpeg.wait()?;
+        if status.success() {
+            status_println!(
+                "    {} virtual display {}",
+                "Recorded".green().bold(),
+                self.path.display()
+            );
+        } else {
+            status_println!(
+                "{}: `{}` exited unsuccessfully while recording '{}' ({status})",
+                "warning".yellow().bold(),
+                Self::FFMPEG,
+                self.path.display()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Scans REAPER's captured stdout and stderr for lines matching `--fail-on-log-pattern`, keeping
+/// the output for `--report-junit`. Piped streams are always read, so REAPER never blocks on a full
+/// pipe.
+struct LogScanner {
+    /// The threads reading each captured stream.
+    readers: Vec<thread::JoinHandle<()>>,
+
+    /// Receives each line matching a pattern, as soon as it is read.
+    matches: sync::mpsc::Receiver<String>,
+
+    /// The output read from both streams, interleaved by line.
+    output: sync::Arc<sync::Mutex<String>>,
+}
+impl LogScanner {
+    /// How long to wait for the remaining output to be read once the session ends.
+    const DRAIN_TIMEOUT: time::Duration = time::Duration::from_secs(1);
+
+    /// Read REAPER's captured streams on background threads. Streams configured as `inherit`
+    /// are forwarded to `cargo-reaper`'s own stdout and stderr as they are read, or both streams
+    /// to `log_file` when passed `--log-file`. When passed `--tee`, both streams are forwarded,
+    /// and also written to `tee` with a `[stdout]` or `[stderr]` prefix.
+    fn spawn(
+        reaper: &mut process::Child,
+        args: &cli::RunArgs,
+        log_file: Option<&fs::File>,
+        tee: Option<fs::File>,
+    ) -> Self {
+        let (sender, matches) = sync::mpsc::channel();
+        let output = sync::Arc::new(sync::Mutex::new(String::new()));
+        let tee = tee.map(|tee| sync::Arc::new(sync::Mutex::new(tee)));
+        let forward = |stdio: &cli::Stdio, stream: Box<dyn io::Write + Send>| match log_file {
+            Some(log_file) => log_file
+                .try_clone()
+                .ok()
+                .map(|log_file| Box::new(log_file) as _),
+            None => (tee.is_some() || matches!(stdio, cli::Stdio::Inherit)).then_some(stream),
+        };
+        let streams: [(Option<Box<dyn io::Read + Send>>, _, _); 2] = [
+            (
+                reaper.stdout.take().map(|stdout| Box::new(stdout) as _),
+                forward(
+                    &args.stdout,
+                    if util::JSON_MESSAGE_FORMAT.load(sync::atomic::Ordering::Relaxed) {
+                        Box::new(io::stderr())
+                    } else {
+                        Box::new(io::stdout())
+                    },
+                ),
+                "stdout",
+            ),
+            (
+                reaper.stderr.take().map(|stderr| Box::new(stderr) as _),
+                forward(&args.stderr, Box::new(io::stderr())),
+                "stderr",
+            ),
+        ];
+        let readers = streams
+            .into_iter()
+            .filter_map(|(stream, forward, name)| Some((stream?, forward, name)))
+            .map(|(stream, mut forward, name)| {
+                let patterns = args.fail_on_log_pattern.clone();
+                let sender = sender.clone();
+                let output = output.clone();
+                let tee = tee.clone();
+                thread::spawn(move || {
+                    let mut reader = io::BufReader::new(stream);
+                    let mut line = Vec::new();
+                    while io::BufRead::read_until(&mut reader, b'\n', &mut line)
+                        .is_ok_and(|read| read > 0)
+                    {
+                        if let Some(forward) = forward.as_mut() {
+                            let _ = forward.write_all(&line).and_then(|_| forward.flush());
+                        }
+                        if let Some(mut tee) = tee.as_ref().and_then(|tee| tee.lock().ok()) {
+                            let mut prefixed = format!("[{name}] ").into_bytes();
+                            prefixed.extend_from_slice(&line);
+                            if !prefixed.ends_with(b"\n") {
+                                prefixed.push(b'\n');
                             }
+                            let _ = io::Write::write_all(&mut *tee, &prefixed);
                         }
-                    }
-                    match reaper.try_wait()? {
-                        Some(_) if window_title.is_some() => {
-                            kill_and_exit(&mut reaper, &mut xvfb, exit_code)?;
+                        let text = String::from_utf8_lossy(&line);
+                        if let Ok(mut output) = output.lock() {
+                            output.push_str(&text);
                         }
-                        Some(status) => break Ok(status),
-                        None if start.elapsed() >= *timeout => {
-                            kill_and_exit(&mut reaper, &mut xvfb, exit_code)?;
+                        let text = text.trim_end();
+                        if patterns.iter().any(|pattern| pattern.is_match(text)) {
+                            let _ = sender.send(text.to_string());
                         }
-                        None => thread::sleep(time::Duration::from_secs(1)),
+                        line.clear();
                     }
+                })
+            })
+            .collect();
+        Self {
+            readers,
+            matches,
+            output,
+        }
+    }
+
+    /// The first line read that matched a pattern, if any.
+    fn matched_line(&self) -> Option<String> {
+        self.matches.try_recv().ok()
+    }
+
+    /// Wait for the remaining output to be read, returning the first line that matched a
+    /// pattern, if any.
+    fn finish(&self) -> Option<String> {
+        let start = time::Instant::now();
+        while start.elapsed() < Self::DRAIN_TIMEOUT
+            && !self.readers.iter().all(thread::JoinHandle::is_finished)
+        {
+            thread::sleep(time::Duration::from_millis(10));
+        }
+        self.matched_line()
+    }
+
+    /// The output read so far.
+    fn output(&self) -> String {
+        self.output
+            .lock()
+            .map(|output| output.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// A window to locate.
+enum LocateWindow<'a> {
+    /// A title containing the string (`--locate-window`).
+    Title(&'a str),
+
+    /// A title matching the regular expression (`--locate-window-regex`).
+    Regex(&'a regex::Regex),
+
+    /// A window class (`--locate-class`).
+    #[cfg(target_os = "linux")]
+    Class(&'a str),
+}
+impl LocateWindow<'_> {
+    /// Whether a matching window is currently open.
+    fn is_open(&self, #[cfg(target_os = "linux")] display: &str) -> bool {
+        let window_titles = || {
+            cfg_select! {
+                target_os = "linux" => window_titles(display),
+                _ => window_titles(),
+            }
+        };
+        match self {
+            Self::Title(window_title) => window_titles()
+                .iter()
+                .any(|title| title.contains(window_title)),
+            Self::Regex(window_regex) => window_titles()
+                .iter()
+                .any(|title| window_regex.is_match(title)),
+            #[cfg(target_os = "linux")]
+            Self::Class(window_class) => find_window_class(display, window_class),
+        }
+    }
+}
+impl fmt::Display for LocateWindow<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Title(window_title) => write!(f, "window titled `{window_title}`"),
+            Self::Regex(window_regex) => write!(f, "window titled `/{window_regex}/`"),
+            #[cfg(target_os = "linux")]
+            Self::Class(window_class) => write!(f, "window of class `{window_class}`"),
+        }
+    }
+}
+
+/// How a REAPER session ended.
+pub(crate) struct RunOutcome {
+    /// The status code `cargo-reaper` should exit with.
+    pub(crate) status: i32,
+    /// Why the session ended.
+    pub(crate) reason: RunReason,
+}
+
+/// Why a REAPER session ended.
+pub(crate) enum RunReason {
+    /// REAPER exited on its own, without windows being located.
+    Exited(process::ExitStatus),
+    /// A window was located.
+    WindowLocated,
+    /// The file passed to `--wait-for-file` was created.
+    MarkerFileCreated,
+    /// REAPER exited before a window was located, or the marker file was created.
+    NotLocated,
+    /// The timeout was reached.
+    TimedOut,
+    /// A window was not located, or the marker file was not created, within the startup timeout.
+    StartupTimedOut,
+    /// A window passed to `--fail-on-window` or `--fail-on-window-regex` was located.
+    ErrorWindowLocated(String),
+    /// A line of REAPER's output matched `--fail-on-log-pattern`.
+    LogPatternMatched(String),
+    /// `cargo-reaper` received `SIGINT` or `SIGTERM`.
+    Interrupted,
+    /// REAPER was left running, detached from `cargo-reaper` (`--detach`).
+    Detached,
+}
+impl fmt::Display for RunReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Exited(status) => write!(f, "REAPER exited ({status})"),
+            Self::WindowLocated => write!(f, "a window was located"),
+            Self::MarkerFileCreated => write!(f, "the marker file was created"),
+            Self::NotLocated => write!(f, "REAPER exited"),
+            Self::TimedOut => write!(f, "the timeout was reached"),
+            Self::StartupTimedOut => write!(f, "the startup timeout was reached"),
+            Self::ErrorWindowLocated(window) => write!(f, "error {window} was located"),
+            Self::LogPatternMatched(_) => write!(f, "REAPER's output matched a pattern"),
+            Self::Interrupted => write!(f, "`cargo-reaper` was interrupted"),
+            Self::Detached => write!(f, "REAPER was detached"),
+        }
+    }
+}
+impl RunReason {
+    /// The name of the reason, as reported by the `finished` event.
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Exited(_) => "exited",
+            Self::WindowLocated => "window-located",
+            Self::MarkerFileCreated => "marker-file-created",
+            Self::NotLocated => "not-located",
+            Self::TimedOut => "timeout",
+            Self::StartupTimedOut => "startup-timeout",
+            Self::ErrorWindowLocated(_) => "error-window-located",
+            Self::LogPatternMatched(_) => "log-pattern-matched",
+            Self::Interrupted => "interrupted",
+            Self::Detached => "detached",
+        }
+    }
+}
+
+/// The results of a REAPER session's assertions, e.g. a window being located, reported by
+/// `--report-junit`.
+struct Assertions {
+    /// When the session started.
+    start: time::Instant,
+
+    /// The result of each assertion, in the order they were decided.
+    results: Vec<Assertion>,
+}
+impl Default for Assertions {
+    fn default() -> Self {
+        Self {
+            start: time::Instant::now(),
+            results: Vec::new(),
+        }
+    }
+}
+impl Assertions {
+    /// Record the result of an assertion decided now.
+    fn record(&mut self, name: impl Into<String>, verdict: Verdict) {
+        self.results.push(Assertion {
+            name: name.into(),
+            duration: self.start.elapsed(),
+            verdict,
+        });
+    }
+}
+
+/// The result of one of a REAPER session's assertions.
+pub(crate) struct Assertion {
+    /// What was asserted.
+    name: String,
+
+    /// How long into the session the assertion was decided.
+    duration: time::Duration,
+
+    /// Whether the assertion held.
+    verdict: Verdict,
+}
+
+/// Whether an assertion held.
+pub(crate) enum Verdict {
+    /// The assertion held.
+    Passed,
+
+    /// The assertion did not hold, and why.
+    Failed(String),
+
+    /// The assertion was not checked, and why.
+    Skipped(String),
+}
+
+/// Set once `cargo-reaper` receives `SIGINT` or `SIGTERM` while a REAPER session is running.
+static INTERRUPTED: sync::atomic::AtomicBool = sync::atomic::AtomicBool::new(false);
+
+/// The exit code of a session interrupted by `SIGINT` or `SIGTERM`.
+pub(crate) const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+/// The exit code of a session killed because the timeout was reached, following `timeout(1)`.
+const TIMEOUT_EXIT_CODE: i32 = 124;
+
+/// The exit code of a session killed because an error window was located.
+const ERROR_WINDOW_EXIT_CODE: i32 = 3;
+
+/// The exit code of a session killed because REAPER's output matched `--fail-on-log-pattern`.
+const LOG_PATTERN_EXIT_CODE: i32 = 4;
+
+/// How long `--timeout-after-startup` waits for REAPER to start up, before counting the timeout
+/// from when REAPER was spawned.
+const STARTUP_CAP: time::Duration = time::Duration::from_secs(120);
+
+/// The titles of REAPER's main window, e.g. `untitled - REAPER v7.22`, which signal that REAPER
+/// has started up.
+const MAIN_WINDOW_PATTERN: &str = r"\bREAPER v\d";
+
+/// Whether REAPER has started up, as observed by `--timeout-after-startup`.
+#[derive(Debug, Clone, Copy)]
+enum Startup {
+    /// REAPER has not started up yet.
+    Pending,
+
+    /// REAPER started up this long after it was spawned.
+    Observed(time::Duration),
+
+    /// REAPER was not observed starting up within [`STARTUP_CAP`].
+    Unobserved,
+}
+
+/// Wait for the debugger REAPER runs under (`--debugger`) to exit. Interrupts are left to the
+/// debugger, which pauses REAPER rather than ending the session.
+fn wait_for_debugger(session: &mut Session) -> io::Result<RunOutcome> {
+    let status = session.reaper.wait()?;
+    event::emit(Event::Exited {
+        status: reaper_exit_code(status),
+    });
+    Ok(RunOutcome {
+        status: session.report(reaper_exit_code(status)),
+        reason: RunReason::Exited(status),
+    })
+}
+
+/// Wait for the REAPER session to end, polling once per second, then kill what remains of the
+/// session and report its outcome.
+///
+/// When locating windows, or waiting for a marker file, the session ends as soon as any of them is
+/// located (with status code 0), unless `--keep-going` is passed. If REAPER exits before then, the
+/// status code is 1. If a window passed to `--fail-on-window` is located, the session ends with
+/// status code 3. If the timeout is reached (counted from when REAPER started up when passed
+/// `--timeout-after-startup`), the status code is 124 unless a window was found, or
+/// `--timeout-ok` is passed. If REAPER's output matches `--fail-on-log-pattern`, the session ends
+/// with status code 4. If `cargo-reaper` is interrupted, the status code is 130. Otherwise, the
+/// status code is REAPER's exit status once it exits, see [`reaper_exit_code`].
+///
+/// The result of each assertion is recorded in the session, see [`Assertions`].
+///
+/// > Note: This function is platform agnostic
+fn wait(session: &mut Session, args: &cli::RunArgs) -> io::Result<RunOutcome> {
+    let windows = args
+        .window_title
+        .as_deref()
+        .map(LocateWindow::Title)
+        .into_iter()
+        .chain(args.window_regex.as_ref().map(LocateWindow::Regex));
+    #[cfg(target_os = "linux")]
+    let windows = windows.chain(args.window_class.as_deref().map(LocateWindow::Class));
+    let windows = windows.collect::<Vec<_>>();
+    let error_windows = args
+        .fail_on_window
+        .iter()
+        .map(|title| LocateWindow::Title(title))
+        .chain(args.fail_on_window_regex.iter().map(LocateWindow::Regex))
+        .collect::<Vec<_>>();
+    let find_window = |window: &&LocateWindow| {
+        cfg_select! {
+            target_os = "linux" => window.is_open(&args.display),
+            _ => window.is_open(),
+        }
+    };
+
+    let locate = || {
+        if let Some(window) = windows.iter().find(find_window) {
+            event::emit(Event::WindowLocated {
+                window: &window.to_string(),
+            });
+            Some(RunReason::WindowLocated)
+        } else if let Some(marker) = args
+            .wait_for_file
+            .as_deref()
+            .filter(|marker| marker.exists())
+        {
+            event::emit(Event::MarkerFileCreated { path: marker });
+            Some(RunReason::MarkerFileCreated)
+        } else {
+            None
+        }
+    };
+    let locating = !windows.is_empty() || args.wait_for_file.is_some();
+    let main_window = regex::Regex::new(MAIN_WINDOW_PATTERN).expect("valid main window pattern");
+    let mut startup = args.timeout_after_startup.then_some(Startup::Pending);
+
+    let start = time::Instant::now();
+    let spawned = time::SystemTime::now();
+    let mut located = None;
+    let mut progress = Progress::new(args.progress);
+    let target = if !windows.is_empty() {
+        Some("window")
+    } else {
+        args.wait_for_file.as_ref().map(|_| "marker file")
+    };
+
+    let (status, reason) = loop {
+        if let Some(progress) = progress.as_mut() {
+            progress.clear();
+        }
+        if INTERRUPTED.load(sync::atomic::Ordering::SeqCst) {
+            status_println!(" {} REAPER session", "Interrupted".yellow().bold());
+            event::emit(Event::Interrupted);
+            break (INTERRUPTED_EXIT_CODE, RunReason::Interrupted);
+        }
+        if let Some(line) = session
+            .log_scanner
+            .as_ref()
+            .and_then(LogScanner::matched_line)
+        {
+            event::emit(Event::LogPatternMatched { line: &line });
+            session.screenshot(true);
+            break (LOG_PATTERN_EXIT_CODE, RunReason::LogPatternMatched(line));
+        }
+        if let Some(error_window) = error_windows.iter().find(find_window) {
+            event::emit(Event::ErrorWindowLocated {
+                window: &error_window.to_string(),
+            });
+            session.screenshot(true);
+            break (
+                ERROR_WINDOW_EXIT_CODE,
+                RunReason::ErrorWindowLocated(error_window.to_string()),
+            );
+        }
+        if let Some(Startup::Pending) = startup {
+            if find_window(&&LocateWindow::Regex(&main_window))
+                || session
+                    .splash_log
+                    .as_ref()
+                    .is_some_and(|splash_log| splash_log.reports_loaded(spawned))
+            {
+                startup = Some(Startup::Observed(start.elapsed()));
+            } else if start.elapsed() >= STARTUP_CAP {
+                status_println!(
+                    "{}: REAPER was not observed starting up within {}, counting the timeout from when it was spawned",
+                    "warning".yellow().bold(),
+                    humantime::format_duration(STARTUP_CAP)
+                );
+                startup = Some(Startup::Unobserved);
+            }
+        }
+        // The timeout is counted from when REAPER was spawned, unless it is counted from when it
+        // started up (`--timeout-after-startup`), which may not have happened yet.
+        let timeout = match startup {
+            Some(Startup::Pending) => None,
+            Some(Startup::Observed(startup)) => args.timeout.map(|timeout| timeout + startup),
+            Some(Startup::Unobserved) | None => args.timeout,
+        };
+        if locating
+            && located.is_none()
+            && let Some(reason) = locate()
+        {
+            session.assertions.record(
+                match reason {
+                    RunReason::WindowLocated => locate_window_assertion(&windows),
+                    _ => marker_file_assertion(args),
+                },
+                Verdict::Passed,
+            );
+            session.screenshot(false);
+            if !args.keep_going {
+                break (0, reason);
+            }
+            located = Some(reason);
+        }
+        match session.reaper.try_wait()? {
+            Some(status) if locating => {
+                event::emit(Event::Exited {
+                    status: reaper_exit_code(status),
+                });
+                session.screenshot(located.is_none());
+                break match located.take() {
+                    Some(reason) => (0, reason),
+                    None => (1, RunReason::NotLocated),
+                };
+            }
+            Some(status) => {
+                event::emit(Event::Exited {
+                    status: reaper_exit_code(status),
+                });
+                session.screenshot(!status.success());
+                break (reaper_exit_code(status), RunReason::Exited(status));
+            }
+            None if located.is_none()
+                && let Some(startup_timeout) = args.startup_timeout
+                && start.elapsed() >= startup_timeout =>
+            {
+                event::emit(Event::StartupTimeout);
+                eprintln!(
+                    "{}: {} within startup timeout ({})",
+                    "error".magenta(),
+                    if windows.is_empty() {
+                        "file passed to `--wait-for-file` not created"
+                    } else {
+                        "window not found"
+                    },
+                    humantime::format_duration(startup_timeout)
+                );
+                session.screenshot(true);
+                break (TIMEOUT_EXIT_CODE, RunReason::StartupTimedOut);
+            }
+            None if timeout.is_some_and(|timeout| start.elapsed() >= timeout) => {
+                event::emit(Event::Timeout);
+                session.screenshot(true);
+                break match located.take() {
+                    Some(_) => (0, RunReason::TimedOut),
+                    None if args.timeout_ok => (i32::from(locating), RunReason::TimedOut),
+                    None => (TIMEOUT_EXIT_CODE, RunReason::TimedOut),
+                };
+            }
+            None => {
+                if let Some(progress) = progress.as_mut() {
+                    progress.update(
+                        start.elapsed(),
+                        timeout,
+                        target.map(|target| (target, located.is_some())),
+                        session.reaper.id(),
+                    );
+                }
+                // Sleep in short increments, so an interrupt is handled promptly.
+                let tick = time::Instant::now();
+                while !INTERRUPTED.load(sync::atomic::Ordering::SeqCst)
+                    && tick.elapsed() < time::Duration::from_secs(1)
+                {
+                    thread::sleep(time::Duration::from_millis(100));
+                }
+            }
+        }
+    };
+
+    let seconds = |duration: time::Duration| format!("{:.1}s", duration.as_secs_f64());
+    match startup {
+        Some(Startup::Observed(startup)) => status_println!(
+            "     {} took {}, run lasted {}",
+            "Startup".green().bold(),
+            seconds(startup),
+            seconds(start.elapsed().saturating_sub(startup))
+        ),
+        Some(Startup::Pending | Startup::Unobserved) => status_println!(
+            "     {} was not observed, run lasted {}",
+            "Startup".green().bold(),
+            seconds(start.elapsed())
+        ),
+        None => {}
+    }
+    session.kill()?;
+    // Output REAPER wrote just before the session ended may not have been scanned yet.
+    let (status, reason) = match session.log_scanner.as_ref().and_then(LogScanner::finish) {
+        Some(line) if !matches!(reason, RunReason::LogPatternMatched(_)) => {
+            event::emit(Event::LogPatternMatched { line: &line });
+            (LOG_PATTERN_EXIT_CODE, RunReason::LogPatternMatched(line))
+        }
+        _ => (status, reason),
+    };
+
+    // Conditions that were not met, or were not checked, since the session ended first. Windows
+    // to locate and the marker file are alternatives, so the others are skipped once one is met.
+    let unmet = |alternative_met: bool| {
+        if alternative_met {
+            Verdict::Skipped("an alternative condition was met first".to_string())
+        } else {
+            Verdict::Failed(format!("not met before {reason}"))
+        }
+    };
+    let located = located.as_ref().unwrap_or(&reason);
+    let window_located = matches!(located, RunReason::WindowLocated);
+    let marker_file_created = matches!(located, RunReason::MarkerFileCreated);
+    if !windows.is_empty() && !window_located {
+        session.assertions.record(
+            locate_window_assertion(&windows),
+            unmet(marker_file_created),
+        );
+    }
+    if args.wait_for_file.is_some() && !marker_file_created {
+        session
+            .assertions
+            .record(marker_file_assertion(args), unmet(window_located));
+    }
+    for error_window in &error_windows {
+        let error_window = error_window.to_string();
+        session.assertions.record(
+            format!("no {error_window} located"),
+            match &reason {
+                RunReason::ErrorWindowLocated(located) if *located == error_window => {
+                    Verdict::Failed("located".to_string())
                 }
+                _ => Verdict::Passed,
             },
-        )
+        );
+    }
+    for pattern in &args.fail_on_log_pattern {
+        session.assertions.record(
+            format!("output does not match `{pattern}`"),
+            match &reason {
+                RunReason::LogPatternMatched(line) if pattern.is_match(line) => {
+                    Verdict::Failed(format!("matched: {line}"))
+                }
+                _ => Verdict::Passed,
+            },
+        );
+    }
+    if let RunReason::Exited(status) = reason {
+        session.assertions.record(
+            "REAPER exits successfully",
+            if status.success() {
+                Verdict::Passed
+            } else {
+                Verdict::Failed(format!("REAPER exited unsuccessfully ({status})"))
+            },
+        );
+    }
+
+    Ok(RunOutcome {
+        status: session.report(status),
+        reason,
     })
 }
 
-fn run_reaper(
-    reaper: &path::PathBuf,
-    project: Option<&path::PathBuf>,
-    stdin: process::Stdio,
-    stdout: process::Stdio,
-    stderr: process::Stdio,
-) -> io::Result<process::Child> {
-    process::Command::new(reaper)
-        .args(project.iter())
-        .stdin(stdin)
-        .stdout(stdout)
-        .stderr(stderr)
-        .spawn()
-        .map_err(|err| {
-            io::Error::new(
-                err.kind(),
-                format!("Command `{}` failed: {}", reaper.display(), err),
-            )
-        })
+/// The name of the assertion that one of `windows` is located.
+fn locate_window_assertion(windows: &[LocateWindow]) -> String {
+    format!(
+        "{} located",
+        windows
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(" or ")
+    )
+}
+
+/// The name of the assertion that the `--wait-for-file` marker file is created.
+fn marker_file_assertion(args: &cli::RunArgs) -> String {
+    format!(
+        "marker file `{}` created",
+        args.wait_for_file
+            .as_deref()
+            .unwrap_or(path::Path::new(""))
+            .display()
+    )
 }
 
+/// The exit code `cargo-reaper` exits with when REAPER exits with `status`. On unix, REAPER being
+/// terminated by a signal maps onto `128 + signal`, following the shell convention.
+fn reaper_exit_code(status: process::ExitStatus) -> i32 {
+    #[cfg(unix)]
+    if let Some(signal) = std::os::unix::process::ExitStatusExt::signal(&status) {
+        return 128 + signal;
+    }
+    status.code().unwrap_or(1)
+}
+
+/// The titles of the windows on the virtual display.
 #[cfg(target_os = "linux")]
-fn run_reaper_headless(
-    reaper: &path::PathBuf,
-    project: Option<&path::PathBuf>,
-    display: &str,
-    stdin: cli::Stdio,
-    stdout: cli::Stdio,
-    stderr: cli::Stdio,
-) -> io::Result<(process::Child, process::Child)> {
-    const XVFB: &str = "Xvfb";
-    const XVFB_ARGS: &[&str; 5] = &["-screen", "0", "1024x768x24", "-nolisten", "tcp"];
-
-    process::Command::new(XVFB)
-        .arg(display)
-        .args(XVFB_ARGS)
+fn window_titles(display: &str) -> Vec<String> {
+    const XDOTOOL_ARGS: &[&str; 5] = &["search", "--name", "", "getwindowname", "%@"];
+
+    process::Command::new(XDOTOOL)
+        .args(XDOTOOL_ARGS)
         .env("DISPLAY", display)
-        .stdin(stdin)
-        .stdout(stdout)
-        .stderr(stderr)
-        .spawn()
-        .map_err(|err| io::Error::new(err.kind(), format!("Command `{}` failed: {}", XVFB, err)))
-        .and_then(|xvfb| {
-            Ok((
-                xvfb,
-                process::Command::new(reaper)
-                    .args(project.iter())
-                    .env("DISPLAY", display)
-                    .stdin(stdin)
-                    .stdout(stdout)
-                    .stderr(stderr)
-                    .spawn()
-                    .map_err(|err| {
-                        io::Error::new(
-                            err.kind(),
-                            format!("Command `{}` failed: {}", reaper.display(), err),
-                        )
-                    })?,
-            ))
+        .output()
+        .map(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .map(String::from)
+                .collect()
         })
+        .unwrap_or_default()
 }
 
+/// Search the virtual display for a window with a class matching `window_class`.
 #[cfg(target_os = "linux")]
-fn kill_and_exit(
-    reaper: &mut process::Child,
-    xvfb: &mut process::Child,
-    exit_code: i32,
-) -> io::Result<()> {
-    reaper
-        .kill()
-        .and_then(|_| reaper.wait())
-        .and_then(|_| xvfb.kill())
-        .and_then(|_| xvfb.wait())?;
-    process::exit(exit_code);
+fn find_window_class(display: &str, window_class: &str) -> bool {
+    const XDOTOOL_ARGS: &[&str; 2] = &["search", "--class"];
+
+    process::Command::new(XDOTOOL)
+        .args(XDOTOOL_ARGS)
+        .arg(window_class)
+        .env("DISPLAY", display)
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+/// The titles of the top-level windows.
+#[cfg(target_os = "windows")]
+fn window_titles() -> Vec<String> {
+    use windows::{
+        Win32::{
+            Foundation::{HWND, LPARAM},
+            UI::WindowsAndMessaging::{EnumWindows, GetWindowTextW},
+        },
+        core::BOOL,
+    };
+
+    unsafe extern "system" fn collect_title(hwnd: HWND, titles: LPARAM) -> BOOL {
+        // SAFETY: `titles` is the `Vec<String>` pointer passed to `EnumWindows` below, which
+        // outlives the enumeration.
+        let titles = unsafe { &mut *(titles.0 as *mut Vec<String>) };
+        let mut title = [0u16; 512];
+        let len = unsafe { GetWindowTextW(hwnd, &mut title) };
+        if len > 0 {
+            titles.push(String::from_utf16_lossy(&title[..len as usize]));
+        }
+        true.into()
+    }
+
+    let mut titles: Vec<String> = Vec::new();
+    match unsafe {
+        EnumWindows(
+            Some(collect_title),
+            LPARAM(&mut titles as *mut Vec<String> as isize),
+        )
+    } {
+        Ok(()) => titles,
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Post `WM_CLOSE` to the visible top-level windows of the process `pid`, returning whether it
+/// had any.
+#[cfg(target_os = "windows")]
+fn close_windows(pid: u32) -> bool {
+    use windows::{
+        Win32::{
+            Foundation::{HWND, LPARAM, WPARAM},
+            UI::WindowsAndMessaging::{
+                EnumWindows, GetWindowThreadProcessId, IsWindowVisible, PostMessageW, WM_CLOSE,
+            },
+        },
+        core::BOOL,
+    };
+
+    /// The process whose windows are closed, and whether any were.
+    struct Target {
+        pid: u32,
+        closed: bool,
+    }
+
+    unsafe extern "system" fn close_window(hwnd: HWND, target: LPARAM) -> BOOL {
+        // SAFETY: `target` is the `Target` pointer passed to `EnumWindows` below, which outlives
+        // the enumeration.
+        let target = unsafe { &mut *(target.0 as *mut Target) };
+        let mut pid = 0;
+        unsafe { GetWindowThreadProcessId(hwnd, Some(&mut pid)) };
+        if pid == target.pid && unsafe { IsWindowVisible(hwnd) }.as_bool() {
+            target.closed |=
+                unsafe { PostMessageW(Some(hwnd), WM_CLOSE, WPARAM(0), LPARAM(0)) }.is_ok();
+        }
+        true.into()
+    }
+
+    let mut target = Target { pid, closed: false };
+    // Enumeration only fails when the callback does, which it never does.
+    let _ = unsafe {
+        EnumWindows(
+            Some(close_window),
+            LPARAM(&mut target as *mut Target as isize),
+        )
+    };
+    target.closed
+}
+
+/// The titles of the windows of every running application.
+///
+/// Window titles are queried through `System Events`, which requires the terminal to be granted
+/// accessibility permissions.
+#[cfg(target_os = "macos")]
+fn window_titles() -> Vec<String> {
+    const OSASCRIPT: &str = "osascript";
+    const OSASCRIPT_ARGS: &[&str; 2] = &[
+        "-e",
+        r#"tell application "System Events"
+            set titles to ""
+            repeat with window_name in (get name of every window of every process)
+                repeat with title in window_name
+                    set titles to titles & title & linefeed
+                end repeat
+            end repeat
+            return titles
+        end tell"#,
+    ];
+
+    process::Command::new(OSASCRIPT)
+        .args(OSASCRIPT_ARGS)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
 }
@@ -0,0 +1,118 @@
+use std::{path, sync, time};
+
+use crate::util;
+
+/// A lifecycle event of a REAPER session, printed to stdout as a line of JSON when passed
+/// `--message-format json`.
+pub(crate) enum Event<'a> {
+    /// The plugin(s) were built before REAPER was launched.
+    BuildFinished,
+
+    /// The virtual display server REAPER is attached to was spawned.
+    #[cfg(target_os = "linux")]
+    DisplayServerSpawned { pid: u32, display: &'a str },
+
+    /// REAPER was spawned.
+    ReaperSpawned { pid: u32, path: &'a path::Path },
+
+    /// A window passed to `--locate-window`, `--locate-window-regex` or `--locate-class` was
+    /// located.
+    WindowLocated { window: &'a str },
+
+    /// A window passed to `--fail-on-window` or `--fail-on-window-regex` was located.
+    ErrorWindowLocated { window: &'a str },
+
+    /// The file passed to `--wait-for-file` was created.
+    MarkerFileCreated { path: &'a path::Path },
+
+    /// A line of REAPER's output matched `--fail-on-log-pattern`.
+    LogPatternMatched { line: &'a str },
+
+    /// The timeout was reached.
+    Timeout,
+
+    /// A window was not located, or the marker file was not created, within `--startup-timeout`.
+    StartupTimeout,
+
+    /// `cargo-reaper` received `SIGINT` or `SIGTERM`.
+    Interrupted,
+
+    /// A process that was still running once the session ended was killed, `graceful` being
+    /// whether it exited within the grace period of `SIGTERM`.
+    Killed { process: &'a str, graceful: bool },
+
+    /// REAPER exited on its own.
+    Exited { status: i32 },
+
+    /// The session ended, `status` being the status code `cargo-reaper` exits with.
+    Finished { status: i32, reason: &'a str },
+}
+impl Event<'_> {
+    /// The name of the event, i.e. the value of its `event` field.
+    fn name(&self) -> &'static str {
+        match self {
+            Self::BuildFinished => "build-finished",
+            #[cfg(target_os = "linux")]
+            Self::DisplayServerSpawned { .. } => "display-server-spawned",
+            Self::ReaperSpawned { .. } => "reaper-spawned",
+            Self::WindowLocated { .. } => "window-located",
+            Self::ErrorWindowLocated { .. } => "error-window-located",
+            Self::MarkerFileCreated { .. } => "marker-file-created",
+            Self::LogPatternMatched { .. } => "log-pattern-matched",
+            Self::Timeout => "timeout",
+            Self::StartupTimeout => "startup-timeout",
+            Self::Interrupted => "interrupted",
+            Self::Killed { .. } => "killed",
+            Self::Exited { .. } => "exited",
+            Self::Finished { .. } => "finished",
+        }
+    }
+
+    /// The fields of the event, besides `event` and `timestamp`.
+    fn fields(&self) -> serde_json::Value {
+        match self {
+            Self::BuildFinished | Self::Timeout | Self::StartupTimeout | Self::Interrupted => {
+                serde_json::json!({})
+            }
+            #[cfg(target_os = "linux")]
+            Self::DisplayServerSpawned { pid, display } => {
+                serde_json::json!({ "pid": pid, "display": display })
+            }
+            Self::ReaperSpawned { pid, path } => serde_json::json!({ "pid": pid, "path": path }),
+            Self::WindowLocated { window } | Self::ErrorWindowLocated { window } => {
+                serde_json::json!({ "window": window })
+            }
+            Self::MarkerFileCreated { path } => serde_json::json!({ "path": path }),
+            Self::LogPatternMatched { line } => serde_json::json!({ "line": line }),
+            Self::Killed { process, graceful } => {
+                serde_json::json!({ "process": process, "graceful": graceful })
+            }
+            Self::Exited { status } => serde_json::json!({ "status": status }),
+            Self::Finished { status, reason } => {
+                serde_json::json!({ "status": status, "reason": reason })
+            }
+        }
+    }
+}
+
+/// Print lifecycle events to stdout, and human-readable output to stderr from now on.
+pub(crate) fn enable() {
+    util::JSON_MESSAGE_FORMAT.store(true, sync::atomic::Ordering::Relaxed);
+}
+
+/// Print `event` as a line of JSON, if passed `--message-format json`.
+pub(crate) fn emit(event: Event) {
+    if !util::JSON_MESSAGE_FORMAT.load(sync::atomic::Ordering::Relaxed) {
+        return;
+    }
+    let mut object = serde_json::json!({
+        "event": event.name(),
+        "timestamp": humantime::format_rfc3339_millis(time::SystemTime::now()).to_string(),
+    });
+    if let (Some(object), serde_json::Value::Object(fields)) =
+        (object.as_object_mut(), event.fields())
+    {
+        object.extend(fields);
+    }
+    println!("{object}");
+}
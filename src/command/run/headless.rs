@@ -0,0 +1,378 @@
+use std::{collections, env, fs, io, path, process, thread, time};
+
+use crate::{
+    cli,
+    util::{self, Colorize, status_println},
+};
+
+/// The display number `auto` starts scanning from for an unused display.
+const AUTO_DISPLAY_START: u32 = 99;
+
+/// The number of unused displays `auto` attempts to start the display server on.
+const AUTO_DISPLAY_ATTEMPTS: u32 = 5;
+
+/// How long to wait for the display server to start accepting connections.
+const STARTUP_TIMEOUT: time::Duration = time::Duration::from_secs(5);
+
+/// A display server that REAPER can be attached to when running headless.
+pub(crate) trait HeadlessBackend {
+    /// The program run as the display server.
+    fn program(&self) -> &'static str;
+
+    /// The packages that provide the program, for Debian/Ubuntu, Fedora and Arch Linux.
+    fn packages(&self) -> [&'static str; 3];
+
+    /// Whether the display server renders into a window on the host display, in which case the
+    /// host's `DISPLAY` is never used as the virtual display.
+    fn is_nested(&self) -> bool {
+        false
+    }
+
+    /// The command that spawns the display server on `display`, with the user's `server_args`.
+    /// Default arguments that conflict with `server_args` are omitted.
+    fn command(
+        &mut self,
+        program: &path::Path,
+        display: &str,
+        screen: &cli::Screen,
+        server_args: &[String],
+    ) -> process::Command;
+
+    /// The X server REAPER connects to, which is started by the display server itself for
+    /// compositors running Xwayland.
+    fn x_server(&self) -> &'static str {
+        self.program()
+    }
+
+    /// Whether the display server is accepting connections.
+    fn is_ready(&self, display: &str) -> bool {
+        display_socket(display).exists()
+    }
+
+    /// The `DISPLAY` REAPER is attached to.
+    fn display_env(&self, display: &str) -> String {
+        display.to_string()
+    }
+}
+
+/// The backend selected by `--backend`.
+pub(crate) fn backend(backend: cli::Backend) -> Box<dyn HeadlessBackend> {
+    match backend {
+        cli::Backend::Xvfb => Box::new(Xvfb),
+        cli::Backend::Xephyr => Box::new(Xephyr),
+        cli::Backend::Weston => Box::new(Weston::default()),
+    }
+}
+
+/// A virtual framebuffer X server.
+struct Xvfb;
+impl HeadlessBackend for Xvfb {
+    fn program(&self) -> &'static str {
+        "Xvfb"
+    }
+
+    fn packages(&self) -> [&'static str; 3] {
+        ["xvfb", "xorg-x11-server-Xvfb", "xorg-server-xvfb"]
+    }
+
+    fn command(
+        &mut self,
+        program: &path::Path,
+        display: &str,
+        screen: &cli::Screen,
+        server_args: &[String],
+    ) -> process::Command {
+        let mut command = process::Command::new(program);
+        command.arg(display);
+        if !has_any_arg(server_args, &["-screen"]) {
+            command.args(["-screen", "0"]).arg(screen.to_string());
+        }
+        if !has_any_arg(server_args, &["-nolisten", "-listen"]) {
+            command.args(["-nolisten", "tcp"]);
+        }
+        command.args(server_args).env("DISPLAY", display);
+        command
+    }
+}
+
+/// A nested X server, rendering into a window on the host display.
+struct Xephyr;
+impl HeadlessBackend for Xephyr {
+    fn program(&self) -> &'static str {
+        "Xephyr"
+    }
+
+    fn packages(&self) -> [&'static str; 3] {
+        [
+            "xserver-xephyr",
+            "xorg-x11-server-Xephyr",
+            "xorg-server-xephyr",
+        ]
+    }
+
+    fn is_nested(&self) -> bool {
+        true
+    }
+
+    fn command(
+        &mut self,
+        program: &path::Path,
+        display: &str,
+        screen: &cli::Screen,
+        server_args: &[String],
+    ) -> process::Command {
+        let mut command = process::Command::new(program);
+        command.arg(display);
+        if !has_any_arg(server_args, &["-screen"]) {
+            command
+                .arg("-screen")
+                .arg(format!("{}x{}", screen.width, screen.height));
+        }
+        if !has_any_arg(server_args, &["-nolisten", "-listen"]) {
+            command.args(["-nolisten", "tcp"]);
+        }
+        command.args(server_args);
+        command
+    }
+}
+
+/// A headless Wayland compositor, running Xwayland so REAPER still gets an X display.
+#[derive(Default)]
+struct Weston {
+    /// The X display numbers in use before the compositor was spawned. Xwayland picks its own
+    /// display number, which is the one that appears afterwards.
+    displays_before: collections::BTreeSet<u32>,
+}
+impl Weston {
+    /// The display number Xwayland was started on, if it has started.
+    fn xwayland_display(&self) -> Option<u32> {
+        display_numbers()
+            .into_iter()
+            .find(|number| !self.displays_before.contains(number))
+    }
+}
+impl HeadlessBackend for Weston {
+    fn program(&self) -> &'static str {
+        "weston"
+    }
+
+    fn packages(&self) -> [&'static str; 3] {
+        ["weston", "weston", "weston"]
+    }
+
+    fn command(
+        &mut self,
+        program: &path::Path,
+        _display: &str,
+        screen: &cli::Screen,
+        server_args: &[String],
+    ) -> process::Command {
+        self.displays_before = display_numbers();
+        let mut command = process::Command::new(program);
+        if !has_any_arg(server_args, &["--backend", "-B"]) {
+            command.arg("--backend=headless");
+        }
+        if !has_any_arg(server_args, &["--socket", "-S"]) {
+            command.arg(format!("--socket=cargo-reaper-{}", process::id()));
+        }
+        if !has_any_arg(server_args, &["--width"]) {
+            command.arg(format!("--width={}", screen.width));
+        }
+        if !has_any_arg(server_args, &["--height"]) {
+            command.arg(format!("--height={}", screen.height));
+        }
+        command.arg("--xwayland").args(server_args);
+        command
+    }
+
+    fn x_server(&self) -> &'static str {
+        "Xwayland"
+    }
+
+    fn is_ready(&self, _display: &str) -> bool {
+        self.xwayland_display().is_some()
+    }
+
+    fn display_env(&self, display: &str) -> String {
+        self.xwayland_display()
+            .map_or_else(|| display.to_string(), |number| format!(":{number}"))
+    }
+}
+
+/// Whether `server_args` contains any of `flags`, either on its own or as `--flag=value`.
+fn has_any_arg(server_args: &[String], flags: &[&str]) -> bool {
+    server_args.iter().any(|arg| {
+        let flag = arg.split_once('=').map_or(arg.as_str(), |(flag, _)| flag);
+        flags.contains(&flag)
+    })
+}
+
+/// The file the display server's output is written to when passed `--log-file`, i.e. a sibling of
+/// REAPER's log file named after the display server, e.g. `reaper.xvfb.log`.
+pub(crate) fn log_file(path: &path::Path, backend: &dyn HeadlessBackend) -> path::PathBuf {
+    let program = backend.program().to_lowercase();
+    path.with_extension(match path.extension() {
+        Some(extension) => format!("{program}.{}", extension.to_string_lossy()),
+        None => program,
+    })
+}
+
+/// The socket the X server for `display` accepts connections on.
+pub(crate) fn display_socket(display: &str) -> path::PathBuf {
+    path::PathBuf::from(format!(
+        "/tmp/.X11-unix/X{}",
+        display.trim_start_matches(':')
+    ))
+}
+
+/// The X display numbers that currently have a socket.
+fn display_numbers() -> collections::BTreeSet<u32> {
+    fs::read_dir("/tmp/.X11-unix")
+        .map(|entries| {
+            entries
+                .filter_map(|entry| {
+                    entry
+                        .ok()?
+                        .file_name()
+                        .to_str()?
+                        .strip_prefix('X')?
+                        .parse()
+                        .ok()
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The first display number from `start` that has neither a lock file nor a socket.
+fn unused_display_number(start: u32) -> u32 {
+    (start..)
+        .find(|number| {
+            !path::Path::new(&format!("/tmp/.X{number}-lock")).exists()
+                && !display_socket(&format!(":{number}")).exists()
+        })
+        .unwrap_or(start)
+}
+
+/// The user's `--xvfb-args`, split on whitespace. A nested display server is never run on the
+/// host's display, which falls back to `auto`.
+fn server_args(backend: &dyn HeadlessBackend, args: &mut cli::RunArgs) -> Vec<String> {
+    if backend.is_nested() && env::var("DISPLAY").is_ok_and(|host| host == args.display) {
+        args.display = cli::AUTO_XSERVER_DISPLAY.to_string();
+    }
+    args.xvfb_args
+        .iter()
+        .flat_map(|server_args| server_args.split_whitespace())
+        .map(String::from)
+        .collect()
+}
+
+/// The command that would spawn the display server for `--dry-run`, on the first unused display
+/// number when the display is `auto`. `--display` is updated to the display REAPER would be
+/// attached to.
+pub(crate) fn dry_run_command(
+    backend: &mut dyn HeadlessBackend,
+    program: &path::Path,
+    args: &mut cli::RunArgs,
+) -> process::Command {
+    let server_args = server_args(backend, args);
+    if args.display == cli::AUTO_XSERVER_DISPLAY {
+        args.display = format!(":{}", unused_display_number(AUTO_DISPLAY_START));
+    }
+    let mut command = backend.command(program, &args.display, &args.screen, &server_args);
+    command.envs(args.xvfb_env.iter().map(|env| (&env.key, &env.value)));
+    args.display = backend.display_env(&args.display);
+    command
+}
+
+/// Spawn the display server on `--display` and wait for it to accept connections. When the
+/// display is `auto`, an unused display number is chosen, retrying with the next one if the
+/// display server still reports the display as taken. `--display` is updated to the display
+/// REAPER should be attached to.
+pub(crate) fn spawn(
+    backend: &mut dyn HeadlessBackend,
+    program: &path::Path,
+    args: &mut cli::RunArgs,
+) -> io::Result<process::Child> {
+    let server_args = server_args(backend, args);
+    let log_file = args.log_file.as_deref().map(|path| log_file(path, backend));
+    let display = &mut args.display;
+    let auto = display == cli::AUTO_XSERVER_DISPLAY;
+
+    let mut number = AUTO_DISPLAY_START;
+    for _ in 0..AUTO_DISPLAY_ATTEMPTS {
+        let candidate = if auto {
+            number = unused_display_number(number);
+            format!(":{number}")
+        } else {
+            display.clone()
+        };
+        let mut command = backend.command(program, &candidate, &args.screen, &server_args);
+        command
+            .envs(args.xvfb_env.iter().map(|env| (&env.key, &env.value)))
+            .stdin(process::Stdio::null());
+        match log_file
+            .as_deref()
+            .map(|path| super::open_log_file(path, &command))
+            .transpose()?
+        {
+            Some(log_file) => command.stdout(log_file.try_clone()?).stderr(log_file),
+            None => command
+                .stdout(match &args.xvfb_stdout {
+                    cli::Stdio::Inherit => util::inherit_stdout(),
+                    stdout => stdout.output()?,
+                })
+                .stderr(args.xvfb_stderr.output()?),
+        };
+        let mut server = command.spawn().map_err(|err| {
+            io::Error::new(
+                err.kind(),
+                format!("Command `{}` failed: {}", program.display(), err),
+            )
+        })?;
+        // The display server exits straight away when the display is taken, otherwise it is
+        // ready once it accepts connections.
+        let start = time::Instant::now();
+        while server.try_wait()?.is_none() {
+            if !backend.is_ready(&candidate) && start.elapsed() >= STARTUP_TIMEOUT {
+                // Otherwise REAPER would be attached to a display nobody serves, and fail much
+                // later with an unrelated X error.
+                let _ = server.kill();
+                let _ = server.wait();
+                return Err(io::Error::other(format!(
+                    "`{}` was started, but {} never accepted connections within {}s",
+                    program.display(),
+                    backend.x_server(),
+                    STARTUP_TIMEOUT.as_secs()
+                )));
+            }
+            if backend.is_ready(&candidate) {
+                let candidate = backend.display_env(&candidate);
+                if candidate != *display {
+                    status_println!(
+                        "       {} virtual display {candidate}",
+                        "Using".green().bold()
+                    );
+                }
+                *display = candidate;
+                return Ok(server);
+            }
+            thread::sleep(time::Duration::from_millis(100));
+        }
+        if !auto {
+            break;
+        }
+        number += 1;
+    }
+    Err(io::Error::other(if auto {
+        format!(
+            "failed to start `{}` on an unused display after {AUTO_DISPLAY_ATTEMPTS} attempts",
+            program.display()
+        )
+    } else {
+        format!(
+            "`{}` exited before display {display} was ready",
+            program.display()
+        )
+    }))
+}
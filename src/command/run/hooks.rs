@@ -0,0 +1,105 @@
+use std::{env, path, process};
+
+use crate::util::{self, Colorize, find_project_root, status_println};
+
+/// The environment variable hooks are given REAPER's PID in, once it has been spawned.
+const PID_ENV: &str = "CARGO_REAPER_PID";
+
+/// The environment variable hooks are given the display REAPER is attached to in, when running
+/// headless.
+#[cfg(target_os = "linux")]
+const DISPLAY_ENV: &str = "CARGO_REAPER_DISPLAY";
+
+/// When a hook is run during a session.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Stage {
+    /// Before REAPER is launched (`--before`).
+    Before,
+
+    /// Once the session has ended, however it ended (`--after`).
+    After,
+}
+impl Stage {
+    /// The flag hooks of this stage are passed with.
+    fn flag(self) -> &'static str {
+        match self {
+            Self::Before => "--before",
+            Self::After => "--after",
+        }
+    }
+}
+
+/// The command that runs `hook` through the shell from the project root (or the current
+/// directory outside of a `cargo-reaper` project), given REAPER's `pid` once it has been spawned
+/// and the `display` it is attached to when running headless.
+pub(crate) fn command(
+    hook: &str,
+    pid: Option<u32>,
+    #[cfg_attr(not(target_os = "linux"), expect(unused_variables))] display: Option<&str>,
+) -> process::Command {
+    let (shell, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    let mut command = process::Command::new(shell);
+    command
+        .args([flag, hook])
+        .current_dir(
+            find_project_root()
+                .or_else(|_| env::current_dir())
+                .unwrap_or_else(|_| path::PathBuf::from(".")),
+        )
+        .envs(pid.map(|pid| (PID_ENV, pid.to_string())));
+    #[cfg(target_os = "linux")]
+    command.envs(display.map(|display| (DISPLAY_ENV, display)));
+    command
+}
+
+/// Run each of the `stage`'s `hooks` in order, reporting the ones that fail, and returning
+/// whether they all succeeded. Failures are reported as errors when passed `--strict-hooks`, and
+/// as warnings otherwise.
+pub(crate) fn run(
+    stage: Stage,
+    hooks: &[String],
+    pid: Option<u32>,
+    display: Option<&str>,
+    strict: bool,
+) -> bool {
+    let mut succeeded = true;
+    for hook in hooks {
+        status_println!(
+            "     {} `{}` hook `{hook}`",
+            "Running".green().bold(),
+            stage.flag()
+        );
+        let failure = match command(hook, pid, display)
+            .stdin(process::Stdio::null())
+            .stdout(util::inherit_stdout())
+            .stderr(process::Stdio::inherit())
+            .status()
+        {
+            Ok(status) if status.success() => continue,
+            Ok(status) => match status.code() {
+                Some(code) => format!("exited with status code {code}"),
+                None => format!("was terminated ({status})"),
+            },
+            Err(err) => format!("failed to run: {err}"),
+        };
+        succeeded = false;
+        if strict {
+            eprintln!(
+                "{}: `{}` hook `{hook}` {failure}",
+                "error".magenta(),
+                stage.flag()
+            );
+        } else {
+            status_println!(
+                "{}: `{}` hook `{hook}` {failure}",
+                "warning".yellow().bold(),
+                stage.flag()
+            );
+        }
+    }
+    succeeded
+}
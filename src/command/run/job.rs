@@ -0,0 +1,128 @@
+use std::{io, os::windows::io::AsRawHandle, process};
+
+use windows::{
+    Win32::{
+        Foundation::{CloseHandle, HANDLE},
+        System::{
+            Diagnostics::ToolHelp::{
+                CreateToolhelp32Snapshot, TH32CS_SNAPTHREAD, THREADENTRY32, Thread32First,
+                Thread32Next,
+            },
+            JobObjects::{
+                AssignProcessToJobObject, CreateJobObjectW, JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+                JOBOBJECT_EXTENDED_LIMIT_INFORMATION, JobObjectExtendedLimitInformation,
+                SetInformationJobObject, TerminateJobObject,
+            },
+            Threading::{OpenThread, ResumeThread, THREAD_SUSPEND_RESUME},
+        },
+    },
+    core::PCWSTR,
+};
+
+/// A Job Object REAPER (and every process it spawns) is assigned to, which is killed once the job
+/// is closed, i.e. when it is dropped or `cargo-reaper` itself is terminated, so that REAPER
+/// never outlives `cargo-reaper` and keeps plugin(s) locked.
+///
+/// Only processes spawned after REAPER is assigned to the job belong to it, which is why REAPER
+/// is created with `CREATE_SUSPENDED`, and only [`resume`]d once assigned.
+pub(crate) struct Job(HANDLE);
+impl Job {
+    /// Create a job and assign `child` to it.
+    pub(crate) fn assign(child: &process::Child) -> io::Result<Self> {
+        // SAFETY: the job is created without security attributes or a name, and owned by `Self`,
+        // which closes it once dropped.
+        let job = Self(unsafe { CreateJobObjectW(None, PCWSTR::null()) }?);
+        let mut limits = JOBOBJECT_EXTENDED_LIMIT_INFORMATION::default();
+        limits.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+        // SAFETY: `limits` is the `JOBOBJECT_EXTENDED_LIMIT_INFORMATION` the information class
+        // expects, and its size is passed along with it.
+        unsafe {
+            SetInformationJobObject(
+                job.0,
+                JobObjectExtendedLimitInformation,
+                &limits as *const JOBOBJECT_EXTENDED_LIMIT_INFORMATION as *const _,
+                size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+            )
+        }?;
+        // SAFETY: the child's handle is valid for as long as the child is borrowed.
+        unsafe { AssignProcessToJobObject(job.0, HANDLE(child.as_raw_handle())) }?;
+        Ok(job)
+    }
+
+    /// Kill every process still running in the job.
+    pub(crate) fn terminate(&self) -> io::Result<()> {
+        // SAFETY: the job is open until `Self` is dropped.
+        unsafe { TerminateJobObject(self.0, 1) }?;
+        Ok(())
+    }
+}
+impl Drop for Job {
+    fn drop(&mut self) {
+        // SAFETY: the job was opened by `Job::assign`, and is closed exactly once.
+        let _ = unsafe { CloseHandle(self.0) };
+    }
+}
+
+/// Resume every thread of `child`, which was created with `CREATE_SUSPENDED`.
+pub(crate) fn resume(child: &process::Child) -> io::Result<()> {
+    // SAFETY: the snapshot is closed below, once its threads have been walked.
+    let snapshot = unsafe { CreateToolhelp32Snapshot(TH32CS_SNAPTHREAD, 0) }?;
+    let mut entry = THREADENTRY32 {
+        dwSize: size_of::<THREADENTRY32>() as u32,
+        ..Default::default()
+    };
+    let mut resume_threads = || -> io::Result<()> {
+        // SAFETY: `entry.dwSize` is set to the size of the entry, as the snapshot API expects.
+        let mut next = unsafe { Thread32First(snapshot, &mut entry) };
+        while next.is_ok() {
+            if entry.th32OwnerProcessID == child.id() {
+                // SAFETY: the thread is closed right after it is resumed.
+                let thread =
+                    unsafe { OpenThread(THREAD_SUSPEND_RESUME, false, entry.th32ThreadID) }?;
+                // SAFETY: the thread was opened with `THREAD_SUSPEND_RESUME` access.
+                let resumed = unsafe { ResumeThread(thread) };
+                // SAFETY: the thread was opened above, and is closed exactly once.
+                let _ = unsafe { CloseHandle(thread) };
+                if resumed == u32::MAX {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            // SAFETY: as with `Thread32First`.
+            next = unsafe { Thread32Next(snapshot, &mut entry) };
+        }
+        Ok(())
+    };
+    let result = resume_threads();
+    // SAFETY: the snapshot was opened above, and is closed exactly once.
+    let _ = unsafe { CloseHandle(snapshot) };
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{process, thread, time};
+
+    use super::Job;
+
+    #[test]
+    fn dropping_the_job_kills_its_processes() {
+        let mut child = process::Command::new("powershell")
+            .args(["-NoProfile", "-Command", "Start-Sleep -Seconds 60"])
+            .stdout(process::Stdio::null())
+            .stderr(process::Stdio::null())
+            .spawn()
+            .expect("failed to spawn a dummy child process");
+        let job = Job::assign(&child).expect("failed to assign the child to a job");
+        assert!(child.try_wait().unwrap().is_none());
+
+        drop(job);
+        let start = time::Instant::now();
+        while child.try_wait().unwrap().is_none() {
+            if start.elapsed() > time::Duration::from_secs(10) {
+                let _ = child.kill();
+                panic!("the child outlived its job");
+            }
+            thread::sleep(time::Duration::from_millis(50));
+        }
+    }
+}
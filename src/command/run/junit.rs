@@ -0,0 +1,107 @@
+use std::{fmt::Write as _, fs, io, path, time};
+
+use super::{Assertion, Verdict};
+use crate::util::find_project_root;
+
+/// The name of the test suite, i.e. the name of the package (or workspace directory) containing
+/// the extension plugin(s).
+pub(crate) fn suite_name() -> String {
+    let project_root = find_project_root().ok();
+    project_root
+        .as_deref()
+        .and_then(|project_root| {
+            cargo_toml::Manifest::from_path(project_root.join("Cargo.toml"))
+                .ok()?
+                .package
+                .map(|package| package.name)
+        })
+        .or_else(|| {
+            project_root?
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+        })
+        .unwrap_or_else(|| "cargo-reaper".to_string())
+}
+
+/// Write a JUnit XML report to `path`, with a test case per assertion and REAPER's captured
+/// output attached as `system-out`.
+pub(crate) fn write(
+    path: &path::Path,
+    suite_name: &str,
+    assertions: &[Assertion],
+    duration: time::Duration,
+    output: &str,
+) -> io::Result<()> {
+    let count = |verdict: fn(&Verdict) -> bool| {
+        assertions
+            .iter()
+            .filter(|assertion| verdict(&assertion.verdict))
+            .count()
+    };
+    let suite_name = escape(suite_name);
+    let mut report = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites>\n");
+    let _ = writeln!(
+        report,
+        "  <testsuite name=\"{suite_name}\" tests=\"{}\" failures=\"{}\" errors=\"0\" skipped=\"{}\" time=\"{:.3}\">",
+        assertions.len(),
+        count(|verdict| matches!(verdict, Verdict::Failed(_))),
+        count(|verdict| matches!(verdict, Verdict::Skipped(_))),
+        duration.as_secs_f64()
+    );
+    for assertion in assertions {
+        let _ = write!(
+            report,
+            "    <testcase name=\"{}\" classname=\"{suite_name}\" time=\"{:.3}\"",
+            escape(&assertion.name),
+            assertion.duration.as_secs_f64()
+        );
+        match &assertion.verdict {
+            Verdict::Passed => report.push_str("/>\n"),
+            Verdict::Failed(message) => {
+                let _ = writeln!(
+                    report,
+                    ">\n      <failure message=\"{}\"/>\n    </testcase>",
+                    escape(message)
+                );
+            }
+            Verdict::Skipped(message) => {
+                let _ = writeln!(
+                    report,
+                    ">\n      <skipped message=\"{}\"/>\n    </testcase>",
+                    escape(message)
+                );
+            }
+        }
+    }
+    let _ = writeln!(
+        report,
+        "    <system-out>{}</system-out>\n  </testsuite>\n</testsuites>",
+        escape(output)
+    );
+
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, report)
+}
+
+/// Escape `text` for use in XML attribute values and text content, dropping characters XML does
+/// not allow.
+fn escape(text: &str) -> String {
+    text.chars()
+        .filter(|c| matches!(c, '\t' | '\n' | '\r') || *c >= ' ')
+        .fold(String::with_capacity(text.len()), |mut escaped, c| {
+            match c {
+                '&' => escaped.push_str("&amp;"),
+                '<' => escaped.push_str("&lt;"),
+                '>' => escaped.push_str("&gt;"),
+                '"' => escaped.push_str("&quot;"),
+                '\'' => escaped.push_str("&apos;"),
+                c => escaped.push(c),
+            }
+            escaped
+        })
+}
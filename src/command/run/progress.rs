@@ -0,0 +1,96 @@
+use std::{
+    io::{self, IsTerminal, Write},
+    sync, time,
+};
+
+use crate::util::{self, Colorize};
+
+/// How often a line is printed by `--progress` when stderr is not a terminal.
+const INTERVAL: time::Duration = time::Duration::from_secs(10);
+
+/// The heartbeat printed to stderr by `--progress` while waiting for a REAPER session to end.
+pub(crate) struct Progress {
+    /// Whether stderr is a terminal, in which case a single line is updated in place.
+    is_terminal: bool,
+
+    /// Whether the line is currently drawn on the terminal.
+    drawn: bool,
+
+    /// When the last line was printed, when stderr is not a terminal.
+    printed: Option<time::Instant>,
+}
+impl Progress {
+    /// The heartbeat, if passed `--progress`. Nothing is printed in `--message-format json` mode.
+    pub(crate) fn new(progress: bool) -> Option<Self> {
+        (progress && !util::JSON_MESSAGE_FORMAT.load(sync::atomic::Ordering::Relaxed)).then(|| {
+            Self {
+                is_terminal: io::stderr().is_terminal(),
+                drawn: false,
+                printed: None,
+            }
+        })
+    }
+
+    /// Print the elapsed and remaining time, whether `target` (e.g. the window to locate) has
+    /// been `located`, and REAPER's `pid`.
+    pub(crate) fn update(
+        &mut self,
+        elapsed: time::Duration,
+        timeout: Option<time::Duration>,
+        target: Option<(&str, bool)>,
+        pid: u32,
+    ) {
+        if !self.is_terminal
+            && self
+                .printed
+                .is_some_and(|printed| printed.elapsed() < INTERVAL)
+        {
+            return;
+        }
+        let seconds = |duration: time::Duration| {
+            humantime::format_duration(time::Duration::from_secs(duration.as_secs()))
+        };
+        let mut line = format!(
+            "     {} {} elapsed",
+            "Waiting".green().bold(),
+            seconds(elapsed)
+        );
+        if let Some(timeout) = timeout {
+            line.push_str(&format!(
+                ", {} remaining",
+                seconds(timeout.saturating_sub(elapsed))
+            ));
+        }
+        if let Some((target, located)) = target {
+            line.push_str(&format!(
+                ", {target} {}",
+                if located {
+                    "located"
+                } else {
+                    "not located yet"
+                }
+            ));
+        }
+        line.push_str(&format!(", REAPER pid {pid}"));
+
+        let mut stderr = io::stderr().lock();
+        let _ = if self.is_terminal {
+            self.drawn = true;
+            write!(stderr, "\r\x1b[2K{line}")
+        } else {
+            self.printed = Some(time::Instant::now());
+            writeln!(stderr, "{line}")
+        };
+        let _ = stderr.flush();
+    }
+
+    /// Erase the line from the terminal, so other output starts on a clean line.
+    pub(crate) fn clear(&mut self) {
+        if self.drawn {
+            self.drawn = false;
+            let mut stderr = io::stderr().lock();
+            let _ = write!(stderr, "\r\x1b[2K");
+            let _ = stderr.flush();
+        }
+    }
+}
@@ -0,0 +1,204 @@
+use std::{env, ffi, fs, path, process, time};
+
+use crate::{
+    cli,
+    util::{Colorize, status_println, target_dir},
+};
+
+/// The environment variable the dynamic linker preloads libraries from.
+#[cfg(target_os = "linux")]
+const PRELOAD_ENV: &str = "LD_PRELOAD";
+
+/// The environment variable the dynamic linker preloads libraries from.
+#[cfg(target_os = "macos")]
+const PRELOAD_ENV: &str = "DYLD_INSERT_LIBRARIES";
+
+/// The `rustc` executable Cargo builds with.
+fn rustc() -> ffi::OsString {
+    env::var_os("RUSTC").unwrap_or_else(|| "rustc".into())
+}
+
+/// The output of `rustc` invoked with `args`.
+fn rustc_output(args: &[&str]) -> anyhow::Result<String> {
+    let output = process::Command::new(rustc())
+        .args(args)
+        .output()
+        .map_err(|err| anyhow::anyhow!("failed to run `rustc {}`: {err}", args.join(" ")))?;
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// The host target triple of the active toolchain, or an error if it isn't a nightly toolchain,
+/// which sanitizers require.
+fn nightly_host() -> anyhow::Result<String> {
+    let version = rustc_output(&["-vV"])?;
+    let field = |name: &str| {
+        version
+            .lines()
+            .find_map(|line| line.strip_prefix(name))
+            .map(str::trim)
+            .unwrap_or_default()
+    };
+    let release = field("release:");
+    if !release.contains("nightly") && !release.contains("dev") {
+        anyhow::bail!(
+            "`--sanitizer` requires a nightly toolchain, but the active toolchain is `{release}`. Try `cargo +nightly reaper run`, or `rustup override set nightly`"
+        );
+    }
+    Ok(field("host:").to_string())
+}
+
+/// The arguments passed to `cargo build` to instrument plugin(s) with `sanitizer`. Plugin(s) are
+/// built for an explicit target, so that build scripts and proc-macros are not instrumented.
+pub(crate) fn build_args(
+    sanitizer: cli::Sanitizer,
+    mut args: Vec<String>,
+) -> anyhow::Result<Vec<String>> {
+    let host = nightly_host()?;
+    if env::var_os("RUSTFLAGS").is_some() {
+        status_println!(
+            "{}: `RUSTFLAGS` is set, which takes precedence over the flags `--sanitizer` builds plugin(s) with",
+            "warning".yellow().bold()
+        );
+    }
+    if !args.iter().any(|arg| arg == "--target") && env::var_os("CARGO_BUILD_TARGET").is_none() {
+        args.extend(["--target".to_string(), host]);
+    }
+    let flag = match sanitizer {
+        cli::Sanitizer::Address => "-Zsanitizer=address",
+    };
+    args.extend([
+        "--config".to_string(),
+        format!("build.rustflags=[\"{flag}\"]"),
+    ]);
+    Ok(args)
+}
+
+/// The directory sanitizer reports are written to.
+fn report_dir() -> path::PathBuf {
+    target_dir().join("asan")
+}
+
+/// The shared AddressSanitizer runtime, which must be preloaded into REAPER since it is not
+/// instrumented itself. The active toolchain's runtime is preferred, falling back to the runtimes
+/// of `clang` and `gcc`.
+fn runtime() -> anyhow::Result<path::PathBuf> {
+    let extension = if cfg!(target_os = "macos") {
+        "dylib"
+    } else {
+        "so"
+    };
+    let sysroot = rustc_output(&["--print", "sysroot"])?;
+    let toolchain_runtime = fs::read_dir(
+        path::Path::new(sysroot.trim())
+            .join("lib")
+            .join("rustlib")
+            .join(nightly_host()?)
+            .join("lib"),
+    )
+    .ok()
+    .and_then(|entries| {
+        entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .find(|path| {
+                path.extension().is_some_and(|ext| ext == extension)
+                    && path
+                        .file_name()
+                        .is_some_and(|name| name.to_string_lossy().contains("rt.asan"))
+            })
+    });
+    let compiler_runtime = || {
+        let candidates: &[(&str, String)] = &[
+            (
+                "clang",
+                if cfg!(target_os = "macos") {
+                    "libclang_rt.asan_osx_dynamic.dylib".to_string()
+                } else {
+                    format!("libclang_rt.asan-{}.so", env::consts::ARCH)
+                },
+            ),
+            ("gcc", "libasan.so".to_string()),
+        ];
+        candidates.iter().find_map(|(compiler, file_name)| {
+            let output = process::Command::new(compiler)
+                .arg(format!("-print-file-name={file_name}"))
+                .output()
+                .ok()?;
+            // Compilers print the file name unchanged when they can't find it.
+            let runtime = path::PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+            (runtime.is_absolute() && runtime.is_file()).then_some(runtime)
+        })
+    };
+    toolchain_runtime.or_else(compiler_runtime).ok_or_else(|| {
+        anyhow::anyhow!(
+            "unable to locate the shared AddressSanitizer runtime, which is required by `--sanitizer address`. Install `clang` or `gcc` (e.g. `libasan` on Fedora)"
+        )
+    })
+}
+
+/// The environment variables REAPER is run with to load plugin(s) instrumented with `sanitizer`,
+/// i.e. the preloaded runtime and where reports are written to. Options already set in
+/// `ASAN_OPTIONS` are kept.
+pub(crate) fn env(sanitizer: cli::Sanitizer) -> anyhow::Result<Vec<cli::EnvVar>> {
+    match sanitizer {
+        cli::Sanitizer::Address => {
+            let runtime = runtime()?;
+            let report_dir = report_dir();
+            fs::create_dir_all(&report_dir)?;
+            status_println!(
+                "       {} AddressSanitizer runtime {}",
+                "Using".green().bold(),
+                runtime.display()
+            );
+            let log_path = format!("log_path={}", report_dir.join("asan").display());
+            Ok(vec![
+                cli::EnvVar {
+                    key: PRELOAD_ENV.to_string(),
+                    value: runtime.display().to_string(),
+                },
+                cli::EnvVar {
+                    key: "ASAN_OPTIONS".to_string(),
+                    value: match env::var("ASAN_OPTIONS") {
+                        Ok(options) if !options.is_empty() => format!("{options}:{log_path}"),
+                        _ => log_path,
+                    },
+                },
+            ])
+        }
+    }
+}
+
+/// Summarize the sanitizer reports written since `since`, returning whether there were any.
+pub(crate) fn report(since: time::SystemTime) -> bool {
+    let mut reports = fs::read_dir(report_dir())
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.file_name()
+                        .is_some_and(|name| name.to_string_lossy().starts_with("asan."))
+                        && fs::metadata(path)
+                            .and_then(|metadata| metadata.modified())
+                            .is_ok_and(|modified| modified >= since)
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    reports.sort();
+    for report in &reports {
+        let contents = fs::read_to_string(report).unwrap_or_default();
+        // e.g. `SUMMARY: AddressSanitizer: heap-use-after-free src/lib.rs:42 in my_plugin::run`
+        let summary = contents
+            .lines()
+            .find_map(|line| line.strip_prefix("SUMMARY: "))
+            .or_else(|| {
+                contents
+                    .lines()
+                    .find_map(|line| line.split_once("ERROR: ").map(|(_, error)| error))
+            })
+            .unwrap_or("AddressSanitizer report");
+        eprintln!("{}: {summary} ({})", "error".magenta(), report.display());
+    }
+    !reports.is_empty()
+}
@@ -0,0 +1,142 @@
+use std::{ffi, fs, io, path, process, time};
+
+use crate::util::{Colorize, status_println, target_dir};
+
+/// The program REAPER is run under when passed `--valgrind`.
+pub(crate) const VALGRIND: &str = "valgrind";
+
+/// The status code `valgrind` exits with when it reports errors, which is propagated by
+/// `cargo-reaper`.
+pub(crate) const ERROR_EXIT_CODE: i32 = 42;
+
+/// The command that runs the REAPER executable under `valgrind`, with the user's `valgrind_args`,
+/// writing its logs to Cargo's target directory. Definite leaks are reported as errors unless
+/// `valgrind_args` configure the leak check. REAPER's arguments follow.
+pub(crate) fn command(
+    reaper: &path::Path,
+    valgrind_args: &[String],
+) -> io::Result<process::Command> {
+    let log_dir = target_dir();
+    fs::create_dir_all(&log_dir)?;
+    let valgrind_args = valgrind_args
+        .iter()
+        .flat_map(|valgrind_args| valgrind_args.split_whitespace())
+        .collect::<Vec<_>>();
+    let mut log_file = ffi::OsString::from("--log-file=");
+    log_file.push(log_dir.join("valgrind-%p.log"));
+
+    let mut command = process::Command::new(VALGRIND);
+    command
+        .arg(format!("--error-exitcode={ERROR_EXIT_CODE}"))
+        .arg(log_file);
+    if !valgrind_args
+        .iter()
+        .any(|arg| arg.starts_with("--leak-check"))
+    {
+        command.arg("--leak-check=full");
+    }
+    command.args(valgrind_args).arg(reaper);
+    Ok(command)
+}
+
+/// The errors and definite leaks reported by a `valgrind` log.
+#[derive(Default)]
+struct Summary {
+    /// The number of errors, including definite leaks when they are checked for.
+    errors: u64,
+
+    /// The number of distinct places errors originated from.
+    contexts: u64,
+
+    /// The number of bytes definitely lost.
+    definitely_lost_bytes: u64,
+
+    /// The number of blocks definitely lost.
+    definitely_lost_blocks: u64,
+}
+impl Summary {
+    /// Parse the `ERROR SUMMARY` and `definitely lost` lines of a `valgrind` log, e.g.
+    /// `==4242== ERROR SUMMARY: 3 errors from 2 contexts (suppressed: 0 from 0)`.
+    fn parse(log: &str) -> Self {
+        /// The numbers in `text`, ignoring thousands separators.
+        fn numbers(text: &str) -> Vec<u64> {
+            text.replace(',', "")
+                .split_whitespace()
+                .filter_map(|word| word.parse().ok())
+                .collect()
+        }
+
+        let mut summary = Self::default();
+        for line in log.lines() {
+            if let Some((_, rest)) = line.split_once("ERROR SUMMARY:") {
+                let rest = rest.split_once('(').map_or(rest, |(rest, _)| rest);
+                if let [errors, contexts, ..] = numbers(rest)[..] {
+                    summary.errors = errors;
+                    summary.contexts = contexts;
+                }
+            } else if let Some((_, rest)) = line.split_once("definitely lost:")
+                && let [bytes, blocks, ..] = numbers(rest)[..]
+            {
+                summary.definitely_lost_bytes = bytes;
+                summary.definitely_lost_blocks = blocks;
+            }
+        }
+        summary
+    }
+}
+
+/// Summarize the `valgrind` logs written since `since`, returning whether any reported errors.
+pub(crate) fn report(since: time::SystemTime) -> bool {
+    let log_dir = target_dir();
+    let mut logs = fs::read_dir(&log_dir)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.file_name()
+                        .and_then(|name| name.to_str())
+                        .is_some_and(|name| name.starts_with("valgrind-") && name.ends_with(".log"))
+                        && fs::metadata(path)
+                            .and_then(|metadata| metadata.modified())
+                            .is_ok_and(|modified| modified >= since)
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    logs.sort();
+    if logs.is_empty() {
+        status_println!(
+            "{}: no `valgrind` log was written to '{}'",
+            "warning".yellow().bold(),
+            log_dir.display()
+        );
+        return false;
+    }
+
+    let mut reported_errors = false;
+    for log in logs {
+        let summary = Summary::parse(&fs::read_to_string(&log).unwrap_or_default());
+        let leaks = format!(
+            "{} bytes definitely lost in {} block(s)",
+            summary.definitely_lost_bytes, summary.definitely_lost_blocks
+        );
+        if summary.errors > 0 {
+            reported_errors = true;
+            eprintln!(
+                "{}: `valgrind` reported {} error(s) from {} context(s), {leaks} ({})",
+                "error".magenta(),
+                summary.errors,
+                summary.contexts,
+                log.display()
+            );
+        } else {
+            status_println!(
+                "    {} reported no errors, {leaks} ({})",
+                "Valgrind".green().bold(),
+                log.display()
+            );
+        }
+    }
+    reported_errors
+}
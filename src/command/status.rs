@@ -0,0 +1,203 @@
+use std::{collections, fs, path};
+
+use crate::{
+    config::ReaperPluginConfig,
+    util::{
+        self, Colorize, LinkStatus, TargetOs, find_project_root, newest_artifact, points_into,
+        project_target_dir, status_println,
+    },
+};
+
+/// The plugin(s) of a project that are out of sync, as printed by `cargo reaper status`.
+#[derive(Default)]
+struct Status {
+    /// The number of plugins symlinked to an artifact in Cargo's target directory.
+    up_to_date: usize,
+
+    /// Configured plugins without an artifact in Cargo's target directory.
+    not_built: Vec<String>,
+
+    /// Configured plugins with an artifact that isn't in the `UserPlugins` directory.
+    not_linked: Vec<(String, path::PathBuf)>,
+
+    /// Configured plugins symlinked to an existing file outside of Cargo's target directory.
+    stale: Vec<(String, path::PathBuf)>,
+
+    /// Configured plugins symlinked to a missing file.
+    dangling: Vec<(String, path::PathBuf)>,
+
+    /// Configured plugins installed as a regular file rather than a symlink.
+    copied: Vec<(String, path::PathBuf)>,
+
+    /// Files in the `UserPlugins` directory symlinked to an artifact in Cargo's target directory,
+    /// whose keys are no longer configured.
+    unconfigured: Vec<(String, path::PathBuf)>,
+
+    /// Files in the `UserPlugins` directory symlinked to a missing file in Cargo's target
+    /// directory, whose keys are no longer configured.
+    orphaned: Vec<(String, path::PathBuf)>,
+}
+impl Status {
+    /// The number of plugins that are out of sync.
+    fn out_of_sync(&self) -> usize {
+        self.not_built.len()
+            + self.not_linked.len()
+            + self.stale.len()
+            + self.dangling.len()
+            + self.copied.len()
+            + self.unconfigured.len()
+            + self.orphaned.len()
+    }
+
+    /// Print the out of sync plugins to stdout, grouped by what's wrong with them, along with the
+    /// commands that bring them back in sync.
+    fn print(&self) {
+        /// Print a section of `entries`, if any.
+        fn section(title: &str, hint: &str, entries: impl ExactSizeIterator<Item = String>) {
+            if entries.len() == 0 {
+                return;
+            }
+            println!("{}", title.bold());
+            println!("  ({hint})");
+            for entry in entries {
+                println!("        {}", entry.red());
+            }
+            println!();
+        }
+        let arrow =
+            |(name, target): &(String, path::PathBuf)| format!("{name} -> {}", target.display());
+        let parenthesized =
+            |(name, path): &(String, path::PathBuf)| format!("{name} ({})", path.display());
+
+        section(
+            "Not built:",
+            "use `cargo reaper build` to build and symlink them",
+            self.not_built.iter().cloned(),
+        );
+        section(
+            "Built but not linked:",
+            "use `cargo reaper build`, or `cargo reaper link <PLUGIN_PATH>` to symlink them",
+            self.not_linked.iter().map(parenthesized),
+        );
+        section(
+            "Stale links:",
+            "use `cargo reaper build` to symlink them to the artifacts in the target directory",
+            self.stale.iter().map(arrow),
+        );
+        section(
+            "Dangling links:",
+            "use `cargo reaper clean -p <PLUGIN_KEY>`, then `cargo reaper build` to recreate them",
+            self.dangling.iter().map(arrow),
+        );
+        section(
+            "Copied:",
+            "remove them from the `UserPlugins` directory, so `cargo reaper build` can symlink them",
+            self.copied.iter().map(parenthesized),
+        );
+        section(
+            "No longer configured:",
+            "use `cargo reaper clean --stale` to remove them",
+            self.unconfigured.iter().map(arrow),
+        );
+        section(
+            "Orphaned links:",
+            "use `cargo reaper clean --orphans` to remove them",
+            self.orphaned.iter().map(arrow),
+        );
+    }
+}
+
+/// Print which plugin(s) of the project are out of sync between the configuration, Cargo's target
+/// directory and the `UserPlugins` directory (of the `portable` installation, if any).
+///
+/// The `UserPlugins` directory is scanned once, resolving every symlink, so that the files of
+/// plugins which are no longer configured can be found as well. When passed `--check`, an error is
+/// returned if anything is out of sync.
+pub(crate) fn status(portable: Option<&path::Path>, check: bool) -> anyhow::Result<()> {
+    let project_root = find_project_root()?;
+    let config = ReaperPluginConfig::load(&project_root)?;
+    let target_dir = project_target_dir(&project_root);
+    let user_plugins_dir = util::resource_dir(portable)?.join("UserPlugins");
+    if !user_plugins_dir.is_dir() {
+        status_println!(
+            "{}: the 'UserPlugins' directory '{}' does not exist, launch REAPER to initialize it",
+            "warning".yellow().bold(),
+            user_plugins_dir.display()
+        );
+    }
+
+    let mut installed = fs::read_dir(&user_plugins_dir)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_ok_and(|file_type| !file_type.is_dir()))
+        .map(|entry| {
+            (
+                entry.file_name().to_string_lossy().into_owned(),
+                (entry.path(), LinkStatus::at(&entry.path(), &target_dir)),
+            )
+        })
+        .collect::<collections::BTreeMap<_, _>>();
+
+    let mut status = Status::default();
+    let mut plugin_names = config
+        .extension_plugins()
+        .keys()
+        .map(|plugin_name| plugin_name.as_ref().to_string())
+        .collect::<Vec<_>>();
+    plugin_names.sort();
+    for plugin_name in plugin_names {
+        let file_name = TargetOs::host().add_plugin_ext(&plugin_name);
+        match installed.remove(&file_name) {
+            Some((_, LinkStatus::Linked(_))) => status.up_to_date += 1,
+            Some((plugin_path, LinkStatus::Stale(target))) => {
+                if plugin_path.exists() {
+                    status.stale.push((plugin_name, target));
+                } else {
+                    status.dangling.push((plugin_name, target));
+                }
+            }
+            Some((plugin_path, LinkStatus::Copied)) => {
+                status.copied.push((plugin_name, plugin_path))
+            }
+            Some((_, LinkStatus::NotLinked)) | None => {
+                match newest_artifact(&target_dir, &file_name) {
+                    Some(artifact) => status.not_linked.push((plugin_name, artifact)),
+                    None => status.not_built.push(plugin_name),
+                }
+            }
+        }
+    }
+    for (file_name, (_, link_status)) in installed {
+        match link_status {
+            LinkStatus::Linked(target) => status.unconfigured.push((file_name, target)),
+            LinkStatus::Stale(target) if points_into(&target, &target_dir) => {
+                status.orphaned.push((file_name, target))
+            }
+            _ => {}
+        }
+    }
+
+    println!("{:>12} {}", "Config:".bold(), config.file().display());
+    println!(
+        "{:>12} {}",
+        "UserPlugins:".bold(),
+        user_plugins_dir.display()
+    );
+    println!();
+    status.print();
+    let out_of_sync = status.out_of_sync();
+    println!(
+        "{} plugin(s) up to date, {} out of sync",
+        status.up_to_date,
+        match out_of_sync {
+            0 => "nothing".to_string(),
+            out_of_sync => out_of_sync.to_string(),
+        }
+    );
+
+    if check && out_of_sync > 0 {
+        anyhow::bail!("`cargo reaper status --check` found {out_of_sync} plugin(s) out of sync");
+    }
+    Ok(())
+}
@@ -0,0 +1,169 @@
+use std::{ffi, path};
+
+use crate::{
+    cli::{self, FromArgMatches},
+    command::{
+        build::build,
+        run::{self, RunReason},
+    },
+    config::{ReaperPluginConfig, TestCase},
+    util::{Colorize, find_project_root, status_println},
+};
+
+/// How long REAPER may run for a test case that does not set a `timeout`.
+const DEFAULT_TIMEOUT: &str = "60s";
+
+/// Build REAPER extension plugin(s) once, then run each test case (or only the one named `name`)
+/// in its own REAPER session, returning the status code `cargo-reaper` should exit with.
+///
+/// A test case passes once its window is located, or when its timeout is reached, as long as
+/// none of its assertions failed. On Linux, each test case runs headless.
+pub(crate) fn test(
+    name: Option<String>,
+    reaper: Option<path::PathBuf>,
+    no_build: bool,
+    args: Vec<String>,
+) -> anyhow::Result<i32> {
+    let project_root = find_project_root()?;
+    let config = ReaperPluginConfig::load(&project_root)?;
+    if config.tests().is_empty() {
+        anyhow::bail!(
+            "no test cases found, add a `[[test]]` table to '{}'",
+            config.file().display()
+        );
+    }
+    let tests = match name.as_deref() {
+        Some(name) => {
+            let Some(test) = config.tests().iter().find(|test| test.name == name) else {
+                anyhow::bail!(
+                    "no test case named `{name}`, expected one of: {}",
+                    config
+                        .tests()
+                        .iter()
+                        .map(|test| test.name.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+            };
+            vec![test]
+        }
+        None => config.tests().iter().collect(),
+    };
+
+    if no_build {
+        run::warn_stale_plugins(false, None)?;
+    } else {
+        build(false, false, None, args)?;
+    }
+
+    let mut results = Vec::with_capacity(tests.len());
+    for (index, test) in tests.iter().enumerate() {
+        status_println!(
+            "     {} test case `{}` ({}/{})",
+            "Testing".green().bold(),
+            test.name,
+            index + 1,
+            tests.len()
+        );
+        let outcome = run_args(&project_root, test, reaper.as_deref())
+            .and_then(|args| run::run(args, None))
+            .map_err(|err| {
+                eprintln!("{}: {err:#}", "error".magenta());
+            });
+        if let Ok(outcome) = &outcome
+            && matches!(outcome.reason, RunReason::Interrupted)
+        {
+            return Ok(run::INTERRUPTED_EXIT_CODE);
+        }
+        results.push((test, outcome.map(|outcome| outcome.status)));
+    }
+
+    let failed = results
+        .iter()
+        .filter(|(_, status)| *status != Ok(0))
+        .count();
+    status_println!("\n{}:\n", "Test Results".green().bold());
+    for (test, status) in &results {
+        match status {
+            Ok(0) => status_println!("  {} ... {}", test.name, "ok".green()),
+            Ok(status) => status_println!(
+                "  {} ... {} (status code {status})",
+                test.name,
+                "FAILED".red().bold()
+            ),
+            Err(()) => status_println!(
+                "  {} ... {} (REAPER could not be run)",
+                test.name,
+                "FAILED".red().bold()
+            ),
+        }
+    }
+    status_println!(
+        "\n{} test case(s) passed, {failed} failed",
+        results.len() - failed
+    );
+    Ok(i32::from(failed > 0))
+}
+
+/// The arguments of a `cargo reaper run` invocation that runs `test`, parsed as if passed on the
+/// command line so they are validated in the same way.
+fn run_args(
+    project_root: &path::Path,
+    test: &TestCase,
+    reaper: Option<&path::Path>,
+) -> anyhow::Result<cli::RunArgs> {
+    let mut args = vec![
+        ffi::OsString::from("run"),
+        "--no-build".into(),
+        "--no-staleness-check".into(),
+    ];
+    if cfg!(target_os = "linux") {
+        args.push("--headless".into());
+    }
+    if let Some(reaper) = reaper {
+        args.extend(["--exec".into(), reaper.into()]);
+    }
+    if let Some(project) = test.project.as_deref() {
+        args.extend(["--open".into(), project_root.join(project).into()]);
+    }
+    if let Some(title) = test.locate_window.as_deref() {
+        args.extend(["--locate-window".into(), title.into()]);
+    }
+    for title in &test.fail_on_window {
+        args.extend(["--fail-on-window".into(), title.into()]);
+    }
+    args.extend([
+        "--timeout".into(),
+        test.timeout
+            .map_or_else(
+                || DEFAULT_TIMEOUT.to_string(),
+                |timeout| humantime::format_duration(timeout).to_string(),
+            )
+            .into(),
+        "--timeout-ok".into(),
+    ]);
+    if let Some(plugins) = test.require_plugin_loaded.as_ref() {
+        args.push("--require-plugin-loaded".into());
+        args.extend(plugins.iter().map(ffi::OsString::from));
+    }
+
+    let command = <cli::RunArgs as clap::Args>::augment_args(clap::Command::new("run"));
+    let matches = command.try_get_matches_from(args).map_err(|err| {
+        anyhow::anyhow!(
+            "invalid test case `{}`:\n{}",
+            test.name,
+            err.render().ansi()
+        )
+    })?;
+    let mut run_args = cli::RunArgs::from_arg_matches(&matches)?;
+    // Environment variables configure how REAPER runs, but never what a test case asserts.
+    let is_env =
+        |id: &str| matches.value_source(id) == Some(clap::parser::ValueSource::EnvVariable);
+    if is_env("project") {
+        run_args.project.clear();
+    }
+    if is_env("window_title") {
+        run_args.window_title = None;
+    }
+    Ok(run_args)
+}
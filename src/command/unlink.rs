@@ -0,0 +1,158 @@
+use std::{collections, fs, path};
+
+use crate::{
+    cli::PluginPattern,
+    command::clean::expand_plugin_patterns,
+    config::ReaperPluginConfig,
+    registry,
+    util::{Colorize, TargetOs, find_project_root},
+};
+
+/// Remove the entries of the `UserPlugins` directories matched by `plugins`, by plugin key or by
+/// file name, without touching Cargo's target directory. Plugin keys are those of the
+/// configuration file when run within a project, and the file names of the entries without the
+/// plugin extension of the host, so that plugins which are no longer configured can be unlinked
+/// as well.
+///
+/// Entries that are regular files rather than symlinks, e.g. plugins installed manually, are only
+/// removed when passed `--force`, and are otherwise reported.
+pub(crate) fn unlink(
+    plugins: &[PluginPattern],
+    dry_run: bool,
+    force: bool,
+    user_plugins_dirs: &[path::PathBuf],
+) -> anyhow::Result<()> {
+    let entries = user_plugins_dirs
+        .iter()
+        .flat_map(|user_plugins_dir| fs::read_dir(user_plugins_dir).into_iter().flatten())
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_ok_and(|file_type| !file_type.is_dir()))
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    let mut keys = find_project_root()
+        .and_then(|project_root| ReaperPluginConfig::load(&project_root))
+        .map(|config| {
+            config
+                .extension_plugins()
+                .keys()
+                .map(|plugin_name| plugin_name.as_ref().to_string())
+                .collect::<collections::BTreeSet<_>>()
+        })
+        .unwrap_or_default();
+    let plugin_ext = TargetOs::host().add_plugin_ext("");
+    for file_name in &entries {
+        if let Some(plugin_name) = file_name.strip_suffix(plugin_ext.as_str()) {
+            keys.insert(plugin_name.to_string());
+        }
+        keys.insert(file_name.clone());
+    }
+    let keys = keys.iter().map(String::as_str).collect::<Vec<_>>();
+    let expansions = expand_plugin_patterns(plugins, &keys)?;
+
+    let resolve = |name: &str| {
+        user_plugins_dirs
+            .iter()
+            .flat_map(|user_plugins_dir| {
+                [
+                    user_plugins_dir.join(name),
+                    user_plugins_dir.join(TargetOs::host().add_plugin_ext(name)),
+                ]
+            })
+            .filter(|path| {
+                path.symlink_metadata()
+                    .is_ok_and(|metadata| !metadata.is_dir())
+            })
+            .collect::<Vec<_>>()
+    };
+    let mut paths = collections::BTreeSet::new();
+    let mut not_linked = Vec::new();
+    for (plugin, matched) in &expansions {
+        let resolved = matched
+            .iter()
+            .flat_map(|name| resolve(name))
+            .collect::<Vec<_>>();
+        if resolved.is_empty() {
+            not_linked.push(plugin.to_string());
+        }
+        paths.extend(resolved);
+    }
+    if !not_linked.is_empty() {
+        anyhow::bail!(
+            "The following plugin(s) are not linked in {}: {}",
+            user_plugins_dirs
+                .iter()
+                .map(|user_plugins_dir| format!("'{}'", user_plugins_dir.display()))
+                .collect::<Vec<_>>()
+                .join(", "),
+            not_linked.join(", ")
+        );
+    }
+
+    let mut removed = Vec::with_capacity(paths.len());
+    let (mut symlinks_removed, mut files_removed, mut failures) = (0, 0, 0);
+    for path in &paths {
+        let target = fs::read_link(path).ok();
+        match &target {
+            Some(target) => println!(
+                "    {} {} -> {}",
+                "Removing".magenta().bold(),
+                path.display(),
+                target.display()
+            ),
+            None if force => println!("    {} {}", "Removing".magenta().bold(), path.display()),
+            None => {
+                println!(
+                    "{}: '{}' is a regular file rather than a symlink, pass `--force` to remove it",
+                    "warning".yellow().bold(),
+                    path.display()
+                );
+                continue;
+            }
+        }
+        let outcome = if dry_run {
+            Ok(())
+        } else {
+            fs::remove_file(path)
+        };
+        match outcome {
+            Ok(()) if target.is_some() => symlinks_removed += 1,
+            Ok(()) => files_removed += 1,
+            Err(err) => {
+                failures += 1;
+                eprintln!(
+                    "{}: failed to remove '{}':\n{err:#?}",
+                    "error".magenta(),
+                    path.display()
+                );
+                continue;
+            }
+        }
+        removed.push(path.as_path());
+    }
+    if !dry_run {
+        registry::forget(removed);
+    }
+
+    let mut summary = format!("{symlinks_removed} symlink(s)");
+    if force {
+        summary.push_str(&format!(", {files_removed} file(s)"));
+    }
+    println!(
+        "     {} {summary}",
+        if dry_run {
+            "Summary".green().bold()
+        } else {
+            "Removed".green().bold()
+        },
+    );
+    if dry_run {
+        println!(
+            "{}: no files deleted due to --dry-run",
+            "warning".yellow().bold()
+        );
+    }
+    if failures > 0 {
+        anyhow::bail!("`cargo reaper unlink` failed to remove {failures} item(s)");
+    }
+    Ok(())
+}
@@ -0,0 +1,137 @@
+use std::{fmt, path, sync};
+
+use crate::{
+    cli::ListFormat,
+    command::run::{ReaperSource, resolve_reaper},
+    config::ReaperPluginConfig,
+    util::{self, Colorize, find_project_root},
+};
+
+/// A path resolved by `cargo reaper which`, or the reason it couldn't be resolved.
+type Resolved<T> = Result<T, String>;
+
+/// The paths `cargo reaper which` resolves.
+struct Which {
+    /// The REAPER executable, where it was resolved from, and its version, if it can be detected.
+    reaper: Resolved<(path::PathBuf, ReaperSource, Option<String>)>,
+
+    user_plugins_dir: Resolved<path::PathBuf>,
+    config_file: Resolved<path::PathBuf>,
+}
+impl Which {
+    fn to_json(&self) -> serde_json::Value {
+        /// The `path` or `error` of a resolved `path`, along with the `fields` of the path.
+        fn resolved<T>(
+            resolved: &Resolved<T>,
+            fields: impl FnOnce(&T) -> serde_json::Value,
+        ) -> serde_json::Value {
+            match resolved {
+                Ok(resolved) => fields(resolved),
+                Err(err) => serde_json::json!({ "path": null, "error": err }),
+            }
+        }
+        serde_json::json!({
+            "reaper": resolved(&self.reaper, |(reaper, source, version)| {
+                serde_json::json!({
+                    "path": reaper,
+                    "source": source.name(),
+                    "version": version,
+                })
+            }),
+            "user_plugins_dir": resolved(&self.user_plugins_dir, |user_plugins_dir| {
+                serde_json::json!({
+                    "path": user_plugins_dir,
+                    "exists": user_plugins_dir.is_dir(),
+                })
+            }),
+            "config_file": resolved(&self.config_file, |config_file| {
+                serde_json::json!({ "path": config_file })
+            }),
+        })
+    }
+}
+impl fmt::Display for Which {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        /// Write the `label` of a resolved path, or the reason it couldn't be resolved.
+        fn line<T>(
+            f: &mut fmt::Formatter<'_>,
+            label: &str,
+            resolved: &Resolved<T>,
+            display: impl FnOnce(&T) -> String,
+        ) -> fmt::Result {
+            match resolved {
+                Ok(resolved) => writeln!(f, "{:>12} {}", label.green().bold(), display(resolved)),
+                Err(err) => writeln!(f, "{:>12} {}", label.green().bold(), err.red()),
+            }
+        }
+        line(f, "REAPER:", &self.reaper, |(reaper, source, _)| {
+            format!("{} ({source})", reaper.display())
+        })?;
+        if let Ok((_, _, version)) = &self.reaper {
+            writeln!(
+                f,
+                "{:>12} {}",
+                "Version:".green().bold(),
+                version.as_deref().unwrap_or("unknown")
+            )?;
+        }
+        line(
+            f,
+            "UserPlugins:",
+            &self.user_plugins_dir,
+            |user_plugins_dir| {
+                if user_plugins_dir.is_dir() {
+                    user_plugins_dir.display().to_string()
+                } else {
+                    format!(
+                        "{} ({})",
+                        user_plugins_dir.display(),
+                        "does not exist, launch REAPER to initialize it".yellow()
+                    )
+                }
+            },
+        )?;
+        line(f, "Config:", &self.config_file, |config_file| {
+            config_file.display().to_string()
+        })?;
+        Ok(())
+    }
+}
+
+/// Print the REAPER executable `cargo reaper run` would launch (honoring `--exec` and
+/// `--portable`) along with its version, the `UserPlugins` directory plugin(s) are symlinked to,
+/// and the configuration file of the current project, in `format`. Paths that can't be resolved
+/// are printed along with the reason, rather than returning an error.
+pub(crate) fn which(
+    reaper: Option<path::PathBuf>,
+    portable: Option<&path::Path>,
+    format: ListFormat,
+) -> anyhow::Result<()> {
+    if format == ListFormat::Json {
+        util::JSON_MESSAGE_FORMAT.store(true, sync::atomic::Ordering::Relaxed);
+    }
+    let which = Which {
+        reaper: resolve_reaper(reaper, portable)
+            .map_err(|err| err.to_string())
+            .and_then(|(reaper, source)| {
+                if !reaper.is_file() {
+                    return Err(format!("'{}' ({source}) does not exist", reaper.display()));
+                }
+                let version = util::reaper_version(&reaper).map(|version| version.to_string());
+                Ok((reaper, source, version))
+            }),
+        user_plugins_dir: util::resource_dir(portable)
+            .map(|resource_dir| resource_dir.join("UserPlugins"))
+            .map_err(|err| format!("{err:#}")),
+        config_file: find_project_root()
+            .and_then(|project_root| ReaperPluginConfig::load(&project_root))
+            .map(|config| config.file().clone())
+            .map_err(|err| format!("{err:#}")),
+    };
+
+    match format {
+        ListFormat::Human => print!("{which}"),
+        ListFormat::Json => println!("{}", which.to_json()),
+    }
+    Ok(())
+}
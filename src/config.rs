@@ -1,8 +1,20 @@
-use std::{collections, fs, path};
+use std::{collections, fmt, fs, io::Write, path, process, str, time};
+
+use crate::{
+    cli,
+    util::{Colorize, is_project_root},
+};
 
 /// Acceptable plugin config toml names for renaming and symlinking REAPER extenion plugins built with Rust.
 pub(crate) const CONFIG_FILE_NAMES: &[&str; 2] = &[".reaper.toml", "reaper.toml"];
 
+/// The file name of the user's config file in `cargo-reaper`'s config directory.
+const USER_CONFIG_FILE_NAME: &str = "config.toml";
+
+/// Directories that are never searched for config files by [`ReaperPluginConfig::discover`],
+/// besides hidden directories.
+const DISCOVERY_IGNORED_DIRS: &[&str; 2] = &["target", "node_modules"];
+
 /// The parsed contents of a `reaper.toml` config file.
 #[derive(Debug, serde::Deserialize)]
 pub(crate) struct ReaperPluginConfig {
@@ -16,6 +28,18 @@ pub(crate) struct ReaperPluginConfig {
 
     /// The contents of a deserialized `reaper.toml` config file.
     extension_plugins: collections::HashMap<toml::Spanned<String>, toml::Spanned<path::PathBuf>>,
+
+    /// The test cases run by `cargo reaper test`, i.e. the `[[test]]` tables.
+    #[serde(default, rename = "test")]
+    tests: Vec<TestCase>,
+
+    /// The defaults for `cargo reaper run`, i.e. the `[run]` table.
+    #[serde(default)]
+    run: RunDefaults,
+
+    /// Whether plugin(s) are symlinked by absolute or relative paths, i.e. `symlink_style`.
+    #[serde(default)]
+    symlink_style: SymlinkStyle,
 }
 impl ReaperPluginConfig {
     /// The path to the `reaper.toml` config file.
@@ -23,6 +47,11 @@ impl ReaperPluginConfig {
         &self.file
     }
 
+    /// The project root, i.e. the directory containing the `reaper.toml` config file.
+    pub(crate) fn root(&self) -> &path::Path {
+        self.file.parent().unwrap_or(path::Path::new(""))
+    }
+
     /// The path to the `reaper.toml` config file.
     pub(crate) fn contents(&self) -> &str {
         &self.contents
@@ -35,6 +64,21 @@ impl ReaperPluginConfig {
         &self.extension_plugins
     }
 
+    /// The test cases listed in the config file.
+    pub(crate) fn tests(&self) -> &[TestCase] {
+        &self.tests
+    }
+
+    /// The defaults for `cargo reaper run` listed in the config file.
+    pub(crate) fn run(&self) -> &RunDefaults {
+        &self.run
+    }
+
+    /// How plugin(s) are symlinked to the `UserPlugins` directory, unless passed `--relative`.
+    pub(crate) fn symlink_style(&self) -> SymlinkStyle {
+        self.symlink_style
+    }
+
     /// Locate and deserialize a `reaper.toml` config file.
     pub(crate) fn load(project_root: &path::Path) -> anyhow::Result<Self> {
         let config_file = CONFIG_FILE_NAMES
@@ -53,4 +97,362 @@ impl ReaperPluginConfig {
 
         Ok(config)
     }
+
+    /// Locate and deserialize every config file in `root` and the directories below it, sorted by
+    /// path, e.g. those of the sub-projects of a monorepo. Hidden directories, `target`
+    /// directories and Cargo build directories (which contain a `CACHEDIR.TAG`) are skipped, as
+    /// are projects ignored by git. Directories that can't be read are skipped with a warning.
+    pub(crate) fn discover(root: &path::Path) -> anyhow::Result<Vec<Self>> {
+        let mut project_roots = Vec::new();
+        let mut dirs = vec![root.to_path_buf()];
+        while let Some(dir) = dirs.pop() {
+            if is_project_root(&dir) {
+                project_roots.push(dir.clone());
+            }
+            let entries = match fs::read_dir(&dir) {
+                Ok(entries) => entries,
+                Err(err) if dir == root => {
+                    anyhow::bail!("failed to read directory '{}': {err}", dir.display())
+                }
+                // On stderr, so that `cargo reaper list --quiet` only prints plugin keys.
+                Err(err) => {
+                    eprintln!(
+                        "{}: skipping '{}', which can't be read: {err}",
+                        "warning".yellow().bold(),
+                        dir.display()
+                    );
+                    continue;
+                }
+            };
+            for entry in entries.filter_map(Result::ok) {
+                let file_name = entry.file_name();
+                let file_name = file_name.to_string_lossy();
+                if entry.file_type().is_ok_and(|file_type| file_type.is_dir())
+                    && !file_name.starts_with('.')
+                    && !DISCOVERY_IGNORED_DIRS.contains(&file_name.as_ref())
+                    && !entry.path().join("CACHEDIR.TAG").is_file()
+                {
+                    dirs.push(entry.path());
+                }
+            }
+        }
+        let ignored = git_ignored(root, &project_roots);
+        project_roots.retain(|project_root| !ignored.contains(project_root));
+        project_roots.sort();
+        project_roots
+            .iter()
+            .map(|project_root| {
+                Self::load(project_root).map_err(|err| {
+                    anyhow::anyhow!(
+                        "failed to load the config file in '{}': {err}",
+                        project_root.display()
+                    )
+                })
+            })
+            .collect()
+    }
+}
+
+/// The `dirs` below `root` that are ignored by git, e.g. by a `.gitignore` file, as reported by
+/// `git check-ignore`. Nothing is ignored when `root` isn't within a git repository, or `git`
+/// isn't installed.
+fn git_ignored(root: &path::Path, dirs: &[path::PathBuf]) -> collections::HashSet<path::PathBuf> {
+    let paths = dirs
+        .iter()
+        .filter_map(|dir| dir.strip_prefix(root).ok())
+        .filter(|path| !path.as_os_str().is_empty())
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    if paths.is_empty() {
+        return collections::HashSet::new();
+    }
+    let output = process::Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(["check-ignore", "--stdin", "-z"])
+        .stdin(process::Stdio::piped())
+        .stdout(process::Stdio::piped())
+        .stderr(process::Stdio::null())
+        .spawn()
+        .and_then(|mut git| {
+            if let Some(mut stdin) = git.stdin.take() {
+                for path in &paths {
+                    stdin.write_all(path.as_bytes())?;
+                    stdin.write_all(b"\0")?;
+                }
+            }
+            git.wait_with_output()
+        });
+    // `git check-ignore` exits with 1 when none of the paths are ignored, and 128 on errors.
+    match output {
+        Ok(output) if output.status.code() == Some(0) => output
+            .stdout
+            .split(|byte| *byte == 0)
+            .filter(|path| !path.is_empty())
+            .map(|path| root.join(String::from_utf8_lossy(path).as_ref()))
+            .collect(),
+        _ => collections::HashSet::new(),
+    }
+}
+
+/// How plugin(s) are symlinked to the `UserPlugins` directory, i.e. the `symlink_style` key of a
+/// `reaper.toml` config file.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum SymlinkStyle {
+    /// Symlinks point to the absolute path of the artifact.
+    #[default]
+    Absolute,
+
+    /// Symlinks point to the artifact by a path relative to the `UserPlugins` directory.
+    Relative,
+}
+
+/// The parsed contents of the user's config file, e.g. `~/.config/cargo-reaper/config.toml`,
+/// which holds the settings that aren't specific to a project.
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct UserConfig {
+    /// The templates of `cargo reaper new --template` by name, i.e. the `[templates]` table.
+    #[serde(default)]
+    templates: collections::BTreeMap<String, TemplateSource>,
+}
+impl UserConfig {
+    /// The path to the user's config file, which may not exist.
+    pub(crate) fn file() -> Option<path::PathBuf> {
+        Some(
+            dirs::config_dir()?
+                .join(env!("CARGO_PKG_NAME"))
+                .join(USER_CONFIG_FILE_NAME),
+        )
+    }
+
+    /// Locate and deserialize the user's config file, which is empty if it doesn't exist.
+    pub(crate) fn load() -> anyhow::Result<Self> {
+        let Some(config_file) = Self::file().filter(|config_file| config_file.is_file()) else {
+            return Ok(Self::default());
+        };
+        let config_contents = fs::read_to_string(&config_file).map_err(|err| {
+            anyhow::anyhow!(
+                "failed to read the user config file '{}':\n{err:#?}",
+                config_file.display()
+            )
+        })?;
+        let mut config: Self = toml::from_str(&config_contents).map_err(|err| {
+            anyhow::anyhow!(
+                "failed to load the user config file '{}':\n{err:#?}",
+                config_file.display()
+            )
+        })?;
+
+        // Relative template paths are relative to the config file.
+        let config_dir = config_file.parent().unwrap_or(path::Path::new(""));
+        for source in config.templates.values_mut() {
+            if let TemplateSource::Path { path } = source {
+                *path = config_dir.join(&path);
+            }
+        }
+        Ok(config)
+    }
+
+    /// The templates listed in the config file, sorted by name.
+    pub(crate) fn templates(&self) -> &collections::BTreeMap<String, TemplateSource> {
+        &self.templates
+    }
+}
+
+/// Where a template listed in the `[templates]` table of the user's config file comes from: a
+/// git URL or a local path, or a table with either a `git` key (and optionally `branch` or `rev`)
+/// or a `path` key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum TemplateSource {
+    /// A git repository, checked out at `branch` or `rev`, or else at its default branch.
+    Git {
+        url: String,
+        branch: Option<String>,
+        rev: Option<String>,
+    },
+
+    /// A local directory.
+    Path { path: path::PathBuf },
+}
+impl fmt::Display for TemplateSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Git { url, branch, rev } => {
+                write!(f, "{url}")?;
+                if let Some(branch) = branch {
+                    write!(f, " (branch `{branch}`)")?;
+                }
+                if let Some(rev) = rev {
+                    write!(f, " (rev `{rev}`)")?;
+                }
+                Ok(())
+            }
+            Self::Path { path } => write!(f, "{}", path.display()),
+        }
+    }
+}
+impl<'de> serde::Deserialize<'de> for TemplateSource {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(serde::Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Short(String),
+            Git {
+                git: String,
+                #[serde(default)]
+                branch: Option<String>,
+                #[serde(default)]
+                rev: Option<String>,
+            },
+            Path {
+                path: path::PathBuf,
+            },
+        }
+        Ok(match Repr::deserialize(deserializer)? {
+            // Anything that looks like a URL or an scp-like git address is cloned, e.g.
+            // `https://github.com/acme/template.git` or `git@github.com:acme/template.git`.
+            Repr::Short(source)
+                if source.contains("://")
+                    || source.starts_with("git@")
+                    || source.ends_with(".git") =>
+            {
+                Self::Git {
+                    url: source,
+                    branch: None,
+                    rev: None,
+                }
+            }
+            Repr::Short(source) => Self::Path {
+                path: source.into(),
+            },
+            Repr::Git { git, branch, rev } => {
+                if branch.is_some() && rev.is_some() {
+                    return Err(serde::de::Error::custom(
+                        "a template can't have both a `branch` and a `rev`",
+                    ));
+                }
+                Self::Git {
+                    url: git,
+                    branch,
+                    rev,
+                }
+            }
+            Repr::Path { path } => Self::Path { path },
+        })
+    }
+}
+
+/// A test case run by `cargo reaper test`, i.e. a `[[test]]` table in a `reaper.toml` config file.
+#[derive(Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct TestCase {
+    /// The name of the test case, which runs it on its own when passed to `cargo reaper test`.
+    pub(crate) name: String,
+
+    /// The REAPER project file to open, relative to the project root.
+    pub(crate) project: Option<path::PathBuf>,
+
+    /// The title of a window that must be located for the test case to pass.
+    pub(crate) locate_window: Option<String>,
+
+    /// The titles of windows that fail the test case when located, e.g. error dialogs.
+    #[serde(default)]
+    pub(crate) fail_on_window: Vec<String>,
+
+    /// How long REAPER may run for, in human-readable format (e.g. 10s, 2m).
+    #[serde(default, deserialize_with = "deserialize_duration")]
+    pub(crate) timeout: Option<time::Duration>,
+
+    /// The plugins REAPER's splash log must mention as loaded, or every plugin in the config file
+    /// when empty.
+    pub(crate) require_plugin_loaded: Option<Vec<String>>,
+}
+
+/// The defaults for `cargo reaper run`, i.e. the `[run]` table in a `reaper.toml` config file.
+/// Each key mirrors a flag, which overrides it when passed on the command line.
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct RunDefaults {
+    /// The REAPER executable file path (`--exec`), relative to the project root.
+    pub(crate) exec: Option<path::PathBuf>,
+
+    /// The REAPER project file to open (`--open`), relative to the project root.
+    pub(crate) project: Option<path::PathBuf>,
+
+    /// How long REAPER may run for (`--timeout`), in human-readable format (e.g. 10s, 2m).
+    #[serde(default, deserialize_with = "deserialize_duration")]
+    pub(crate) timeout: Option<time::Duration>,
+
+    /// The title of a window to locate (`--locate-window`).
+    pub(crate) window_title: Option<String>,
+
+    /// The class of a window to locate (`--locate-class`), on Linux.
+    #[cfg_attr(not(target_os = "linux"), expect(dead_code))]
+    pub(crate) window_class: Option<String>,
+
+    /// A marker file to wait for (`--wait-for-file`), relative to the project root.
+    pub(crate) wait_for_file: Option<path::PathBuf>,
+
+    /// The titles of windows that fail the session when located (`--fail-on-window`).
+    pub(crate) fail_on_window: Option<Vec<String>>,
+
+    /// The plugins REAPER's splash log must mention as loaded (`--require-plugin-loaded`), or
+    /// every plugin in the config file when empty.
+    pub(crate) require_plugin_loaded: Option<Vec<String>>,
+
+    /// Whether to run REAPER in a headless environment (`--headless`), on Linux.
+    #[cfg_attr(not(target_os = "linux"), expect(dead_code))]
+    pub(crate) headless: Option<bool>,
+
+    /// The virtual display used for the headless environment (`--display`), on Linux.
+    #[cfg_attr(not(target_os = "linux"), expect(dead_code))]
+    pub(crate) display: Option<String>,
+
+    /// Configuration for the REAPER process’s standard input (stdin) handle (`--stdin`).
+    #[serde(default, deserialize_with = "deserialize_from_str")]
+    pub(crate) stdin: Option<cli::Stdio>,
+
+    /// Configuration for the REAPER process’s standard output (stdout) handle (`--stdout`).
+    #[serde(default, deserialize_with = "deserialize_from_str")]
+    pub(crate) stdout: Option<cli::Stdio>,
+
+    /// Configuration for the REAPER process’s standard error (stderr) handle (`--stderr`).
+    #[serde(default, deserialize_with = "deserialize_from_str")]
+    pub(crate) stderr: Option<cli::Stdio>,
+
+    /// The environment variables set on the REAPER process (`--env`).
+    pub(crate) env: Option<collections::BTreeMap<String, String>>,
+
+    /// The shell commands run before REAPER is launched (`--before`).
+    pub(crate) before: Option<Vec<String>>,
+
+    /// The shell commands run once the session has ended (`--after`).
+    pub(crate) after: Option<Vec<String>>,
+}
+
+/// Deserialize a human-readable duration, e.g. `30s`.
+fn deserialize_duration<'de, D>(deserializer: D) -> Result<Option<time::Duration>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let duration = <String as serde::Deserialize>::deserialize(deserializer)?;
+    humantime::parse_duration(&duration)
+        .map(Some)
+        .map_err(serde::de::Error::custom)
+}
+
+/// Deserialize a value parsed from a string, e.g. a [`cli::Stdio`].
+fn deserialize_from_str<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: str::FromStr,
+    T::Err: fmt::Display,
+{
+    let value = <String as serde::Deserialize>::deserialize(deserializer)?;
+    value.parse().map(Some).map_err(serde::de::Error::custom)
 }
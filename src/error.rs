@@ -21,7 +21,8 @@ where
     /// A collection of file paths and their contents.
     db: files::SimpleFiles<FilePath, FileContents>,
 
-    /// A collection of diagnostic data containing identifiers corresponding to the db.
+    /// A collection of diagnostic data (errors and warnings) containing identifiers corresponding
+    /// to the db.
     errors: Vec<diagnostic::Diagnostic<FileId>>,
 }
 impl<FilePath, FileContents> TomlErrorEmitter<FilePath, FileContents>
@@ -48,7 +49,56 @@ where
         secondary_span: Option<ops::Range<usize>>,
         secondary_msg: Option<impl Into<Message>>,
     ) {
-        let error = diagnostic::Diagnostic::error().with_message(message.into());
+        self.insert(
+            diagnostic::Severity::Error,
+            path,
+            contents,
+            message,
+            primary_span,
+            primary_msg,
+            secondary_span,
+            secondary_msg,
+        )
+    }
+
+    /// Same as [`TomlErrorEmitter::insert_err`], except the diagnostic is a warning which
+    /// is reported, but does not cause [`TomlErrorEmitter::emit`] to exit.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn insert_warning(
+        &mut self,
+        path: FilePath,
+        contents: FileContents,
+        message: impl Into<Message>,
+        primary_span: ops::Range<usize>,
+        primary_msg: Option<impl Into<Message>>,
+        secondary_span: Option<ops::Range<usize>>,
+        secondary_msg: Option<impl Into<Message>>,
+    ) {
+        self.insert(
+            diagnostic::Severity::Warning,
+            path,
+            contents,
+            message,
+            primary_span,
+            primary_msg,
+            secondary_span,
+            secondary_msg,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn insert(
+        &mut self,
+        severity: diagnostic::Severity,
+        path: FilePath,
+        contents: FileContents,
+        message: impl Into<Message>,
+        primary_span: ops::Range<usize>,
+        primary_msg: Option<impl Into<Message>>,
+        secondary_span: Option<ops::Range<usize>>,
+        secondary_msg: Option<impl Into<Message>>,
+    ) {
+        let error = diagnostic::Diagnostic::new(severity).with_message(message.into());
         let mut labels: Vec<diagnostic::Label<usize>> = Vec::with_capacity(2);
         let mut primary_label: diagnostic::Label<usize> = diagnostic::Label::primary(
             self.db.add(path.clone(), contents.clone()),
@@ -70,19 +120,28 @@ where
         self.errors.push(error.with_labels(labels))
     }
 
-    /// Exit with errors, if any.
+    /// Report all diagnostics, exiting if any of them are errors.
     pub(crate) fn emit(self) -> anyhow::Result<()> {
-        if !self.errors.is_empty() {
-            for error in self.errors.iter().rev() {
-                term::emit_to_write_style(
-                    &mut termcolor::StandardStream::stderr(termcolor::ColorChoice::Auto),
-                    &Default::default(),
-                    &self.db,
-                    error,
-                )?;
-            }
+        if self.report()? > 0 {
             process::exit(1);
         }
         Ok(())
     }
+
+    /// Report all diagnostics, returning how many of them are errors.
+    pub(crate) fn report(self) -> anyhow::Result<usize> {
+        for error in self.errors.iter().rev() {
+            term::emit_to_write_style(
+                &mut termcolor::StandardStream::stderr(termcolor::ColorChoice::Auto),
+                &Default::default(),
+                &self.db,
+                error,
+            )?;
+        }
+        Ok(self
+            .errors
+            .iter()
+            .filter(|error| error.severity >= diagnostic::Severity::Error)
+            .count())
+    }
 }
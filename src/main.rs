@@ -1,18 +1,36 @@
-use std::{env, io};
+use std::{env, io, process};
 
 use crate::{
-    cli::{CargoReaperArgs, CargoReaperCommand, CommandFactory, FromArgMatches, TERM_STYLE},
-    command::{build::build, clean::clean, link::link, list::list, new::new, run::run},
-    util::BINARY_NAME,
+    cli::{
+        CargoReaperArgs, CargoReaperCommand, CommandFactory, FromArgMatches, MessageFormat,
+        RunArgs, TERM_STYLE,
+    },
+    command::{
+        build::build,
+        clean::{clean, clean_all_projects, clean_orphans, clean_stale},
+        doctor::doctor,
+        info::info,
+        link::link,
+        list::{list, list_keys},
+        new::{Template, list_templates, new},
+        run::{
+            apply_config_defaults, build_args, dry_run,
+            event::{self, Event},
+            fresh_config, install_config, run, warn_mismatched_symlinks, warn_stale_plugins,
+        },
+        status::status,
+        test::test,
+        unlink::unlink,
+        which::which,
+    },
+    util::{BINARY_NAME, Colorize, status_println},
 };
 
-#[cfg(target_os = "linux")]
-use crate::command::run::run_headless;
-
 pub(crate) mod cli;
 pub(crate) mod command;
 pub(crate) mod config;
 pub(crate) mod error;
+pub(crate) mod registry;
 pub(crate) mod util;
 
 fn main() -> anyhow::Result<()> {
@@ -32,62 +50,208 @@ fn main() -> anyhow::Result<()> {
         ),
     );
 
-    let args = CargoReaperArgs::from_arg_matches(&cmd.clone().get_matches_from(args)).unwrap();
+    let reaper_args = RunArgs::split_reaper_args(&mut args);
+    let matches = cmd.clone().get_matches_from(args);
+    let mut args = CargoReaperArgs::from_arg_matches(&matches).unwrap();
+    let mut config_defaults = Vec::new();
+    if let CargoReaperCommand::Run(run_args) = &mut args.command
+        && let Some(("run", run_matches)) = matches.subcommand()
+    {
+        run_args.reaper_args = reaper_args;
+        config_defaults = apply_config_defaults(run_args, run_matches)?;
+        if let Err(err) = run_args.validate() {
+            let mut cmd = cmd.clone();
+            cmd.build();
+            if let Some(run_cmd) = cmd.find_subcommand_mut("run") {
+                run_cmd
+                    .error(clap::error::ErrorKind::ArgumentConflict, err)
+                    .exit();
+            }
+        }
+    }
 
     match args.command {
-        CargoReaperCommand::New { template, path } => new(template, path),
-        CargoReaperCommand::List => list(),
-        CargoReaperCommand::Build { no_symlink, args } => build(no_symlink, args),
-        CargoReaperCommand::Link { paths } => link(paths),
-        #[cfg(target_os = "linux")]
-        CargoReaperCommand::Run {
+        CargoReaperCommand::New {
+            list_templates: true,
+            ..
+        } => list_templates(),
+        CargoReaperCommand::New {
+            template,
+            template_git,
+            template_path,
+            branch,
+            rev,
+            name,
+            vcs,
+            list_templates: false,
+            path,
+        } => Template::from_args(template, template_git, template_path, branch, rev).and_then(
+            |template| {
+                new(
+                    template,
+                    name,
+                    vcs,
+                    path.expect("`PATH` is required unless passed `--list-templates`"),
+                )
+            },
+        ),
+        CargoReaperCommand::List {
+            pattern,
+            quiet: true,
+            recursive,
+            ..
+        } => list_keys(pattern.as_ref(), recursive),
+        CargoReaperCommand::List {
+            pattern,
+            no_staleness_check,
+            format,
+            profile,
+            check,
+            verbose,
+            quiet: false,
+            recursive,
+        } => list(
+            pattern.as_ref(),
+            no_staleness_check,
+            format,
+            profile,
+            check,
+            verbose,
+            recursive,
+        ),
+        CargoReaperCommand::Info {
+            key,
+            format,
+            profile,
+        } => info(&key, format, profile),
+        CargoReaperCommand::Status { portable, check } => status(portable.as_deref(), check),
+        CargoReaperCommand::Which {
             reaper,
-            project,
-            no_build,
-            headless,
-            display,
-            window_title,
-            keep_going,
-            timeout,
-            stdin,
-            stdout,
-            stderr,
+            portable,
+            format,
+        } => which(reaper, portable.as_deref(), format),
+        CargoReaperCommand::Doctor { reaper, portable } => doctor(reaper, portable),
+        CargoReaperCommand::Build {
+            no_symlink,
+            relative,
+            portable,
             args,
-        } if headless => (!no_build)
-            .then(|| build(false, args))
-            .transpose()
-            .and_then(|_| {
-                run_headless(
-                    reaper,
-                    project,
-                    display,
-                    window_title,
-                    keep_going,
-                    timeout,
-                    stdin,
-                    stdout,
-                    stderr,
-                )
-            }),
-        CargoReaperCommand::Run {
+        } => build(no_symlink, relative, portable.as_deref(), args).map(|_| ()),
+        CargoReaperCommand::Link {
+            paths,
+            plugins,
+            all,
+            strict,
+            profile,
+            recursive,
+            relative,
+            force,
+            skip_validation,
+            to,
+            create,
+        } => link(
+            paths,
+            plugins,
+            all,
+            strict,
+            profile,
+            recursive,
+            relative,
+            force,
+            skip_validation,
+            to,
+            create,
+        ),
+        CargoReaperCommand::Unlink {
+            plugins,
+            dry_run,
+            force,
+            portable,
+            user_plugins_dirs,
+        } => {
+            let user_plugins_dirs =
+                util::selected_user_plugins_dirs(user_plugins_dirs, portable.as_deref())?;
+            unlink(&plugins, dry_run, force, &user_plugins_dirs)
+        }
+        CargoReaperCommand::Run(mut args) => {
+            if args.message_format == MessageFormat::Json {
+                event::enable();
+            }
+            if args.verbose {
+                for default in config_defaults {
+                    status_println!(
+                        "       {} `{default}` from the `[run]` configuration",
+                        "Using".green().bold()
+                    );
+                }
+            }
+            if args.dry_run {
+                let build_args = (!args.no_build).then(|| build_args(&args)).transpose()?;
+                return dry_run(*args, build_args);
+            }
+            let outcome = fresh_config(&mut args).and_then(|fresh_config| {
+                install_config(&args)?;
+                if args.no_build {
+                    warn_stale_plugins(args.no_staleness_check, args.portable.as_deref())
+                } else {
+                    build_args(&args)
+                        .and_then(|build_args| {
+                            build(false, false, args.portable.as_deref(), build_args)
+                        })
+                        .inspect(|_| event::emit(Event::BuildFinished))
+                        .and_then(|artifacts| {
+                            warn_mismatched_symlinks(&artifacts, args.portable.as_deref())
+                        })
+                }
+                .and_then(|_| run(*args, fresh_config))
+            })?;
+            process::exit(outcome.status)
+        }
+        CargoReaperCommand::Test {
+            name,
             reaper,
-            project,
             no_build,
-            timeout,
-            stdin,
-            stdout,
-            stderr,
             args,
-            ..
-        } => (!no_build)
-            .then(|| build(false, args))
-            .transpose()
-            .and_then(|_| run(reaper, project, timeout, stdin, stdout, stderr)),
+        } => process::exit(test(name, reaper, no_build, args)?),
         CargoReaperCommand::Clean {
             plugins,
             dry_run,
             remove_artifacts,
-        } => clean(&plugins, dry_run, remove_artifacts),
+            all_extensions,
+            orphans,
+            all_orphans,
+            stale,
+            all_projects,
+            strict,
+            yes,
+            format,
+            portable,
+            user_plugins_dirs,
+        } => {
+            let user_plugins_dirs =
+                util::selected_user_plugins_dirs(user_plugins_dirs, portable.as_deref())?;
+            let failures = if all_projects {
+                clean_all_projects(dry_run, yes, format)
+            } else if orphans || all_orphans {
+                clean_orphans(all_orphans, dry_run, yes, format, &user_plugins_dirs)
+            } else if stale {
+                clean_stale(dry_run, format, &user_plugins_dirs)
+            } else {
+                clean(
+                    &plugins,
+                    dry_run,
+                    remove_artifacts,
+                    all_extensions,
+                    yes,
+                    format,
+                    &user_plugins_dirs,
+                )
+            }?;
+            if strict && failures > 0 {
+                anyhow::bail!("`cargo reaper clean --strict` failed to remove {failures} item(s)");
+            }
+            Ok(())
+        }
         CargoReaperCommand::Completions { shell } => {
             let bin_name = cmd.get_name().to_string();
             let mut cmd = cmd;
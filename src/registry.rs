@@ -0,0 +1,127 @@
+//! A registry of the symlinks `cargo-reaper` has created, persisted in a state file, so that
+//! symlinks created by old project checkouts, renamed projects, or `cargo reaper link` can be
+//! cleaned up later, see `cargo reaper clean --all-projects`.
+//!
+//! The registry is best-effort: a missing registry is treated as empty, and a corrupt or
+//! unwritable one only results in a warning, in which case commands behave as if it didn't exist.
+
+use std::{fs, path};
+
+use crate::util::{Colorize, find_project_root, status_println};
+
+/// The file name of the registry in `cargo-reaper`'s state directory.
+const REGISTRY_FILE_NAME: &str = "links.toml";
+
+/// A symlink created by `cargo-reaper`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
+pub(crate) struct Link {
+    /// The path of the symlink, e.g. in the `UserPlugins` directory.
+    pub(crate) path: path::PathBuf,
+
+    /// The file the symlink points to.
+    pub(crate) target: path::PathBuf,
+
+    /// The root of the project the symlink was created from, if it was created from within one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) project: Option<path::PathBuf>,
+}
+
+/// The symlinks recorded in the registry file.
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+pub(crate) struct Registry {
+    #[serde(default, rename = "link", skip_serializing_if = "Vec::is_empty")]
+    links: Vec<Link>,
+}
+impl Registry {
+    /// The path of the registry file, e.g. `~/.local/state/cargo-reaper/links.toml`.
+    fn file() -> Option<path::PathBuf> {
+        Some(
+            dirs::state_dir()
+                .or_else(dirs::data_local_dir)?
+                .join(env!("CARGO_PKG_NAME"))
+                .join(REGISTRY_FILE_NAME),
+        )
+    }
+
+    /// Load the registry, which is empty if it doesn't exist yet, or can't be read or parsed.
+    pub(crate) fn load() -> Self {
+        let Some(file) = Self::file() else {
+            return Self::default();
+        };
+        let contents = match fs::read_to_string(&file) {
+            Ok(contents) => contents,
+            Err(_) => return Self::default(),
+        };
+        toml::from_str(&contents).unwrap_or_else(|err| {
+            status_println!(
+                "{}: ignoring the corrupt symlink registry '{}': {}",
+                "warning".yellow().bold(),
+                file.display(),
+                err.message()
+            );
+            Self::default()
+        })
+    }
+
+    /// Write the registry to its file, warning if that fails.
+    fn save(&self) {
+        let Some(file) = Self::file() else {
+            return;
+        };
+        let result = toml::to_string(self)
+            .map_err(anyhow::Error::from)
+            .and_then(|contents| {
+                if let Some(dir) = file.parent() {
+                    fs::create_dir_all(dir)?;
+                }
+                Ok(fs::write(&file, contents)?)
+            });
+        if let Err(err) = result {
+            status_println!(
+                "{}: failed to update the symlink registry '{}': {err}",
+                "warning".yellow().bold(),
+                file.display()
+            );
+        }
+    }
+
+    /// The recorded symlinks, sorted by path.
+    pub(crate) fn links(&self) -> &[Link] {
+        &self.links
+    }
+
+    /// Remove the symlinks at `paths` from the registry and save it, if any of them were
+    /// recorded.
+    pub(crate) fn forget<'a>(&mut self, paths: impl IntoIterator<Item = &'a path::Path>) {
+        let len = self.links.len();
+        for path in paths {
+            self.links.retain(|link| link.path != path);
+        }
+        if self.links.len() != len {
+            self.save();
+        }
+    }
+}
+
+/// Record the symlink at `path` pointing to `target` in the registry, along with the project it
+/// was created from, if any.
+pub(crate) fn record(path: &path::Path, target: &path::Path) {
+    let mut registry = Registry::load();
+    let link = Link {
+        path: path.to_path_buf(),
+        target: target.to_path_buf(),
+        project: find_project_root().ok(),
+    };
+    if registry.links.contains(&link) {
+        return;
+    }
+    registry.links.retain(|recorded| recorded.path != link.path);
+    registry.links.push(link);
+    registry.links.sort();
+    registry.save();
+}
+
+/// Remove the symlinks at `paths` from the registry.
+pub(crate) fn forget<'a>(paths: impl IntoIterator<Item = &'a path::Path>) {
+    Registry::load().forget(paths);
+}
@@ -1,15 +1,56 @@
-use std::{borrow, env, fmt, fs, io, path};
+use std::{borrow, collections, env, fmt, fs, io, ops, path, process, sync, time};
 
 pub(crate) use colored::Colorize;
 
 use crate::{
-    cli::PluginTemplate,
+    cli::{PluginTemplate, ReaperVersion},
+    config::ReaperPluginConfig,
     error::{Message, TomlErrorEmitter},
+    registry,
 };
 
 /// The REAPER executable binary name.
 pub(crate) const BINARY_NAME: &str = "reaper";
 
+/// Directory names that are skipped when searching a plugin's sources for modifications.
+const STALENESS_IGNORED_DIRS: &[&str; 2] = &["target", ".git"];
+
+/// The `reaper-rs` crates which provide the plugin entry-points REAPER expects.
+pub(crate) const REAPER_RS_CRATES: &[&str; 4] = &[
+    "reaper-low",
+    "reaper-medium",
+    "reaper-high",
+    "reaper-macros",
+];
+
+/// Set when `cargo reaper run --message-format json` (or `cargo reaper list --format json`) is
+/// passed, in which case stdout is reserved for JSON and human-readable output is written to
+/// stderr instead.
+pub(crate) static JSON_MESSAGE_FORMAT: sync::atomic::AtomicBool =
+    sync::atomic::AtomicBool::new(false);
+
+/// Like `println!`, but writes to stderr when stdout is reserved for JSON events.
+macro_rules! status_println {
+    ($($arg:tt)*) => {
+        if $crate::util::JSON_MESSAGE_FORMAT.load(::std::sync::atomic::Ordering::Relaxed) {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
+pub(crate) use status_println;
+
+/// The stdio a child process inheriting stdout is given, which is stderr when stdout is reserved
+/// for JSON events.
+pub(crate) fn inherit_stdout() -> process::Stdio {
+    if JSON_MESSAGE_FORMAT.load(sync::atomic::Ordering::Relaxed) {
+        io::stderr().into()
+    } else {
+        process::Stdio::inherit()
+    }
+}
+
 impl PluginTemplate {
     /// The extension plugin template directory
     const EXT: include_dir::Dir<'_> = include_dir::include_dir!("templates/extension");
@@ -35,6 +76,35 @@ pub(crate) struct PluginManifest {
     version: String,
     authors: Vec<String>,
     description: Option<String>,
+    details: ManifestDetails,
+}
+
+/// The manifest information only shown by `cargo reaper list --verbose`, i.e. `{:#}`.
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct ManifestDetails {
+    pub(crate) license: Option<String>,
+    pub(crate) repository: Option<String>,
+    pub(crate) rust_version: Option<String>,
+    pub(crate) crate_types: Vec<String>,
+    pub(crate) lib_name: Option<String>,
+}
+impl ManifestDetails {
+    /// The details of a plugin's `manifest`, once completed from its workspace. Fields inherited
+    /// from a workspace that couldn't be loaded are treated as missing.
+    pub(crate) fn of(manifest: &cargo_toml::Manifest) -> Self {
+        let inherited = |field: Option<&cargo_toml::Inheritable<String>>| {
+            field.and_then(|field| field.get().ok()).cloned()
+        };
+        let package = manifest.package.as_ref();
+        let lib = manifest.lib.as_ref();
+        Self {
+            license: inherited(package.and_then(|package| package.license.as_ref())),
+            repository: inherited(package.and_then(|package| package.repository.as_ref())),
+            rust_version: inherited(package.and_then(|package| package.rust_version.as_ref())),
+            crate_types: lib.map(|lib| lib.crate_type.clone()).unwrap_or_default(),
+            lib_name: lib.and_then(|lib| lib.name.clone()),
+        }
+    }
 }
 impl PluginManifest {
     pub(crate) fn new(
@@ -48,8 +118,50 @@ impl PluginManifest {
             version,
             authors,
             description,
+            details: ManifestDetails::default(),
         }
     }
+
+    /// The plugin's key in the `cargo-reaper` configuration file.
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub(crate) fn with_details(mut self, details: ManifestDetails) -> Self {
+        self.details = details;
+        self
+    }
+
+    /// The plugin's information as printed by `cargo reaper list --format json`, given the name
+    /// of its `package` and its `manifest_path`, along with the file name the plugin is renamed to
+    /// when built for each platform.
+    pub(crate) fn to_json(
+        &self,
+        package: &str,
+        manifest_path: &path::Path,
+        link_status: Option<&LinkStatus>,
+    ) -> serde_json::Value {
+        serde_json::json!({
+            "key": self.name,
+            "package": package,
+            "version": self.version,
+            "authors": self.authors,
+            "description": self.description,
+            "manifest_path": manifest_path,
+            "license": self.details.license,
+            "repository": self.details.repository,
+            "rust_version": self.details.rust_version,
+            "crate_types": self.details.crate_types,
+            "lib_name": self.details.lib_name,
+            "link_status": link_status.map(LinkStatus::name),
+            "link_target": link_status.and_then(LinkStatus::target),
+            "file_names": {
+                "linux": TargetOs::Linux.add_plugin_ext(&self.name),
+                "macos": TargetOs::MacOs.add_plugin_ext(&self.name),
+                "windows": TargetOs::Windows.add_plugin_ext(&self.name),
+            },
+        })
+    }
 }
 impl fmt::Display for PluginManifest {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -57,6 +169,22 @@ impl fmt::Display for PluginManifest {
         if let Some(ref description) = self.description {
             write!(f, " -- {}", description)?;
         }
+        if f.alternate() {
+            let details = &self.details;
+            let crate_types =
+                (!details.crate_types.is_empty()).then(|| details.crate_types.join(", "));
+            for (field, value) in [
+                ("License", details.license.as_ref()),
+                ("Repository", details.repository.as_ref()),
+                ("Rust version", details.rust_version.as_ref()),
+                ("Crate type", crate_types.as_ref()),
+                ("Library name", details.lib_name.as_ref()),
+            ] {
+                if let Some(value) = value {
+                    write!(f, "\n  {field}: {value}")?;
+                }
+            }
+        }
         if !self.authors.is_empty() {
             write!(f, "\n\nAuthored by: {}", self.authors.join(", "))?;
         }
@@ -64,13 +192,64 @@ impl fmt::Display for PluginManifest {
     }
 }
 
+/// The `UserPlugins` directories `cargo reaper build` may have symlinked plugin(s) to: the one of
+/// the default resource path, and the one of the `portable` installation, if any.
+pub(crate) fn user_plugins_dirs(
+    portable: Option<&path::Path>,
+) -> anyhow::Result<Vec<path::PathBuf>> {
+    let mut user_plugins_dirs = vec![os::user_plugins_dir()?];
+    if let Some(portable) = portable
+        && !user_plugins_dirs.contains(&portable.join("UserPlugins"))
+    {
+        user_plugins_dirs.push(portable.join("UserPlugins"));
+    }
+    Ok(user_plugins_dirs)
+}
+
+/// The `UserPlugins` directories passed with `--user-plugins-dir`, or [`user_plugins_dirs`] when
+/// none were passed. Passed directories are canonicalized like `cargo reaper link --to` does, so
+/// that they match the symlinks recorded in the registry.
+pub(crate) fn selected_user_plugins_dirs(
+    user_plugins_dirs: Vec<path::PathBuf>,
+    portable: Option<&path::Path>,
+) -> anyhow::Result<Vec<path::PathBuf>> {
+    if user_plugins_dirs.is_empty() {
+        return self::user_plugins_dirs(portable);
+    }
+    Ok(user_plugins_dirs
+        .into_iter()
+        .map(|dir| fs::canonicalize(&dir).unwrap_or(dir))
+        .collect())
+}
+
+/// The resource path REAPER is run with. This is the portable installation directory whose
+/// `reaper.ini` is passed to `-cfgfile` if any, otherwise the default resource path.
+pub(crate) fn resource_dir(portable: Option<&path::Path>) -> anyhow::Result<path::PathBuf> {
+    portable.map_or_else(os::resource_dir, |portable| Ok(portable.to_path_buf()))
+}
+
+/// Cargo's target directory, i.e. `CARGO_TARGET_DIR`, or the `target` directory of the project.
+#[cfg(unix)]
+pub(crate) fn target_dir() -> path::PathBuf {
+    project_target_dir(&find_project_root().unwrap_or_default())
+}
+
+/// Cargo's target directory for the project in `project_root`, i.e. `CARGO_TARGET_DIR`, resolved
+/// against `project_root` when relative, or its `target` directory.
+pub(crate) fn project_target_dir(project_root: &path::Path) -> path::PathBuf {
+    env::var_os("CARGO_TARGET_DIR")
+        .filter(|target_dir| !target_dir.is_empty())
+        .map_or_else(
+            || project_root.join("target"),
+            |target_dir| project_root.join(target_dir),
+        )
+}
+
 pub(crate) fn find_project_root() -> anyhow::Result<path::PathBuf> {
     let mut current_dir = env::current_dir()?;
 
     loop {
-        if current_dir.join("Cargo.toml").is_file() && current_dir.join(".reaper.toml").is_file()
-            || current_dir.join("reaper.toml").is_file()
-        {
+        if is_project_root(&current_dir) {
             return Ok(current_dir);
         }
 
@@ -84,6 +263,22 @@ pub(crate) fn find_project_root() -> anyhow::Result<path::PathBuf> {
     )
 }
 
+/// Whether `dir` is the root of a `cargo-reaper` project, i.e. it contains a `reaper.toml`, or a
+/// `.reaper.toml` along with a `Cargo.toml`.
+pub(crate) fn is_project_root(dir: &path::Path) -> bool {
+    dir.join("Cargo.toml").is_file() && dir.join(".reaper.toml").is_file()
+        || dir.join("reaper.toml").is_file()
+}
+
+/// Locate the span of a top-level table within a toml document, e.g. the `[dependencies]` header.
+/// Tables without a header, like dotted keys (`lib.name = "..."`), resolve to the span of their
+/// key.
+pub(crate) fn toml_table_span(contents: &str, table: &str) -> Option<ops::Range<usize>> {
+    let document = toml_edit::Document::parse(contents).ok()?;
+    let (key, item) = document.as_table().get_key_value(table)?;
+    item.span().or_else(|| key.span())
+}
+
 /// Processes the reaper config toml and the plugin `Cargo.toml` files, collecting diagnostic errors and returning the plugin's manifest.
 pub(crate) fn validate_plugin(
     emitter: &mut TomlErrorEmitter<String, String>,
@@ -120,6 +315,7 @@ pub(crate) fn validate_plugin(
     );
 
     let lib = manifest.as_ref().lib.as_ref();
+    let lib_span = toml_table_span(manifest_file_content, "lib").unwrap_or_else(|| manifest.span());
 
     if lib.is_none() {
         emitter.insert_err(
@@ -136,13 +332,11 @@ pub(crate) fn validate_plugin(
         );
     }
     if lib.is_some_and(|lib| lib.name.is_none()) {
-        let lib_index = manifest_file_content.find("[lib]").unwrap();
-        let lib = toml::Spanned::new(lib_index..lib_index + 5, manifest.as_ref().lib.as_ref());
         emitter.insert_err(
             manifest_file.to_string_lossy().to_string(),
             manifest_file_content.to_string(),
             format!("`{}` library is unnamed", plugin_name.as_ref()),
-            lib.span(),
+            lib_span.clone(),
             Some("a name is required in order for plugin path resolution during renaming"),
             None,
             Some("help: add `name = \"<...>\"`"),
@@ -153,18 +347,52 @@ pub(crate) fn validate_plugin(
             .iter()
             .any(|crate_type| crate_type == "cdylib")
     }) {
-        let lib_index = manifest_file_content.find("[lib]").unwrap();
-        let lib = toml::Spanned::new(lib_index..lib_index + 5, manifest.as_ref().lib.as_ref());
         emitter.insert_err(
             manifest_file.to_string_lossy().to_string(),
             manifest_file_content.to_string(),
             format!("`{}` is not a dynamic library", plugin_name.as_ref()),
-            lib.span(),
+            lib_span,
             Some("extension plugins must be dynamic libraries to be recognized"),
             None,
             Some("help: add `crate-type = [\"cdylib\"]`"),
         );
     }
+    let manifest_ref = manifest.as_ref();
+    // Renamed dependencies, e.g. `low = { package = "reaper-low", .. }`, count by their package.
+    if !manifest_ref
+        .dependencies
+        .iter()
+        .chain(
+            manifest_ref
+                .target
+                .values()
+                .flat_map(|target| target.dependencies.iter()),
+        )
+        .any(|(name, dependency)| {
+            REAPER_RS_CRATES.contains(&dependency.package().unwrap_or(name.as_str()))
+        })
+    {
+        emitter.insert_warning(
+            manifest_file.to_string_lossy().to_string(),
+            manifest_file_content.to_string(),
+            format!(
+                "`{}` does not depend on any `reaper-rs` crates",
+                plugin_name.as_ref()
+            ),
+            toml_table_span(manifest_file_content, "dependencies")
+                .unwrap_or_else(|| manifest.span()),
+            Some("REAPER will not load a plugin without a `reaper-rs` entry-point"),
+            None,
+            Some(format!(
+                "help: add one of {}, see the templates generated by `cargo reaper new` for an example",
+                REAPER_RS_CRATES
+                    .iter()
+                    .map(|krate| format!("`{krate}`"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        );
+    }
     Ok(manifest)
 }
 
@@ -192,6 +420,14 @@ where
     ))
 }
 
+/// The version of the REAPER executable at `reaper`, detected from the installation it belongs
+/// to, or `None` if it can't be determined. Symlinks (e.g. `/usr/local/bin/reaper`) are resolved
+/// first, so the installation directory is inspected rather than the directory of the link.
+pub(crate) fn reaper_version(reaper: &path::Path) -> Option<ReaperVersion> {
+    let reaper = fs::canonicalize(reaper).unwrap_or_else(|_| reaper.to_path_buf());
+    os::reaper_version(&reaper)?.parse().ok()
+}
+
 /// Rename the resulting extension plugin, returning the new plugin path if it succeeds.
 ///
 /// When `target_triple` is `Some`, the artifact lives in `target/{triple}/{profile}/`;
@@ -209,7 +445,7 @@ pub(crate) fn rename_plugin(
     fs::rename(plugin_path_from, &plugin_path_to)
         .map_err(|err| anyhow::anyhow!("failed to rename plugin: {err:?}"))?;
 
-    println!(
+    status_println!(
         "     {} {} -> {}",
         "Renamed".green().bold(),
         plugin_path_from.display(),
@@ -219,6 +455,70 @@ pub(crate) fn rename_plugin(
     Ok(plugin_path_to)
 }
 
+/// Which entries already in the way of the symlink of a plugin [`_symlink_plugin`] replaces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Replace {
+    /// Replace nothing, as `cargo reaper link` does.
+    Nothing,
+
+    /// Replace symlinks to another file, e.g. a stale build of the plugin, as `cargo reaper build`
+    /// does.
+    Symlinks,
+
+    /// Replace symlinks and regular files, i.e. `cargo reaper link --force`.
+    All,
+}
+
+/// Replace the file at `symlink_path` with a symlink to `target`, created with `symlink`.
+///
+/// On Unix, the symlink is created next to the file and renamed over it, so that the replacement
+/// is atomic. Windows can't rename over a symlink that way, so the file is removed first, retrying
+/// for as long as it is in the way, e.g. while another process still holds on to it.
+fn replace_with_symlink<S>(
+    target: &path::PathBuf,
+    symlink_path: &path::PathBuf,
+    symlink: &S,
+) -> io::Result<()>
+where
+    S: Fn(&path::PathBuf, &path::PathBuf) -> io::Result<()>,
+{
+    cfg_select! {
+        windows => {
+            const ATTEMPTS: u32 = 10;
+            let mut attempt = 1;
+            loop {
+                let outcome = match fs::remove_file(symlink_path) {
+                    Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+                    _ => symlink(target, symlink_path),
+                };
+                match outcome {
+                    Err(err)
+                        if attempt < ATTEMPTS
+                            && matches!(
+                                err.kind(),
+                                io::ErrorKind::AlreadyExists | io::ErrorKind::PermissionDenied
+                            ) =>
+                    {
+                        attempt += 1;
+                        std::thread::sleep(time::Duration::from_millis(100));
+                    }
+                    outcome => return outcome,
+                }
+            }
+        }
+        _ => {
+            let mut temporary_file_name = std::ffi::OsString::from(".");
+            temporary_file_name.push(symlink_path.file_name().unwrap_or_default());
+            temporary_file_name.push(format!(".{}.tmp", process::id()));
+            let temporary_path = symlink_path.with_file_name(temporary_file_name);
+            symlink(target, &temporary_path)?;
+            fs::rename(&temporary_path, symlink_path).inspect_err(|_| {
+                let _ = fs::remove_file(&temporary_path);
+            })
+        }
+    }
+}
+
 /// Symlink the REAPER extension plugin to the `UserPlugins` directory.
 ///
 /// > Note: This function is platform agnostic
@@ -226,13 +526,22 @@ pub(crate) fn rename_plugin(
 /// # Usage
 ///
 /// This is run automatically when running the `cargo reaper build` command, unless passed `--no-symlink`.
+///
+/// When `relative`, the symlink points to the plugin by a path relative to the `UserPlugins`
+/// directory (see [`relative_path`]), so that it survives the directories being mounted elsewhere.
+/// An entry already in the way of the symlink is only replaced as allowed by `replace`, and never
+/// when it is a directory.
+///
+/// Returns whether a symlink was created, rather than already existing.
 pub(crate) fn _symlink_plugin<S>(
     plugin_path: &path::PathBuf,
     user_plugins_dir: &path::Path,
+    relative: bool,
+    replace: Replace,
     symlink_plugin: S,
-) -> anyhow::Result<()>
+) -> anyhow::Result<bool>
 where
-    S: FnOnce(&path::PathBuf, &path::PathBuf) -> io::Result<()>,
+    S: Fn(&path::PathBuf, &path::PathBuf) -> io::Result<()>,
 {
     if !user_plugins_dir.exists() {
         anyhow::bail!(
@@ -246,68 +555,614 @@ where
             plugin_path.display()
         )
     })?);
-    if symlink_path.exists() {
-        let currently_symlinked_plugin_path = fs::read_link(&symlink_path)?;
-        if &currently_symlinked_plugin_path != plugin_path {
-            println!(
-                "{}: removing stale symlink ({})",
-                "warning".yellow().bold(),
-                symlink_path.display()
-            );
-            fs::remove_file(&symlink_path)?;
-        } else {
-            println!(
-                "    {} symbolic link already exists ({})",
-                "Skipping".yellow().bold(),
-                symlink_path.display(),
-            );
-            return Ok(());
+    let (target, plugin_path) = if relative {
+        let plugin_path = fs::canonicalize(plugin_path)?;
+        let target =
+            relative_path(&plugin_path, &fs::canonicalize(user_plugins_dir)?).ok_or_else(|| {
+                anyhow::anyhow!(
+                    "'{}' has no path relative to the 'UserPlugins' directory '{}'",
+                    plugin_path.display(),
+                    user_plugins_dir.display()
+                )
+            })?;
+        (target, plugin_path)
+    } else {
+        (plugin_path.clone(), plugin_path.clone())
+    };
+    match fs::symlink_metadata(&symlink_path) {
+        Err(_) => {
+            // TODO: Sometimes this will still fail with 'AlreadyExists' errors. We should also go ahead and catch them here.
+            symlink_plugin(&target, &symlink_path)
+                .map_err(|err| anyhow::anyhow!("failed to link extension plugin: {err:?}"))?;
+        }
+        Ok(metadata) if metadata.is_dir() => anyhow::bail!(
+            "'{}' is a directory, remove it so that the plugin can be symlinked",
+            symlink_path.display()
+        ),
+        Ok(metadata) if metadata.is_symlink() => {
+            let currently_symlinked_plugin_path = read_link_target(&symlink_path)?;
+            if currently_symlinked_plugin_path == plugin_path
+                && fs::read_link(&symlink_path)? == target
+            {
+                status_println!(
+                    "    {} symbolic link already exists ({})",
+                    "Skipping".yellow().bold(),
+                    symlink_path.display(),
+                );
+                registry::record(&symlink_path, &plugin_path);
+                return Ok(false);
+            }
+            // Unless the symlink points to the plugin already, but by an absolute rather than a
+            // relative path or vice versa, it's in the way.
+            if currently_symlinked_plugin_path != plugin_path {
+                if replace == Replace::Nothing {
+                    anyhow::bail!(
+                        "'{}' is already a symlink to '{}', pass `--force` to replace it",
+                        symlink_path.display(),
+                        currently_symlinked_plugin_path.display()
+                    );
+                }
+                status_println!(
+                    "{}: replacing stale symlink {} -> {}",
+                    "warning".yellow().bold(),
+                    symlink_path.display(),
+                    currently_symlinked_plugin_path.display()
+                );
+            }
+            replace_with_symlink(&target, &symlink_path, &symlink_plugin)
+                .map_err(|err| anyhow::anyhow!("failed to link extension plugin: {err:?}"))?;
+        }
+        Ok(_) => {
+            match replace {
+                Replace::All => status_println!(
+                    "{}: replacing regular file ({})",
+                    "warning".yellow().bold(),
+                    symlink_path.display()
+                ),
+                Replace::Symlinks => anyhow::bail!(
+                    "'{}' is a regular file rather than a symlink, remove it so that the plugin can be symlinked",
+                    symlink_path.display()
+                ),
+                Replace::Nothing => anyhow::bail!(
+                    "'{}' is a regular file rather than a symlink, pass `--force` to replace it",
+                    symlink_path.display()
+                ),
+            }
+            replace_with_symlink(&target, &symlink_path, &symlink_plugin)
+                .map_err(|err| anyhow::anyhow!("failed to link extension plugin: {err:?}"))?;
         }
     }
-
-    // TODO: Sometimes this will still fail with 'AlreadyExists' errors. We should also go ahead and catch them here.
-    symlink_plugin(plugin_path, &symlink_path)
-        .map_err(|err| anyhow::anyhow!("failed to link extension plugin: {err:?}"))?;
-
-    println!(
+    status_println!(
         "     {} symbolic link {} -> {}",
         "Created".green().bold(),
         symlink_path.display(),
-        plugin_path.display()
+        target.display()
     );
+    registry::record(&symlink_path, &plugin_path);
 
-    Ok(())
+    Ok(true)
 }
 
-/// Remove a REAPER extension plugin symlink from the `UserPlugins` directory.
+/// Remove a REAPER extension plugin symlink from each of the `user_plugins_dirs` containing one,
+/// returning the outcome for each of those symlinks. An error is only returned when the symlink is
+/// missing from every directory.
 ///
 /// > Note: This function is platform agnostic
 ///
 /// # Usage
 ///
 /// This is run automatically when running the `cargo reaper clean` command.
-pub(crate) fn _remove_plugin_symlink(
+pub(crate) fn remove_plugin_symlink(
     plugin_name: &str,
     plugin_file_name: &str,
-    user_plugins_dir: &path::Path,
+    user_plugins_dirs: &[path::PathBuf],
     dry_run: bool,
+) -> anyhow::Result<Vec<(path::PathBuf, anyhow::Result<()>)>> {
+    let removals = user_plugins_dirs
+        .iter()
+        .map(|user_plugins_dir| user_plugins_dir.join(plugin_file_name))
+        .filter(|symlink_path| symlink_path.is_symlink())
+        .map(|symlink_path| {
+            let outcome = if dry_run {
+                Ok(())
+            } else {
+                fs::remove_file(&symlink_path).map_err(|err| {
+                    anyhow::anyhow!(
+                        "failed to remove symlink for `{plugin_name}` ({}):\n{err:#?}",
+                        symlink_path.display()
+                    )
+                })
+            };
+            (symlink_path, outcome)
+        })
+        .collect::<Vec<_>>();
+    if !dry_run {
+        registry::forget(
+            removals
+                .iter()
+                .filter(|(_, outcome)| outcome.is_ok())
+                .map(|(symlink_path, _)| symlink_path.as_path()),
+        );
+    }
+    if removals.is_empty() {
+        anyhow::bail!(
+            "{} a symlink for `{}` ({})",
+            match user_plugins_dirs {
+                [user_plugins_dir] => format!("`{}` does not contain", user_plugins_dir.display()),
+                user_plugins_dirs => format!(
+                    "none of {} contain",
+                    user_plugins_dirs
+                        .iter()
+                        .map(|user_plugins_dir| format!("`{}`", user_plugins_dir.display()))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            },
+            plugin_name,
+            plugin_file_name
+        )
+    }
+    Ok(removals)
+}
+
+/// The most recent modification time of any file within `dir`, skipping build artifacts and
+/// version control directories. Entries that can't be read are skipped, since this only decides
+/// whether to print a warning.
+fn newest_source_modification(dir: &path::Path) -> Option<time::SystemTime> {
+    let mut newest: Option<time::SystemTime> = None;
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.filter_map(Result::ok) {
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                if !STALENESS_IGNORED_DIRS
+                    .iter()
+                    .any(|ignored| entry.file_name() == *ignored)
+                {
+                    dirs.push(entry.path());
+                }
+            } else if file_type.is_file()
+                && let Ok(modified) = entry.metadata().and_then(|meta| meta.modified())
+            {
+                newest = newest.max(Some(modified));
+            }
+        }
+    }
+    newest
+}
+
+/// Print a warning for each plugin whose artifact, symlinked to the `UserPlugins` directory,
+/// is older than the newest file in its manifest directory.
+///
+/// > Note: This function is platform agnostic
+///
+/// # Usage
+///
+/// This is run automatically by `cargo reaper list` and `cargo reaper run --no-build`,
+/// unless passed `--no-staleness-check`.
+pub(crate) fn warn_stale_plugins(
+    project_root: &path::Path,
+    config: &ReaperPluginConfig,
+    user_plugins_dir: &path::Path,
 ) -> anyhow::Result<()> {
-    let symlink_path = user_plugins_dir.join(plugin_file_name);
-    if symlink_path.is_symlink() {
-        if !dry_run {
-            fs::remove_file(&symlink_path).map_err(|err| {
-                anyhow::anyhow!("failed to remove symlink for `{plugin_name}`:\n{err:#?}")
-            })?;
+    for (plugin_name, manifest_dir) in config.extension_plugins().iter() {
+        let symlink_path =
+            user_plugins_dir.join(TargetOs::host().add_plugin_ext(plugin_name.as_ref()));
+        if !symlink_path.is_symlink() {
+            continue;
+        }
+        // A dangling symlink has no artifact to compare against.
+        let Ok(artifact_modified) = fs::metadata(&symlink_path).and_then(|meta| meta.modified())
+        else {
+            continue;
+        };
+        let sources_modified =
+            newest_source_modification(&project_root.join(manifest_dir.get_ref()));
+        if sources_modified.is_some_and(|sources_modified| sources_modified > artifact_modified) {
+            status_println!(
+                "{}: `{}` is older than its sources, consider rebuilding ({})",
+                "warning".yellow().bold(),
+                plugin_name.as_ref(),
+                read_link_target(&symlink_path)?.display()
+            );
         }
-        return Ok(());
     }
+    Ok(())
+}
 
-    anyhow::bail!(
-        "`{}` does not contain a symlink for `{}` ({})",
-        user_plugins_dir.display(),
-        plugin_name,
-        plugin_file_name
-    )
+/// Warn when the `UserPlugins` symlink of a plugin doesn't point at `artifact`, in which case
+/// REAPER would load a different build of the plugin, e.g. one built with another profile.
+/// Nothing is checked when the plugin isn't symlinked.
+pub(crate) fn warn_mismatched_symlink(artifact: &path::Path, user_plugins_dir: &path::Path) {
+    let Some(file_name) = artifact.file_name() else {
+        return;
+    };
+    let symlink_path = user_plugins_dir.join(file_name);
+    let Ok(linked_artifact) = read_link_target(&symlink_path) else {
+        return;
+    };
+    let is_same = |linked_artifact: &path::Path| {
+        linked_artifact == artifact
+            || fs::canonicalize(linked_artifact)
+                .ok()
+                .zip(fs::canonicalize(artifact).ok())
+                .is_some_and(|(linked_artifact, artifact)| linked_artifact == artifact)
+    };
+    if !is_same(&linked_artifact) {
+        status_println!(
+            "{}: '{}' is symlinked to '{}', not '{}', so REAPER will load a different build of the plugin",
+            "warning".yellow().bold(),
+            symlink_path.display(),
+            linked_artifact.display(),
+            artifact.display()
+        );
+    }
+}
+
+/// How a plugin is installed in the `UserPlugins` directory, as shown by `cargo reaper list`.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum LinkStatus {
+    /// Symlinked to an artifact in Cargo's target directory.
+    Linked(path::PathBuf),
+
+    /// Symlinked to a missing file, or to a file outside of Cargo's target directory.
+    Stale(path::PathBuf),
+
+    /// A regular file, e.g. a copy of an artifact or a plugin installed by other means.
+    Copied,
+
+    /// Not present at all.
+    NotLinked,
+}
+impl LinkStatus {
+    /// The status of the plugin named `plugin_name` in `user_plugins_dir`, compared against the
+    /// artifacts in `target_dir`.
+    pub(crate) fn of(
+        plugin_name: &str,
+        user_plugins_dir: &path::Path,
+        target_dir: &path::Path,
+    ) -> Self {
+        Self::at(
+            &user_plugins_dir.join(TargetOs::host().add_plugin_ext(plugin_name)),
+            target_dir,
+        )
+    }
+
+    /// The status of the plugin file at `plugin_path`, compared against the artifacts in
+    /// `target_dir`.
+    pub(crate) fn at(plugin_path: &path::Path, target_dir: &path::Path) -> Self {
+        let Ok(linked_artifact) = read_link_target(plugin_path) else {
+            return if plugin_path.is_file() {
+                Self::Copied
+            } else {
+                Self::NotLinked
+            };
+        };
+        let is_built = fs::canonicalize(plugin_path)
+            .ok()
+            .zip(fs::canonicalize(target_dir).ok())
+            .is_some_and(|(artifact, target_dir)| artifact.starts_with(target_dir));
+        if is_built {
+            Self::Linked(linked_artifact)
+        } else {
+            Self::Stale(linked_artifact)
+        }
+    }
+
+    /// The name of the status, e.g. `not-linked`.
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Self::Linked(_) => "linked",
+            Self::Stale(_) => "stale",
+            Self::Copied => "copied",
+            Self::NotLinked => "not-linked",
+        }
+    }
+
+    /// The file the plugin is symlinked to, if any.
+    pub(crate) fn target(&self) -> Option<&path::Path> {
+        match self {
+            Self::Linked(target) | Self::Stale(target) => Some(target),
+            Self::Copied | Self::NotLinked => None,
+        }
+    }
+}
+impl fmt::Display for LinkStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Linked(target) => write!(f, "{} ({})", "linked".green(), target.display()),
+            Self::Stale(target) => write!(f, "{} ({})", "stale".yellow(), target.display()),
+            Self::Copied => write!(f, "{}", "copied".cyan()),
+            Self::NotLinked => write!(f, "{}", "not linked".red()),
+        }
+    }
+}
+
+/// A file size in bytes, in the largest binary unit it is at least one of, e.g. `1.5 MiB`.
+pub(crate) fn format_size(size: u64) -> String {
+    const UNITS: &[&str; 3] = &["KiB", "MiB", "GiB"];
+
+    let mut value = size as f64;
+    let mut unit = None;
+    for next in UNITS {
+        if value < 1024.0 {
+            break;
+        }
+        value /= 1024.0;
+        unit = Some(next);
+    }
+    match unit {
+        Some(unit) => format!("{value:.1} {unit}"),
+        None => format!("{size} B"),
+    }
+}
+
+/// The path `path` is at relative to the directory `base`, e.g. `../../target/release/x.so`,
+/// computed component-wise from absolute, normalized paths. There is none when the paths don't
+/// share a root, e.g. when they're on different Windows drives.
+pub(crate) fn relative_path(path: &path::Path, base: &path::Path) -> Option<path::PathBuf> {
+    let path = path.components().collect::<Vec<_>>();
+    let base = base.components().collect::<Vec<_>>();
+    let common = path
+        .iter()
+        .zip(&base)
+        .take_while(|(path, base)| path == base)
+        .count();
+    if common == 0 {
+        return None;
+    }
+    let relative = base[common..]
+        .iter()
+        .map(|_| path::Component::ParentDir)
+        .chain(path[common..].iter().copied())
+        .collect::<path::PathBuf>();
+    Some(if relative.as_os_str().is_empty() {
+        path::PathBuf::from(".")
+    } else {
+        relative
+    })
+}
+
+/// The file the symlink at `symlink_path` points to. A relative target is resolved against the
+/// (canonicalized) directory containing the symlink, as the operating system does, so that
+/// relative and absolute symlinks can be compared alike.
+pub(crate) fn read_link_target(symlink_path: &path::Path) -> io::Result<path::PathBuf> {
+    let target = fs::read_link(symlink_path)?;
+    if target.is_absolute() {
+        return Ok(target);
+    }
+    let dir = symlink_path.parent().unwrap_or(path::Path::new(""));
+    let mut resolved = fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+    for component in target.components() {
+        match component {
+            path::Component::ParentDir => {
+                resolved.pop();
+            }
+            path::Component::CurDir => {}
+            component => resolved.push(component),
+        }
+    }
+    Ok(resolved)
+}
+
+/// The architecture of the host, i.e. of `cargo-reaper` itself.
+pub(crate) fn host_architecture() -> object::Architecture {
+    match env::consts::ARCH {
+        "x86_64" => object::Architecture::X86_64,
+        "x86" => object::Architecture::I386,
+        "aarch64" => object::Architecture::Aarch64,
+        "arm" => object::Architecture::Arm,
+        _ => object::Architecture::Unknown,
+    }
+}
+
+/// The architecture of the executable or library at `path`, if it can be parsed and targets a
+/// single architecture (i.e. isn't a universal Mach-O binary).
+pub(crate) fn binary_architecture(path: &path::Path) -> Option<object::Architecture> {
+    let data = fs::read(path).ok()?;
+    let architecture = object::Object::architecture(&object::File::parse(&*data).ok()?);
+    (architecture != object::Architecture::Unknown).then_some(architecture)
+}
+
+/// Check that the file at `plugin_path` is a dynamic library REAPER can load on `os`, i.e. an ELF
+/// shared object, a Mach-O dylib or a PE DLL, built for `architecture` (unless unknown). For a
+/// universal Mach-O binary, any of its architectures may match.
+pub(crate) fn validate_library(
+    plugin_path: &path::Path,
+    os: TargetOs,
+    architecture: object::Architecture,
+) -> anyhow::Result<()> {
+    use object::{Object, read::macho::FatArch};
+
+    /// The name of `architecture`, e.g. `x86_64`.
+    fn architecture_name(architecture: object::Architecture) -> String {
+        format!("{architecture:?}").to_lowercase()
+    }
+    /// The name of `format`, e.g. `Mach-O`.
+    fn format_name(format: object::BinaryFormat) -> String {
+        match format {
+            object::BinaryFormat::Elf => "ELF".to_string(),
+            object::BinaryFormat::MachO => "Mach-O".to_string(),
+            object::BinaryFormat::Pe => "PE".to_string(),
+            format => format!("{format:?}"),
+        }
+    }
+
+    let data = fs::read(plugin_path)
+        .map_err(|err| anyhow::anyhow!("failed to read '{}': {err}", plugin_path.display()))?;
+    let expected = format!(
+        "{} dynamic library{}",
+        format_name(os.library_format()),
+        if architecture == object::Architecture::Unknown {
+            String::new()
+        } else {
+            format!(" ({})", architecture_name(architecture))
+        }
+    );
+    let slices =
+        match object::FileKind::parse(&*data) {
+            Ok(object::FileKind::MachOFat32) => object::read::macho::MachOFatFile32::parse(&*data)
+                .map(|fat| {
+                    fat.arches()
+                        .iter()
+                        .filter_map(|arch| arch.data(&*data).ok())
+                        .collect()
+                }),
+            Ok(object::FileKind::MachOFat64) => object::read::macho::MachOFatFile64::parse(&*data)
+                .map(|fat| {
+                    fat.arches()
+                        .iter()
+                        .filter_map(|arch| arch.data(&*data).ok())
+                        .collect()
+                }),
+            Ok(_) => Ok(vec![&*data]),
+            Err(err) => Err(err),
+        };
+    let files = slices
+        .and_then(|slices| {
+            slices
+                .into_iter()
+                .map(object::File::parse)
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|err| {
+            anyhow::anyhow!(
+                "'{}' is not a dynamic library ({err}), expected: {expected}",
+                plugin_path.display()
+            )
+        })?;
+
+    let is_valid = files.iter().any(|file| {
+        file.format() == os.library_format()
+            && file.kind() == object::ObjectKind::Dynamic
+            && (architecture == object::Architecture::Unknown
+                || file.architecture() == architecture)
+    });
+    if !is_valid {
+        let found = files
+            .iter()
+            .map(|file| {
+                format!(
+                    "{} {} ({})",
+                    format_name(file.format()),
+                    match file.kind() {
+                        object::ObjectKind::Dynamic => "dynamic library",
+                        object::ObjectKind::Executable => "executable",
+                        object::ObjectKind::Relocatable => "object file",
+                        object::ObjectKind::Core => "core dump",
+                        _ => "file of unknown kind",
+                    },
+                    architecture_name(file.architecture())
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        anyhow::bail!(
+            "'{}' was found to be: {found}, expected: {expected}",
+            plugin_path.display()
+        );
+    }
+    Ok(())
+}
+
+/// Whether the symlink `target` points into `target_dir`, even if the file it points to no longer
+/// exists.
+pub(crate) fn points_into(target: &path::Path, target_dir: &path::Path) -> bool {
+    target.starts_with(target_dir)
+        || fs::canonicalize(target_dir).is_ok_and(|target_dir| target.starts_with(target_dir))
+}
+
+/// The symlinks of plugins (i.e. named `reaper_*`) in `user_plugins_dir` whose targets no longer
+/// exist, sorted by path, along with their targets. Only the symlinks pointing into `target_dir`
+/// are returned, if any.
+pub(crate) fn orphaned_symlinks(
+    user_plugins_dir: &path::Path,
+    target_dir: Option<&path::Path>,
+) -> Vec<(path::PathBuf, path::PathBuf)> {
+    let mut orphans = fs::read_dir(user_plugins_dir)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("reaper_"))
+        .map(|entry| entry.path())
+        .filter(|symlink_path| !symlink_path.exists())
+        .filter_map(|symlink_path| {
+            let target = read_link_target(&symlink_path).ok()?;
+            target_dir
+                .is_none_or(|target_dir| points_into(&target, target_dir))
+                .then_some((symlink_path, target))
+        })
+        .collect::<Vec<_>>();
+    orphans.sort();
+    orphans
+}
+
+/// The symlinks in `user_plugins_dir` resolving to an artifact in `target_dir` whose file names
+/// aren't any of the `configured` plugin file names, sorted by path, along with their targets.
+pub(crate) fn unconfigured_symlinks(
+    user_plugins_dir: &path::Path,
+    target_dir: &path::Path,
+    configured: &collections::HashSet<String>,
+) -> Vec<(path::PathBuf, path::PathBuf)> {
+    let mut unconfigured = fs::read_dir(user_plugins_dir)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .filter(|entry| !configured.contains(entry.file_name().to_string_lossy().as_ref()))
+        .filter_map(|entry| match LinkStatus::at(&entry.path(), target_dir) {
+            LinkStatus::Linked(target) => Some((entry.path(), target)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    unconfigured.sort();
+    unconfigured
+}
+
+/// Every plugin artifact named `file_name` in the profile directories of `target_dir`, including
+/// those of cross compilation targets (e.g. `target/release` and
+/// `target/x86_64-unknown-linux-gnu/debug`).
+pub(crate) fn plugin_artifacts(target_dir: &path::Path, file_name: &str) -> Vec<path::PathBuf> {
+    let subdirs = |dir: &path::Path| {
+        fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir())
+            .collect::<Vec<_>>()
+    };
+    let mut artifacts = subdirs(target_dir)
+        .into_iter()
+        .flat_map(|dir| {
+            let mut dirs = subdirs(&dir);
+            dirs.push(dir);
+            dirs
+        })
+        .map(|dir| dir.join(file_name))
+        .filter(|artifact| artifact.is_file())
+        .collect::<Vec<_>>();
+    artifacts.sort();
+    artifacts
+}
+
+/// The most recently built plugin artifact named `file_name` in any profile directory of
+/// `target_dir`, see [`plugin_artifacts`].
+pub(crate) fn newest_artifact(target_dir: &path::Path, file_name: &str) -> Option<path::PathBuf> {
+    plugin_artifacts(target_dir, file_name)
+        .into_iter()
+        .filter_map(|artifact| {
+            fs::metadata(&artifact)
+                .and_then(|meta| meta.modified())
+                .ok()
+                .map(|modified| (modified, artifact))
+        })
+        .max_by_key(|(modified, _)| *modified)
+        .map(|(_, artifact)| artifact)
 }
 
 /// Runtime representation of the plugin target operating system.
@@ -322,6 +1177,9 @@ pub(crate) enum TargetOs {
 }
 
 impl TargetOs {
+    /// Every operating system plugins can be built for.
+    pub(crate) const ALL: [Self; 3] = [Self::Linux, Self::MacOs, Self::Windows];
+
     /// Returns the host OS via compile-time macro. Used when no `--target` is present.
     pub(crate) fn host() -> Self {
         cfg_select! {
@@ -355,6 +1213,15 @@ impl TargetOs {
         }
     }
 
+    /// The format of the dynamic libraries of the operating system, e.g. ELF on Linux.
+    pub(crate) fn library_format(&self) -> object::BinaryFormat {
+        match self {
+            Self::Windows => object::BinaryFormat::Pe,
+            Self::Linux => object::BinaryFormat::Elf,
+            Self::MacOs => object::BinaryFormat::MachO,
+        }
+    }
+
     /// Applies the platform-appropriate library filename prefix transformation.
     /// Unix targets prepend `lib`; Windows does not.
     pub(crate) fn plugin_file_name<'a>(&self, lib_name: &'a str) -> borrow::Cow<'a, str> {
@@ -372,7 +1239,10 @@ pub(crate) mod os {
 
     use std::{io, os, path};
 
-    use super::{_locate_global_default, _remove_plugin_symlink, _symlink_plugin};
+    use super::{_locate_global_default, _symlink_plugin, Replace};
+
+    /// The REAPER executable file path relative to a portable installation directory.
+    pub(crate) const PORTABLE_BINARY_PATH: &str = "reaper.exe";
 
     /// The global default REAPER executable file path for `x86_64-windows` (64bit)
     #[cfg(target_arch = "x86_64")]
@@ -393,41 +1263,108 @@ pub(crate) mod os {
         })
     }
 
-    pub(crate) fn symlink_plugin(plugin_path: &path::PathBuf) -> anyhow::Result<()> {
-        _symlink_plugin(
-            plugin_path,
-            &dirs::data_dir()
-                .ok_or_else(|| anyhow::anyhow!("Unable to find 'AppData' directory"))?
-                .join("REAPER")
-                .join("UserPlugins"),
-            |plugin_path, symlink_path| {
-                os::windows::fs::symlink_file(plugin_path, symlink_path).map_err(|err|
-                    if format!("{err:?}").contains("A required privilege is not held by the client.") {
-                        io::Error::new(
-                            io::ErrorKind::PermissionDenied,
-                            "Windows treats symlink creation as a privileged action, therefore this function is likely to fail unless the user makes changes to their system to permit symlink creation. Users can try enabling Developer Mode, granting the SeCreateSymbolicLinkPrivilege privilege, or running the process as an administrator.",
-                        )
-                    } else {
-                        err
-                    }
-                )
+    /// The path to REAPER's resource directory.
+    pub(crate) fn resource_dir() -> anyhow::Result<path::PathBuf> {
+        Ok(dirs::data_dir()
+            .ok_or_else(|| anyhow::anyhow!("Unable to find 'AppData' directory"))?
+            .join("REAPER"))
+    }
+
+    /// The path to REAPER's `UserPlugins` directory.
+    pub(crate) fn user_plugins_dir() -> anyhow::Result<path::PathBuf> {
+        Ok(resource_dir()?.join("UserPlugins"))
+    }
+
+    /// The `ProductVersion` of the REAPER executable at `reaper`, read from its version resource.
+    pub(crate) fn reaper_version(reaper: &path::Path) -> Option<String> {
+        use std::{ffi, os::windows::ffi::OsStrExt, ptr};
+        use windows::{
+            Win32::Storage::FileSystem::{
+                GetFileVersionInfoSizeW, GetFileVersionInfoW, VerQueryValueW,
             },
+            core::PCWSTR,
+        };
+
+        let wide = |text: &ffi::OsStr| text.encode_wide().chain([0]).collect::<Vec<u16>>();
+        let file_name = wide(reaper.as_os_str());
+        let len = unsafe { GetFileVersionInfoSizeW(PCWSTR(file_name.as_ptr()), None) };
+        if len == 0 {
+            return None;
+        }
+        let mut info = vec![0u8; len as usize];
+        unsafe {
+            GetFileVersionInfoW(
+                PCWSTR(file_name.as_ptr()),
+                None,
+                len,
+                info.as_mut_ptr().cast(),
+            )
+        }
+        .ok()?;
+        let query = |sub_block: &str| {
+            let sub_block = wide(ffi::OsStr::new(sub_block));
+            let mut value = ptr::null_mut();
+            let mut value_len = 0u32;
+            // SAFETY: `info` holds the version resource returned by `GetFileVersionInfoW`, and
+            // `value` points into it for `value_len` units when the query succeeds.
+            unsafe {
+                VerQueryValueW(
+                    info.as_ptr().cast(),
+                    PCWSTR(sub_block.as_ptr()),
+                    &mut value,
+                    &mut value_len,
+                )
+            }
+            .as_bool()
+            .then_some((value, value_len))
+            .filter(|(value, value_len)| !value.is_null() && *value_len > 0)
+        };
+        // The first language and code page pair the version resource is translated to.
+        let (translation, _) = query(r"\VarFileInfo\Translation")?;
+        let (language, code_page) = unsafe {
+            let translation = translation.cast::<u16>();
+            (*translation, *translation.add(1))
+        };
+        let (version, version_len) = query(&format!(
+            r"\StringFileInfo\{language:04x}{code_page:04x}\ProductVersion"
+        ))?;
+        let version =
+            unsafe { std::slice::from_raw_parts(version.cast::<u16>(), version_len as usize) };
+        Some(
+            String::from_utf16_lossy(version)
+                .trim_end_matches('\0')
+                .trim()
+                .to_string(),
         )
     }
 
-    pub(crate) fn remove_plugin_symlink(
-        plugin_name: &str,
-        plugin_file_name: &str,
-        dry_run: bool,
-    ) -> anyhow::Result<()> {
-        _remove_plugin_symlink(
-            plugin_name,
-            plugin_file_name,
-            &dirs::data_dir()
-                .ok_or_else(|| anyhow::anyhow!("Unable to find 'AppData' directory"))?
-                .join("REAPER")
-                .join("UserPlugins"),
-            dry_run,
+    /// Create a symlink at `link` pointing to `original`, explaining how to permit symlink
+    /// creation when the user lacks the privilege.
+    pub(crate) fn symlink(original: &path::Path, link: &path::Path) -> io::Result<()> {
+        os::windows::fs::symlink_file(original, link).map_err(|err|
+            if format!("{err:?}").contains("A required privilege is not held by the client.") {
+                io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    "Windows treats symlink creation as a privileged action, therefore this function is likely to fail unless the user makes changes to their system to permit symlink creation. Users can try enabling Developer Mode, granting the SeCreateSymbolicLinkPrivilege privilege, or running the process as an administrator.",
+                )
+            } else {
+                err
+            }
+        )
+    }
+
+    pub(crate) fn symlink_plugin(
+        plugin_path: &path::PathBuf,
+        user_plugins_dir: &path::Path,
+        relative: bool,
+        replace: Replace,
+    ) -> anyhow::Result<bool> {
+        _symlink_plugin(
+            plugin_path,
+            user_plugins_dir,
+            relative,
+            replace,
+            |plugin_path, symlink_path| symlink(plugin_path, symlink_path),
         )
     }
 }
@@ -437,38 +1374,67 @@ pub(crate) mod os {
     //! Operating system specific functionality for handling operations which require knownledge of
     //! either dynamic library file extensions, or interacting with the `UserPlugins` directory.
 
-    use std::{io, os, path};
+    use std::{fs, io, os, path};
 
-    use super::{_locate_global_default, _remove_plugin_symlink, _symlink_plugin, BINARY_NAME};
+    use super::{_locate_global_default, _symlink_plugin, BINARY_NAME, Replace};
+
+    /// The REAPER executable file path relative to a portable installation directory.
+    pub(crate) const PORTABLE_BINARY_PATH: &str = BINARY_NAME;
 
     pub(crate) fn locate_global_default() -> io::Result<path::PathBuf> {
         _locate_global_default(|| which::which_global(BINARY_NAME).ok())
     }
 
-    pub(crate) fn symlink_plugin(plugin_path: &path::PathBuf) -> anyhow::Result<()> {
-        _symlink_plugin(
-            plugin_path,
-            &dirs::config_dir()
-                .ok_or_else(|| anyhow::anyhow!("Unable to find '.config' directory"))?
-                .join("REAPER")
-                .join("UserPlugins"),
-            |plugin_path, symlink_path| os::unix::fs::symlink(plugin_path, symlink_path),
-        )
+    /// The version of the REAPER executable at `reaper`, read from a `reaper_version` file next
+    /// to it, or else from the latest entry of the changelog (`whatsnew.txt`) shipped with it,
+    /// e.g. `v7.03 - October 5 2023`.
+    pub(crate) fn reaper_version(reaper: &path::Path) -> Option<String> {
+        let dir = reaper.parent()?;
+        if let Ok(version) = fs::read_to_string(dir.join("reaper_version"))
+            && !version.trim().is_empty()
+        {
+            return Some(version.trim().to_string());
+        }
+        fs::read_to_string(dir.join("whatsnew.txt"))
+            .ok()?
+            .lines()
+            .filter_map(|line| line.split_whitespace().next())
+            .find(|word| {
+                word.strip_prefix(['v', 'V'])
+                    .is_some_and(|version| version.starts_with(|c: char| c.is_ascii_digit()))
+            })
+            .map(String::from)
     }
 
-    pub(crate) fn remove_plugin_symlink(
-        plugin_name: &str,
-        plugin_file_name: &str,
-        dry_run: bool,
-    ) -> anyhow::Result<()> {
-        _remove_plugin_symlink(
-            plugin_name,
-            plugin_file_name,
-            &dirs::config_dir()
-                .ok_or_else(|| anyhow::anyhow!("Unable to find '.config' directory"))?
-                .join("REAPER")
-                .join("UserPlugins"),
-            dry_run,
+    /// The path to REAPER's resource directory.
+    pub(crate) fn resource_dir() -> anyhow::Result<path::PathBuf> {
+        Ok(dirs::config_dir()
+            .ok_or_else(|| anyhow::anyhow!("Unable to find '.config' directory"))?
+            .join("REAPER"))
+    }
+
+    /// The path to REAPER's `UserPlugins` directory.
+    pub(crate) fn user_plugins_dir() -> anyhow::Result<path::PathBuf> {
+        Ok(resource_dir()?.join("UserPlugins"))
+    }
+
+    /// Create a symlink at `link` pointing to `original`.
+    pub(crate) fn symlink(original: &path::Path, link: &path::Path) -> io::Result<()> {
+        os::unix::fs::symlink(original, link)
+    }
+
+    pub(crate) fn symlink_plugin(
+        plugin_path: &path::PathBuf,
+        user_plugins_dir: &path::Path,
+        relative: bool,
+        replace: Replace,
+    ) -> anyhow::Result<bool> {
+        _symlink_plugin(
+            plugin_path,
+            user_plugins_dir,
+            relative,
+            replace,
+            |plugin_path, symlink_path| symlink(plugin_path, symlink_path),
         )
     }
 }
@@ -478,9 +1444,12 @@ pub(crate) mod os {
     //! Operating system specific functionality for handling operations which require knownledge of
     //! either dynamic library file extensions, or interacting with the `UserPlugins` directory.
 
-    use std::{io, os, path};
+    use std::{fs, io, os, path};
+
+    use super::{_locate_global_default, _symlink_plugin, Replace};
 
-    use super::{_locate_global_default, _remove_plugin_symlink, _symlink_plugin};
+    /// The REAPER executable file path relative to a portable installation directory.
+    pub(crate) const PORTABLE_BINARY_PATH: &str = "REAPER.app/Contents/MacOS/REAPER";
 
     /// The global default REAPER executable file path for `x86_64-darwin` (Intel) and `aarch64-darwin` (Apple Silicon)
     pub(crate) const GLOBAL_DEFAULT_PATH: &str = "/Applications/REAPER.app/Contents/MacOS/REAPER";
@@ -492,34 +1461,49 @@ pub(crate) mod os {
         })
     }
 
-    pub(crate) fn symlink_plugin(plugin_path: &path::PathBuf) -> anyhow::Result<()> {
-        _symlink_plugin(
-            plugin_path,
-            &dirs::home_dir()
-                .ok_or_else(|| anyhow::anyhow!("Unable to find 'Users' directory"))?
-                .join("Library")
-                .join("Application Support")
-                .join("REAPER")
-                .join("UserPlugins"),
-            |plugin_path, symlink_path| os::unix::fs::symlink(plugin_path, symlink_path),
+    /// The version of the REAPER executable at `reaper`, i.e. the `CFBundleShortVersionString`
+    /// of the `Contents/Info.plist` of the application bundle it belongs to.
+    pub(crate) fn reaper_version(reaper: &path::Path) -> Option<String> {
+        let info_plist = fs::read_to_string(reaper.parent()?.parent()?.join("Info.plist")).ok()?;
+        regex::Regex::new(
+            r"<key>CFBundleShortVersionString</key>\s*<string>\s*([^<]*?)\s*</string>",
         )
+        .ok()?
+        .captures(&info_plist)
+        .map(|captures| captures[1].to_string())
     }
 
-    pub(crate) fn remove_plugin_symlink(
-        plugin_name: &str,
-        plugin_file_name: &str,
-        dry_run: bool,
-    ) -> anyhow::Result<()> {
-        _remove_plugin_symlink(
-            plugin_name,
-            plugin_file_name,
-            &dirs::home_dir()
-                .ok_or_else(|| anyhow::anyhow!("Unable to find 'Users' directory"))?
-                .join("Library")
-                .join("Application Support")
-                .join("REAPER")
-                .join("UserPlugins"),
-            dry_run,
+    /// The path to REAPER's resource directory.
+    pub(crate) fn resource_dir() -> anyhow::Result<path::PathBuf> {
+        Ok(dirs::home_dir()
+            .ok_or_else(|| anyhow::anyhow!("Unable to find 'Users' directory"))?
+            .join("Library")
+            .join("Application Support")
+            .join("REAPER"))
+    }
+
+    /// The path to REAPER's `UserPlugins` directory.
+    pub(crate) fn user_plugins_dir() -> anyhow::Result<path::PathBuf> {
+        Ok(resource_dir()?.join("UserPlugins"))
+    }
+
+    /// Create a symlink at `link` pointing to `original`.
+    pub(crate) fn symlink(original: &path::Path, link: &path::Path) -> io::Result<()> {
+        os::unix::fs::symlink(original, link)
+    }
+
+    pub(crate) fn symlink_plugin(
+        plugin_path: &path::PathBuf,
+        user_plugins_dir: &path::Path,
+        relative: bool,
+        replace: Replace,
+    ) -> anyhow::Result<bool> {
+        _symlink_plugin(
+            plugin_path,
+            user_plugins_dir,
+            relative,
+            replace,
+            |plugin_path, symlink_path| symlink(plugin_path, symlink_path),
         )
     }
 }
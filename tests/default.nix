@@ -76,6 +76,22 @@
     corro.succeed("cargo_reaper_dry_run \"${plugin_name} error\"")
   '';
 
+  # Copy plugin source code into a directory along with the pre-built plugin in its
+  # target directory, link it by key using `cargo-reaper link --plugin` and assert the
+  # symbolic link exists in the `UserPlugins` directory. Linking a key without an
+  # artifact, or that isn't configured, must fail.
+  test-cargo-reaper-link-plugin = { plugin, plugin_source, plugin_name }: ''
+    corro.start()
+    corro.wait_for_unit("multi-user.target")
+    corro.succeed("su - corro -c '${cargo-reaper}/bin/cargo-reaper run --no-build --headless --timeout 5s --stdout null --stderr null'");
+    corro.succeed("su - root -c 'cp -r ${plugin_source}/* /home/corro/'")
+    corro.fail("su - corro -c '${cargo-reaper}/bin/cargo-reaper link --plugin ${plugin_name}'")
+    corro.succeed("su - root -c 'mkdir -p /home/corro/target/release && cp ${plugin}/lib/${plugin_name}.* /home/corro/target/release/'")
+    corro.succeed("su - corro -c '${cargo-reaper}/bin/cargo-reaper link --plugin ${plugin_name}'")
+    corro.succeed("su - corro -c 'test -e ~/.config/REAPER/UserPlugins/${plugin_name}.*'")
+    corro.fail("su - corro -c '${cargo-reaper}/bin/cargo-reaper link --plugin reaper_not_configured'")
+  '';
+
   # Link the pre-built plugin using `cargo-reaper link` and
   # assert the symbolic link exists in the `UserPlugins` directory.
   # Copy plugin source code into a directory and run `cargo-reaper clean`,
@@ -90,4 +106,91 @@
     corro.succeed("su - corro -c '${cargo-reaper}/bin/cargo-reaper clean -p ${plugin_name}'")
     corro.fail("su - corro -c 'test -e ~/.config/REAPER/UserPlugins/${plugin_name}.*'")
   '';
+
+  # Link the pre-built plugin using `cargo-reaper link`, then remove it by key using
+  # `cargo-reaper unlink` and assert the symbolic link no longer exists in the `UserPlugins`
+  # directory, while the artifact it pointed to does. A regular file is only removed with `--force`.
+  test-cargo-reaper-unlink = { plugin, plugin_source, plugin_name }: ''
+    corro.start()
+    corro.wait_for_unit("multi-user.target")
+    corro.succeed("su - corro -c '${cargo-reaper}/bin/cargo-reaper run --no-build --headless --timeout 5s --stdout null --stderr null'");
+    corro.succeed("su - corro -c '${cargo-reaper}/bin/cargo-reaper link ${plugin}/lib/${plugin_name}.*'")
+    corro.succeed("su - root -c 'cp -r ${plugin_source}/* /home/corro/'")
+    corro.succeed("su - corro -c '${cargo-reaper}/bin/cargo-reaper unlink --dry-run ${plugin_name}'")
+    corro.succeed("su - corro -c 'test -e ~/.config/REAPER/UserPlugins/${plugin_name}.*'")
+    corro.succeed("su - corro -c '${cargo-reaper}/bin/cargo-reaper unlink ${plugin_name}'")
+    corro.fail("su - corro -c 'test -e ~/.config/REAPER/UserPlugins/${plugin_name}.*'")
+    corro.succeed("test -e ${plugin}/lib/${plugin_name}.*")
+    corro.fail("su - corro -c '${cargo-reaper}/bin/cargo-reaper unlink ${plugin_name}'")
+    corro.succeed("su - corro -c 'cp ${plugin}/lib/${plugin_name}.* ~/.config/REAPER/UserPlugins/'")
+    corro.succeed("su - corro -c '${cargo-reaper}/bin/cargo-reaper unlink ${plugin_name}'")
+    corro.succeed("su - corro -c 'test -e ~/.config/REAPER/UserPlugins/${plugin_name}.*'")
+    corro.succeed("su - corro -c '${cargo-reaper}/bin/cargo-reaper unlink --force ${plugin_name}'")
+    corro.fail("su - corro -c 'test -e ~/.config/REAPER/UserPlugins/${plugin_name}.*'")
+  '';
+
+  # Link the pre-built plugin using `cargo-reaper link --relative` and assert the symbolic
+  # link exists in the `UserPlugins` directory, and that its target is a relative path.
+  test-cargo-reaper-link-relative = { plugin, plugin_name }: ''
+    corro.start()
+    corro.wait_for_unit("multi-user.target")
+    corro.succeed("su - corro -c '${cargo-reaper}/bin/cargo-reaper run --no-build --headless --timeout 5s --stdout null --stderr null'");
+    corro.succeed("su - corro -c '${cargo-reaper}/bin/cargo-reaper link --relative ${plugin}/lib/${plugin_name}.*'")
+    corro.succeed("su - corro -c 'test -e ~/.config/REAPER/UserPlugins/${plugin_name}.*'")
+    corro.succeed("su - corro -c 'readlink ~/.config/REAPER/UserPlugins/${plugin_name}.* | grep -v ^/'")
+  '';
+
+  # Copy the pre-built plugin to the `UserPlugins` directory, assert `cargo-reaper link`
+  # leaves the copy in place, then replace it with a symbolic link using `cargo-reaper link --force`.
+  test-cargo-reaper-link-force = { plugin, plugin_name }: ''
+    corro.start()
+    corro.wait_for_unit("multi-user.target")
+    corro.succeed("su - corro -c '${cargo-reaper}/bin/cargo-reaper run --no-build --headless --timeout 5s --stdout null --stderr null'");
+    corro.succeed("su - corro -c 'cp ${plugin}/lib/${plugin_name}.* ~/.config/REAPER/UserPlugins/'")
+    corro.succeed("su - corro -c '${cargo-reaper}/bin/cargo-reaper link ${plugin}/lib/${plugin_name}.*'")
+    corro.fail("su - corro -c 'test -L ~/.config/REAPER/UserPlugins/${plugin_name}.*'")
+    corro.succeed("su - corro -c '${cargo-reaper}/bin/cargo-reaper link --force ${plugin}/lib/${plugin_name}.*'")
+    corro.succeed("su - corro -c 'test -L ~/.config/REAPER/UserPlugins/${plugin_name}.*'")
+  '';
+
+  # Link the directory of the pre-built plugin using `cargo-reaper link` and assert the
+  # symbolic link exists in the `UserPlugins` directory. Linking a directory without
+  # any plugin file must fail.
+  test-cargo-reaper-link-directory = { plugin, plugin_name }: ''
+    corro.start()
+    corro.wait_for_unit("multi-user.target")
+    corro.succeed("su - corro -c '${cargo-reaper}/bin/cargo-reaper run --no-build --headless --timeout 5s --stdout null --stderr null'");
+    corro.succeed("su - corro -c '${cargo-reaper}/bin/cargo-reaper link ${plugin}/lib'")
+    corro.succeed("su - corro -c 'test -e ~/.config/REAPER/UserPlugins/${plugin_name}.*'")
+    corro.succeed("su - corro -c 'mkdir -p ~/empty'")
+    corro.fail("su - corro -c '${cargo-reaper}/bin/cargo-reaper link ~/empty'")
+  '';
+
+  # Link a text file named like a plugin using `cargo-reaper link` and assert no symbolic
+  # link was created in the `UserPlugins` directory, unless passed `--skip-validation`.
+  test-cargo-reaper-link-validation = ''
+    corro.start()
+    corro.wait_for_unit("multi-user.target")
+    corro.succeed("su - corro -c '${cargo-reaper}/bin/cargo-reaper run --no-build --headless --timeout 5s --stdout null --stderr null'");
+    corro.succeed("su - corro -c 'echo not a library > ~/reaper_text.so'")
+    corro.succeed("su - corro -c '${cargo-reaper}/bin/cargo-reaper link ~/reaper_text.so'")
+    corro.fail("su - corro -c 'test -e ~/.config/REAPER/UserPlugins/reaper_text.so'")
+    corro.succeed("su - corro -c '${cargo-reaper}/bin/cargo-reaper link --skip-validation ~/reaper_text.so'")
+    corro.succeed("su - corro -c 'test -e ~/.config/REAPER/UserPlugins/reaper_text.so'")
+  '';
+
+  # Copy plugin source code into a directory and run `cargo-reaper link --all --strict`,
+  # which must fail without any artifact. Copy the pre-built plugin into the target
+  # directory, then assert `cargo-reaper link --all --strict` symlinks it.
+  test-cargo-reaper-link-all = { plugin, plugin_source, plugin_name }: ''
+    corro.start()
+    corro.wait_for_unit("multi-user.target")
+    corro.succeed("su - corro -c '${cargo-reaper}/bin/cargo-reaper run --no-build --headless --timeout 5s --stdout null --stderr null'");
+    corro.succeed("su - root -c 'cp -r ${plugin_source}/* /home/corro/'")
+    corro.succeed("su - corro -c '${cargo-reaper}/bin/cargo-reaper link --all'")
+    corro.fail("su - corro -c '${cargo-reaper}/bin/cargo-reaper link --all --strict'")
+    corro.succeed("su - root -c 'mkdir -p /home/corro/target/debug && cp ${plugin}/lib/${plugin_name}.* /home/corro/target/debug/'")
+    corro.succeed("su - corro -c '${cargo-reaper}/bin/cargo-reaper link --all --strict'")
+    corro.succeed("su - corro -c 'test -e ~/.config/REAPER/UserPlugins/${plugin_name}.*'")
+  '';
 }
@@ -0,0 +1 @@
+//! A fixture for `cargo reaper list --recursive`, which is never built.